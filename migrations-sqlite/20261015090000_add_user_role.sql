@@ -0,0 +1,2 @@
+-- SQLite migration adding the user role
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user';
@@ -0,0 +1,2 @@
+-- SQLite migration adding last login tracking
+ALTER TABLE users ADD COLUMN last_login_at TEXT;
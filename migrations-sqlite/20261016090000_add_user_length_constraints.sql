@@ -0,0 +1,16 @@
+-- SQLite migration adding username and email length limits
+-- Back up the domain's username and email length limits in the schema
+-- SQLite can't add a CHECK to an existing table, so triggers enforce the limits instead
+CREATE TRIGGER users_length_insert
+BEFORE INSERT ON users
+WHEN length(NEW.username) > 32 OR length(NEW.email) > 254
+BEGIN
+    SELECT RAISE(ABORT, 'users_length: username or email is too long');
+END;
+
+CREATE TRIGGER users_length_update
+BEFORE UPDATE OF username, email ON users
+WHEN length(NEW.username) > 32 OR length(NEW.email) > 254
+BEGIN
+    SELECT RAISE(ABORT, 'users_length: username or email is too long');
+END;
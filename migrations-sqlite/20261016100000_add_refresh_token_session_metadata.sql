@@ -0,0 +1,3 @@
+-- SQLite migration for session metadata on refresh tokens
+ALTER TABLE refresh_tokens ADD COLUMN user_agent TEXT;
+ALTER TABLE refresh_tokens ADD COLUMN last_used_at TEXT;
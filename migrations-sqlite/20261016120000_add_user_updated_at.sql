@@ -0,0 +1,4 @@
+-- SQLite migration tracking when a user row last changed
+-- ALTER TABLE cannot add a column with a non-constant default, so inserts set it explicitly
+ALTER TABLE users ADD COLUMN updated_at TEXT;
+UPDATE users SET updated_at = created_at;
@@ -0,0 +1,3 @@
+-- SQLite migration making usernames unique regardless of case
+-- Fails if such duplicates already exist, they have to be renamed by hand first
+CREATE UNIQUE INDEX idx_users_username_nocase ON users (username COLLATE NOCASE);
@@ -0,0 +1,8 @@
+-- SQLite migration for single-use registration invite codes
+CREATE TABLE invite_codes (
+    code_hash TEXT PRIMARY KEY,
+    created_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+    used_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+    used_at TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- SQLite migration for the append-only audit log
+-- No foreign keys: entries outlive the users they mention
+CREATE TABLE audit_log (
+    id TEXT PRIMARY KEY,
+    actor_id TEXT,
+    action TEXT NOT NULL,
+    target_id TEXT,
+    ip TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_audit_log_created_at ON audit_log(created_at);
@@ -0,0 +1,7 @@
+-- SQLite migration for rate limit counters
+-- One fixed window per key, shared by every server instance
+CREATE TABLE rate_limits (
+    key TEXT PRIMARY KEY,
+    window_started_at TEXT NOT NULL,
+    count INTEGER NOT NULL
+);
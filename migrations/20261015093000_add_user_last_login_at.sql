@@ -0,0 +1,2 @@
+-- up
+ALTER TABLE users ADD COLUMN last_login_at TIMESTAMP;
@@ -0,0 +1,5 @@
+-- up
+-- Back up the domain's username and email length limits in the schema
+-- NOT VALID enforces the username limit on new writes without rejecting older, longer rows
+ALTER TABLE users ALTER COLUMN email TYPE VARCHAR(254);
+ALTER TABLE users ADD CONSTRAINT users_username_length CHECK (char_length(username) <= 32) NOT VALID;
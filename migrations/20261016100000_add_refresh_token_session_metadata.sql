@@ -0,0 +1,3 @@
+-- up
+ALTER TABLE refresh_tokens ADD COLUMN user_agent VARCHAR(255);
+ALTER TABLE refresh_tokens ADD COLUMN last_used_at TIMESTAMP;
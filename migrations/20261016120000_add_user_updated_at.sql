@@ -0,0 +1,6 @@
+-- up
+ALTER TABLE users ADD COLUMN updated_at TIMESTAMP;
+UPDATE users SET updated_at = COALESCE(created_at, CURRENT_TIMESTAMP);
+ALTER TABLE users
+    ALTER COLUMN updated_at SET DEFAULT CURRENT_TIMESTAMP,
+    ALTER COLUMN updated_at SET NOT NULL;
@@ -0,0 +1,4 @@
+-- up
+-- Usernames differing only in case would let one account impersonate another
+-- Fails if such duplicates already exist, they have to be renamed by hand first
+CREATE UNIQUE INDEX idx_users_username_lower ON users (LOWER(username));
@@ -0,0 +1,12 @@
+-- up
+-- Append-only, so no foreign keys: entries outlive the users they mention
+CREATE TABLE audit_log (
+    id UUID PRIMARY KEY,
+    actor_id UUID,
+    action VARCHAR(32) NOT NULL,
+    target_id UUID,
+    ip VARCHAR(45),
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_audit_log_created_at ON audit_log(created_at);
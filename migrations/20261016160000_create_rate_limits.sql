@@ -0,0 +1,7 @@
+-- up
+-- One fixed window per key, shared by every server instance
+CREATE TABLE rate_limits (
+    key VARCHAR(255) PRIMARY KEY,
+    window_started_at TIMESTAMP NOT NULL,
+    count INTEGER NOT NULL
+);
@@ -1,18 +1,236 @@
+use serde::Serialize;
 use thiserror::Error;
 
+use crate::persistence::is_sqlite_busy;
+
+/// A problem with one field of a request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
 #[derive(Error, Debug)]
 pub enum AppError {
+    /// Keeps the original error so logs show the full source chain
     #[error("Database error: {0}")]
-    Database(String),
+    Database(#[source] sqlx::Error),
+
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    /// Every invalid field of a request, reported together
+    #[error("Validation failed: {0:?}")]
+    InvalidFields(Vec<FieldError>),
 
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// Well-formed input the server refuses to act on
+    #[error("Unprocessable: {0}")]
+    Unprocessable(String),
+
+    #[error("Method not allowed")]
+    MethodNotAllowed,
+
+    /// The request has to be repeated over a secure connection
+    #[error("Upgrade required: {0}")]
+    UpgradeRequired(String),
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
+impl AppError {
+    /// A validation error pinned to one field
+    pub fn validation(field: &'static str, message: impl Into<String>) -> Self {
+        Self::validations(vec![FieldError {
+            field,
+            message: message.into(),
+        }])
+    }
+
+    /// Validation errors for several fields at once
+    pub fn validations(fields: Vec<FieldError>) -> Self {
+        AppError::InvalidFields(fields)
+    }
+}
+
+/// Postgres `string_data_right_truncation`, raised when a value is longer than its `VARCHAR`
+const PG_VALUE_TOO_LONG: &str = "22001";
+
+/// Postgres `query_canceled`, raised when a statement runs past `statement_timeout`
+const PG_QUERY_CANCELED: &str = "57014";
+
+/// SQLite `SQLITE_CONSTRAINT_TRIGGER`, raised by the `RAISE(ABORT)` in constraint triggers
+const SQLITE_CONSTRAINT_TRIGGER: &str = "1811";
+
+/// Whether a schema constraint rejected the value, as opposed to the database failing
+fn is_constraint_rejection(error: &sqlx::Error) -> bool {
+    error.as_database_error().is_some_and(|db_error| {
+        db_error.is_check_violation()
+            || db_error
+                .code()
+                .is_some_and(|code| code == PG_VALUE_TOO_LONG || code == SQLITE_CONSTRAINT_TRIGGER)
+    })
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            // Every pooled connection is busy, which is a capacity problem rather than a SQL error
+            sqlx::Error::PoolTimedOut => {
+                AppError::ServiceUnavailable("Timed out waiting for a database connection".into())
+            }
+            // A timed out query points at an overloaded database, which may recover on retry
+            other
+                if other
+                    .as_database_error()
+                    .and_then(|db_error| db_error.code())
+                    .is_some_and(|code| code == PG_QUERY_CANCELED) =>
+            {
+                AppError::ServiceUnavailable("Database query timed out".into())
+            }
+            // Another writer held the SQLite lock through every retry
+            other if is_sqlite_busy(&other) => {
+                AppError::ServiceUnavailable("Database is busy".into())
+            }
+            // A unique index caught a value someone else already holds
+            other
+                if other
+                    .as_database_error()
+                    .is_some_and(|db_error| db_error.is_unique_violation()) =>
+            {
+                AppError::Conflict("Already taken".into())
+            }
+            // Constraints back up domain validation, so input that slipped past it is still a 400
+            other if is_constraint_rejection(&other) => {
+                AppError::Validation("Value rejected by a database constraint".into())
+            }
+            other => AppError::Database(other),
+        }
+    }
+}
+
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_names_one_field() {
+        let error = AppError::validation("email", "Email is malformed");
+
+        let AppError::InvalidFields(fields) = error else {
+            panic!("expected InvalidFields, got {:?}", error);
+        };
+        assert_eq!(
+            fields,
+            vec![FieldError {
+                field: "email",
+                message: "Email is malformed".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validations_keeps_every_field_in_order() {
+        let fields = vec![
+            FieldError {
+                field: "username",
+                message: "Username is too short".into(),
+            },
+            FieldError {
+                field: "password",
+                message: "Password is too short".into(),
+            },
+        ];
+
+        let error = AppError::validations(fields.clone());
+
+        assert!(matches!(error, AppError::InvalidFields(ref kept) if *kept == fields));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_database_error_keeps_source() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        let sqlx_error = sqlx::query("SELECT * FROM missing_table")
+            .execute(&pool)
+            .await
+            .unwrap_err();
+
+        let error = AppError::from(sqlx_error);
+
+        assert!(matches!(error, AppError::Database(_)));
+        assert!(format!("{:?}", error).contains("no such table: missing_table"));
+        assert!(
+            std::error::Error::source(&error)
+                .is_some_and(|source| source.to_string().contains("missing_table"))
+        );
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_statement_timeout_is_service_unavailable() {
+        let database_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL environment variable must be set for PostgreSQL tests");
+        let mut connection = <sqlx::PgConnection as sqlx::Connection>::connect(&database_url)
+            .await
+            .unwrap();
+        sqlx::query("SET statement_timeout = 10")
+            .execute(&mut connection)
+            .await
+            .unwrap();
+        let sqlx_error = sqlx::query("SELECT pg_sleep(1)")
+            .execute(&mut connection)
+            .await
+            .unwrap_err();
+
+        let error = AppError::from(sqlx_error);
+
+        assert!(matches!(error, AppError::ServiceUnavailable(_)));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_error_is_not_mistaken_for_sqlite_busy() {
+        let database_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL environment variable must be set for PostgreSQL tests");
+        let mut connection = <sqlx::PgConnection as sqlx::Connection>::connect(&database_url)
+            .await
+            .unwrap();
+        // 22021, whose low byte is SQLITE_BUSY's 5
+        let sqlx_error = sqlx::query("SELECT convert_from('\\xff'::bytea, 'UTF8')")
+            .execute(&mut connection)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            sqlx_error
+                .as_database_error()
+                .and_then(|e| e.code())
+                .as_deref(),
+            Some("22021")
+        );
+
+        let error = AppError::from(sqlx_error);
+
+        assert!(matches!(error, AppError::Database(_)));
+    }
+}
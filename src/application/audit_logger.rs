@@ -0,0 +1,84 @@
+use chrono::NaiveDateTime;
+use std::{net::IpAddr, sync::Arc};
+use tracing::{instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        app_error::AppResult,
+        clock::{Clock, SystemClock},
+    },
+    domain::audit::{AuditAction, AuditEntry},
+    persistence::audit_log_repo::{AuditLogFilter, AuditLogRepository},
+};
+
+// ============================================================================
+// Audit Logger
+// ============================================================================
+
+/// Appends sensitive actions to the audit log
+/// Recording is best-effort: a failed write is logged and never fails the action itself
+pub struct AuditLogger {
+    repository: Arc<dyn AuditLogRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl AuditLogger {
+    pub fn new(repository: Arc<dyn AuditLogRepository>) -> Self {
+        Self {
+            repository,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read the time entries are stamped with from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Record that `actor_id` performed `action` on `target_id` from `ip`
+    #[instrument(skip(self))]
+    pub async fn record(
+        &self,
+        actor_id: Option<Uuid>,
+        action: AuditAction,
+        target_id: Option<Uuid>,
+        ip: Option<IpAddr>,
+    ) {
+        let entry = AuditEntry {
+            id: Uuid::new_v4(),
+            actor_id,
+            action,
+            target_id,
+            ip: ip.map(|ip| ip.to_string()),
+            created_at: self.clock.now().naive_utc(),
+        };
+
+        if let Err(e) = self.repository.append(&entry).await {
+            warn!(error = ?e, %action, "Failed to write audit log entry");
+        }
+    }
+
+    /// A page of entries, newest first, with how many there are in total
+    #[instrument(skip(self))]
+    pub async fn list_entries(&self, limit: u32, offset: u32) -> AppResult<(Vec<AuditEntry>, u64)> {
+        let entries = self.repository.list_entries(limit, offset).await?;
+        let total = self.repository.count_entries().await?;
+
+        Ok((entries, total))
+    }
+
+    /// The next batch of entries in `filter`, oldest first, after the one keyed by `after`
+    /// An empty batch means the export has caught up
+    pub async fn export_batch(
+        &self,
+        filter: &AuditLogFilter,
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: u32,
+    ) -> AppResult<Vec<AuditEntry>> {
+        self.repository
+            .list_entries_after(filter, after, limit)
+            .await
+    }
+}
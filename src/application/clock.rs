@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+
+#[cfg(test)]
+use std::sync::Mutex;
+
+// ============================================================================
+// Clock
+// ============================================================================
+
+/// Source of the current time for expiry checks
+/// Services take one so tests can move time instead of sleeping
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Reads the system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that stays put until a test moves it
+#[cfg(test)]
+pub struct MockClock(Mutex<DateTime<Utc>>);
+
+#[cfg(test)]
+impl MockClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self(Mutex::new(now))
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_only_moves_when_advanced() {
+        let start = Utc::now();
+        let clock = MockClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(chrono::Duration::minutes(5));
+
+        assert_eq!(clock.now(), start + chrono::Duration::minutes(5));
+    }
+}
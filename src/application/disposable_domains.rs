@@ -0,0 +1,92 @@
+use std::{collections::HashSet, io, path::Path};
+
+/// Providers refused when no list file is configured
+const BUNDLED_LIST: &str = include_str!("disposable_domains.txt");
+
+// ============================================================================
+// Disposable Email Domains
+// ============================================================================
+
+/// Email domains handing out throwaway inboxes, refused at registration
+/// A listed domain covers its subdomains, so `eu.mailinator.com` is refused with `mailinator.com`
+#[derive(Debug, Clone, Default)]
+pub struct DisposableDomains {
+    domains: HashSet<String>,
+}
+
+impl DisposableDomains {
+    /// The list shipped with the crate
+    pub fn bundled() -> Self {
+        Self::parse(BUNDLED_LIST)
+    }
+
+    /// Read a list from `path`, in the same format as the bundled one
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// One domain per line, blank lines and `#` comments are skipped
+    pub fn parse(list: &str) -> Self {
+        let domains = list
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .map(normalize)
+            .filter(|domain| !domain.is_empty())
+            .collect();
+        Self { domains }
+    }
+
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// Whether `domain` or any domain it sits under is listed
+    pub fn contains(&self, domain: &str) -> bool {
+        let mut domain = normalize(domain);
+        loop {
+            if self.domains.contains(&domain) {
+                return true;
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => domain = parent.to_string(),
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Lowercase without surrounding whitespace or the trailing dot of a fully qualified name
+fn normalize(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_list_refuses_known_providers_only() {
+        let domains = DisposableDomains::bundled();
+
+        assert!(domains.contains("mailinator.com"));
+        assert!(domains.contains("MailInator.COM."));
+        assert!(domains.contains("eu.mailinator.com"));
+        assert!(!domains.contains("example.com"));
+        assert!(!domains.contains("notmailinator.com"));
+        assert!(!domains.contains("com"));
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_blank_lines() {
+        let domains =
+            DisposableDomains::parse("# header\n\n Throwaway.Example \nspam.test # note\n");
+
+        assert_eq!(domains.len(), 2);
+        assert!(domains.contains("throwaway.example"));
+        assert!(domains.contains("spam.test"));
+    }
+}
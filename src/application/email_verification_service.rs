@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use time::Duration;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        clock::{Clock, SystemClock},
+    },
+    crypto::token::{generate_token, hash_token},
+    persistence::email_verification_repo::EmailVerificationRepository,
+};
+
+// ============================================================================
+// Email Verification Service
+// ============================================================================
+
+pub struct EmailVerificationService {
+    repository: Arc<dyn EmailVerificationRepository>,
+    token_ttl: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl EmailVerificationService {
+    pub fn new(repository: Arc<dyn EmailVerificationRepository>, token_ttl: Duration) -> Self {
+        Self {
+            repository,
+            token_ttl,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read the time for token expiry from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Create a single-use verification token for a user and return its plain value
+    #[instrument(skip(self))]
+    pub async fn issue_token(&self, user_id: Uuid) -> AppResult<String> {
+        let token = generate_token();
+        let expires_at = self.clock.now().naive_utc()
+            + chrono::Duration::seconds(self.token_ttl.whole_seconds());
+
+        self.repository
+            .create_token(user_id, &hash_token(&token), expires_at)
+            .await?;
+
+        Ok(token)
+    }
+
+    /// Consume a verification token and mark its user's email as verified
+    #[instrument(skip(self, token))]
+    pub async fn verify_email(&self, token: &str) -> AppResult<Uuid> {
+        let user_id = self
+            .repository
+            .consume_token(&hash_token(token), self.clock.now().naive_utc())
+            .await?
+            .ok_or_else(|| AppError::Validation("Invalid or expired verification token".into()))?;
+
+        info!(%user_id, "Email verified");
+
+        Ok(user_id)
+    }
+}
+
+// The fixtures run against in-memory SQLite
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::{
+        application::clock::MockClock,
+        domain::{email::Email, username::Username},
+        persistence::{SqliteEmailVerificationRepository, SqliteUserRepository, UserRepository},
+    };
+
+    async fn setup(
+        token_ttl: Duration,
+    ) -> (EmailVerificationService, Arc<dyn UserRepository>, Uuid) {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        let users: Arc<dyn UserRepository> = Arc::new(SqliteUserRepository::new(pool.clone()));
+        let user_id = users
+            .create_user(
+                &Username::parse("testuser").unwrap(),
+                &Email::parse("testuser@gmail.com").unwrap(),
+                "hash",
+            )
+            .await
+            .unwrap();
+
+        let service = EmailVerificationService::new(
+            Arc::new(SqliteEmailVerificationRepository::new(pool)),
+            token_ttl,
+        );
+        (service, users, user_id)
+    }
+
+    #[tokio::test]
+    async fn test_verify_email_with_valid_token() {
+        let (service, users, user_id) = setup(Duration::hours(24)).await;
+
+        let token = service.issue_token(user_id).await.unwrap();
+        let verified_id = service.verify_email(&token).await.unwrap();
+
+        assert_eq!(verified_id, user_id);
+        let user = users
+            .get_user_by_username("testuser")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(user.email_verified);
+    }
+
+    #[tokio::test]
+    async fn test_verify_email_rejects_expired_token() {
+        let (service, users, user_id) = setup(Duration::hours(24)).await;
+        let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+        let service = service.with_clock(clock.clone());
+
+        let token = service.issue_token(user_id).await.unwrap();
+        clock.advance(chrono::Duration::hours(25));
+        let result = service.verify_email(&token).await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+        let user = users
+            .get_user_by_username("testuser")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!user.email_verified);
+    }
+
+    #[tokio::test]
+    async fn test_verify_email_rejects_reused_token() {
+        let (service, _, user_id) = setup(Duration::hours(24)).await;
+
+        let token = service.issue_token(user_id).await.unwrap();
+        service.verify_email(&token).await.unwrap();
+        let result = service.verify_email(&token).await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_email_rejects_unknown_token() {
+        let (service, _, _) = setup(Duration::hours(24)).await;
+
+        let result = service.verify_email("not-a-token").await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::{email::Email, username::Username};
+
+// ============================================================================
+// Domain Events
+// ============================================================================
+
+/// Something that happened in the domain that other parts of the system may react to
+#[derive(Debug, Clone, PartialEq)]
+pub enum DomainEvent {
+    UserRegistered {
+        id: Uuid,
+        username: Username,
+        email: Email,
+    },
+}
+
+// ============================================================================
+// Port Traits (Interfaces for dependencies)
+// ============================================================================
+
+/// Receives domain events once the change that raised them has been stored
+/// Sinks should not fail the caller, so problems are theirs to log
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    async fn emit(&self, event: DomainEvent);
+}
+
+/// Sink that drops every event, used when nothing is listening
+pub struct NoopEventSink;
+
+#[async_trait]
+impl EventSink for NoopEventSink {
+    async fn emit(&self, _event: DomainEvent) {}
+}
@@ -0,0 +1,114 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Keyed digest of a failed attempt, the password itself is never kept
+type AttemptKey = [u8; 32];
+
+// ============================================================================
+// Failed Login Cache
+// ============================================================================
+
+/// Remembers failed logins for a few seconds so identical rapid retries skip Argon2
+/// Attempts are keyed by username, the stored hash they were checked against and the password
+/// tried, through an HMAC with a per-process key. A password change moves the stored hash,
+/// so a cached failure can never reject what has since become the right password
+pub struct FailedLoginCache {
+    ttl: Duration,
+    key: [u8; 32],
+    failures: Mutex<HashMap<AttemptKey, Instant>>,
+}
+
+impl FailedLoginCache {
+    pub fn new(ttl: Duration) -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        Self {
+            ttl,
+            key,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether this exact attempt failed within the last `ttl`
+    pub fn is_known_failure(&self, username: &str, stored_hash: &str, password: &str) -> bool {
+        self.is_known_failure_at(username, stored_hash, password, Instant::now())
+    }
+
+    /// Remember that this attempt failed
+    pub fn remember_failure(&self, username: &str, stored_hash: &str, password: &str) {
+        self.remember_failure_at(username, stored_hash, password, Instant::now())
+    }
+
+    fn is_known_failure_at(
+        &self,
+        username: &str,
+        stored_hash: &str,
+        password: &str,
+        now: Instant,
+    ) -> bool {
+        let key = self.attempt_key(username, stored_hash, password);
+        let failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        failures
+            .get(&key)
+            .is_some_and(|failed_at| now.duration_since(*failed_at) < self.ttl)
+    }
+
+    fn remember_failure_at(&self, username: &str, stored_hash: &str, password: &str, now: Instant) {
+        let key = self.attempt_key(username, stored_hash, password);
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Expired entries would never match again, so drop them to bound memory
+        failures.retain(|_, failed_at| now.duration_since(*failed_at) < self.ttl);
+
+        failures.insert(key, now);
+    }
+
+    fn attempt_key(&self, username: &str, stored_hash: &str, password: &str) -> AttemptKey {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key)
+            .expect("HMAC-SHA256 accepts keys of any length");
+        // Length prefixes keep ("ab", "c") and ("a", "bc") apart
+        for part in [username.to_lowercase().as_str(), stored_hash, password] {
+            mac.update(&(part.len() as u64).to_le_bytes());
+            mac.update(part.as_bytes());
+        }
+        mac.finalize().into_bytes().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_remembers_exact_attempt_until_ttl() {
+        let cache = FailedLoginCache::new(TTL);
+        let now = Instant::now();
+        cache.remember_failure_at("alice", "$hash", "wrong", now);
+
+        assert!(cache.is_known_failure_at("ALICE", "$hash", "wrong", now));
+        assert!(cache.is_known_failure_at("alice", "$hash", "wrong", now + TTL / 2));
+        assert!(!cache.is_known_failure_at("alice", "$hash", "wrong", now + TTL));
+    }
+
+    #[test]
+    fn test_other_attempts_are_not_matched() {
+        let cache = FailedLoginCache::new(TTL);
+        let now = Instant::now();
+        cache.remember_failure_at("alice", "$hash", "wrong", now);
+
+        assert!(!cache.is_known_failure_at("alice", "$hash", "other", now));
+        assert!(!cache.is_known_failure_at("bob", "$hash", "wrong", now));
+        assert!(
+            !cache.is_known_failure_at("alice", "$new-hash", "wrong", now),
+            "a changed password must be checked afresh"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::application::app_error::{AppError, AppResult};
+
+// ============================================================================
+// Hash Limiter
+// ============================================================================
+
+/// Runs password hashing on Tokio's blocking pool, at most `max_concurrent` hashes at a time
+/// Every Argon2 hash allocates its full memory cost, so the cap bounds peak memory; excess work queues
+pub struct HashLimiter {
+    permits: Arc<Semaphore>,
+}
+
+impl HashLimiter {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(
+                max_concurrent.clamp(1, Semaphore::MAX_PERMITS),
+            )),
+        }
+    }
+
+    /// No cap beyond the size of the blocking pool itself
+    pub fn unbounded() -> Self {
+        Self::new(Semaphore::MAX_PERMITS)
+    }
+
+    /// Wait for a free slot, then run `work` on the blocking pool
+    /// The slot stays taken until `work` finishes, even if the caller stops waiting for it
+    pub async fn run<T, F>(&self, work: F) -> AppResult<T>
+    where
+        F: FnOnce() -> AppResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| AppError::Internal("Hash limiter is closed".into()))?;
+
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            work()
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Hashing task failed: {}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_dropped_caller_keeps_slot_until_work_finishes() {
+        let limiter = Arc::new(HashLimiter::new(1));
+        let (started_tx, started_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+
+        let caller = tokio::spawn({
+            let limiter = limiter.clone();
+            async move {
+                limiter
+                    .run(move || {
+                        started_tx.send(()).unwrap();
+                        release_rx.recv().unwrap();
+                        Ok(())
+                    })
+                    .await
+            }
+        });
+        tokio::task::spawn_blocking(move || started_rx.recv().unwrap())
+            .await
+            .unwrap();
+        // Like a client disconnecting while its password is being hashed
+        caller.abort();
+        assert!(caller.await.unwrap_err().is_cancelled());
+
+        assert_eq!(limiter.permits.available_permits(), 0);
+
+        release_tx.send(()).unwrap();
+        limiter.run(|| Ok(())).await.unwrap();
+        assert_eq!(limiter.permits.available_permits(), 1);
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::AppResult,
+    crypto::token::{generate_token, hash_token},
+    persistence::invite_code_repo::InviteCodeRepository,
+};
+
+// ============================================================================
+// Invite Service
+// ============================================================================
+
+/// Mints the single-use codes that admit new users while registration is invite-only
+pub struct InviteService {
+    repository: Arc<dyn InviteCodeRepository>,
+}
+
+impl InviteService {
+    pub fn new(repository: Arc<dyn InviteCodeRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Create an invite code on behalf of `created_by`
+    /// Only its hash is stored, so the returned code can't be shown again
+    #[instrument(skip(self))]
+    pub async fn mint_code(&self, created_by: Uuid) -> AppResult<String> {
+        let code = generate_token();
+        self.repository
+            .create_code(&hash_token(&code), created_by)
+            .await?;
+
+        info!(%created_by, "Invite code minted");
+
+        Ok(code)
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::Duration;
+use std::sync::Arc;
+
+use crate::{
+    application::{
+        app_error::AppResult,
+        clock::{Clock, SystemClock},
+    },
+    persistence::{InMemoryRateLimitStore, RateLimitStore},
+};
+
+/// Length of the window failed logins are counted over, and so the longest a lockout lasts
+const WINDOW: Duration = Duration::minutes(15);
+
+// ============================================================================
+// Login Lockout
+// ============================================================================
+
+/// Locks an account after too many failed logins within a 15 minute window
+/// Counters live in memory unless a shared store is given with `with_store`
+pub struct LoginLockout {
+    max_failures: u32,
+    store: Arc<dyn RateLimitStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LoginLockout {
+    pub fn new(max_failures: u32) -> Self {
+        Self {
+            max_failures,
+            store: Arc::new(InMemoryRateLimitStore::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Keep counters in `store`, such as the database so every instance shares them
+    pub fn with_store(mut self, store: Arc<dyn RateLimitStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Read the time windows are measured against from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether the account has used up its failed attempts for the current window
+    pub async fn is_locked(&self, username: &str) -> AppResult<bool> {
+        let failures = self
+            .store
+            .current_count(&key(username), WINDOW, self.clock.now().naive_utc())
+            .await?;
+        Ok(failures >= self.max_failures)
+    }
+
+    /// Count a failed login against the account
+    pub async fn record_failure(&self, username: &str) -> AppResult<()> {
+        self.store
+            .incr_and_check(
+                &key(username),
+                self.max_failures,
+                WINDOW,
+                self.clock.now().naive_utc(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Forget earlier failures once the user proves they know the password
+    pub async fn record_success(&self, username: &str) -> AppResult<()> {
+        self.store.reset(&key(username)).await
+    }
+}
+
+fn key(username: &str) -> String {
+    format!("login:{}", username.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::clock::MockClock;
+
+    fn setup(max_failures: u32) -> (LoginLockout, Arc<MockClock>) {
+        let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+        let lockout = LoginLockout::new(max_failures).with_clock(clock.clone());
+        (lockout, clock)
+    }
+
+    #[tokio::test]
+    async fn test_locks_after_max_failures() {
+        let (lockout, _) = setup(3);
+
+        for _ in 0..2 {
+            lockout.record_failure("alice").await.unwrap();
+        }
+        assert!(!lockout.is_locked("alice").await.unwrap());
+
+        lockout.record_failure("ALICE").await.unwrap();
+        assert!(lockout.is_locked("alice").await.unwrap());
+        assert!(!lockout.is_locked("bob").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_lock_expires_with_window() {
+        let (lockout, clock) = setup(1);
+        lockout.record_failure("alice").await.unwrap();
+
+        clock.advance(WINDOW);
+
+        assert!(!lockout.is_locked("alice").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_success_clears_failures() {
+        let (lockout, _) = setup(2);
+        lockout.record_failure("alice").await.unwrap();
+
+        lockout.record_success("alice").await.unwrap();
+        lockout.record_failure("alice").await.unwrap();
+
+        assert!(!lockout.is_locked("alice").await.unwrap());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_shared_store_locks_across_instances() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .unwrap();
+        let store: Arc<dyn RateLimitStore> =
+            Arc::new(crate::persistence::SqliteRateLimitStore::new(pool));
+        let first = LoginLockout::new(2).with_store(store.clone());
+        let second = LoginLockout::new(2).with_store(store);
+
+        first.record_failure("alice").await.unwrap();
+        second.record_failure("alice").await.unwrap();
+
+        assert!(first.is_locked("alice").await.unwrap());
+        assert!(second.is_locked("alice").await.unwrap());
+    }
+}
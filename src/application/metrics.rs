@@ -0,0 +1,96 @@
+use std::{collections::BTreeMap, fmt::Write, sync::RwLock};
+
+// ============================================================================
+// Metrics Registry
+// ============================================================================
+
+struct Sample {
+    kind: &'static str,
+    help: &'static str,
+    value: i64,
+}
+
+/// Gauges and counters sampled by background tasks and served at `/metrics` in the Prometheus
+/// text format
+#[derive(Default)]
+pub struct Metrics {
+    samples: RwLock<BTreeMap<&'static str, Sample>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a gauge, registering it on first use
+    pub fn set_gauge(&self, name: &'static str, help: &'static str, value: i64) {
+        self.set(name, "gauge", help, value);
+    }
+
+    /// Set a counter to a running total kept elsewhere, registering it on first use
+    pub fn set_counter(&self, name: &'static str, help: &'static str, value: u64) {
+        self.set(
+            name,
+            "counter",
+            help,
+            i64::try_from(value).unwrap_or(i64::MAX),
+        );
+    }
+
+    fn set(&self, name: &'static str, kind: &'static str, help: &'static str, value: i64) {
+        let mut samples = self.samples.write().unwrap_or_else(|e| e.into_inner());
+        samples.insert(name, Sample { kind, help, value });
+    }
+
+    /// Current value of a gauge or counter, `None` until it has been set
+    pub fn gauge(&self, name: &str) -> Option<i64> {
+        let samples = self.samples.read().unwrap_or_else(|e| e.into_inner());
+        samples.get(name).map(|sample| sample.value)
+    }
+
+    /// Every gauge and counter in the Prometheus text exposition format, sorted by name
+    pub fn render(&self) -> String {
+        let samples = self.samples.read().unwrap_or_else(|e| e.into_inner());
+        let mut output = String::new();
+        for (name, sample) in samples.iter() {
+            // Writing to a String can't fail
+            let _ = writeln!(output, "# HELP {} {}", name, sample.help);
+            let _ = writeln!(output, "# TYPE {} {}", name, sample.kind);
+            let _ = writeln!(output, "{} {}", name, sample.value);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_lists_gauges_in_prometheus_format() {
+        let metrics = Metrics::new();
+        metrics.set_gauge("b_gauge", "Second", 1);
+        metrics.set_gauge("a_gauge", "First", 5);
+        metrics.set_gauge("a_gauge", "First", 7);
+
+        assert_eq!(metrics.gauge("a_gauge"), Some(7));
+        assert_eq!(metrics.gauge("missing"), None);
+        assert_eq!(
+            metrics.render(),
+            "# HELP a_gauge First\n# TYPE a_gauge gauge\na_gauge 7\n\
+             # HELP b_gauge Second\n# TYPE b_gauge gauge\nb_gauge 1\n"
+        );
+    }
+
+    #[test]
+    fn test_counters_render_with_counter_type() {
+        let metrics = Metrics::new();
+        metrics.set_counter("events_total", "Events seen", 3);
+
+        assert_eq!(metrics.gauge("events_total"), Some(3));
+        assert_eq!(
+            metrics.render(),
+            "# HELP events_total Events seen\n# TYPE events_total counter\nevents_total 3\n"
+        );
+    }
+}
@@ -1,2 +1,16 @@
 pub mod app_error;
+pub mod audit_logger;
+pub mod clock;
+pub mod disposable_domains;
+pub mod email_verification_service;
+pub mod events;
+pub mod failed_login_cache;
+pub mod hash_limiter;
+pub mod invite_service;
+pub mod login_lockout;
+pub mod metrics;
+pub mod password_reset_service;
+pub mod registration_limiter;
+pub mod session_service;
+pub mod token_service;
 pub mod user_service;
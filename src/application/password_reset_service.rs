@@ -0,0 +1,263 @@
+use secrecy::{ExposeSecret, SecretString};
+use std::sync::Arc;
+use time::Duration;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        clock::{Clock, SystemClock},
+        hash_limiter::HashLimiter,
+        user_service::PasswordHasher,
+    },
+    crypto::token::{generate_token, hash_token},
+    domain::{
+        email::Email,
+        password::{DEFAULT_PASSWORD_MAX_BYTES, check_password_size, validate_password_strength},
+    },
+    persistence::{password_reset_repo::PasswordResetRepository, user_repo::UserRepository},
+};
+
+// ============================================================================
+// Password Reset Service
+// ============================================================================
+
+pub struct PasswordResetService {
+    hasher: Arc<dyn PasswordHasher>,
+    users: Arc<dyn UserRepository>,
+    repository: Arc<dyn PasswordResetRepository>,
+    token_ttl: Duration,
+    clock: Arc<dyn Clock>,
+    hash_limiter: Arc<HashLimiter>,
+    max_password_bytes: usize,
+}
+
+impl PasswordResetService {
+    pub fn new(
+        hasher: Arc<dyn PasswordHasher>,
+        users: Arc<dyn UserRepository>,
+        repository: Arc<dyn PasswordResetRepository>,
+        token_ttl: Duration,
+    ) -> Self {
+        Self {
+            hasher,
+            users,
+            repository,
+            token_ttl,
+            clock: Arc::new(SystemClock),
+            hash_limiter: Arc::new(HashLimiter::unbounded()),
+            max_password_bytes: DEFAULT_PASSWORD_MAX_BYTES,
+        }
+    }
+
+    /// Read the time for token expiry from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Share a cap on concurrent password hashes with other services
+    pub fn with_hash_limiter(mut self, limiter: Arc<HashLimiter>) -> Self {
+        self.hash_limiter = limiter;
+        self
+    }
+
+    /// Refuse new passwords longer than this many bytes with a 422, before hashing them
+    pub fn with_max_password_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_password_bytes = max_bytes;
+        self
+    }
+
+    /// Create a reset token if a user owns the email address
+    /// Returns `None` for unknown or malformed addresses so callers can't tell them apart
+    #[instrument(skip(self, email))]
+    pub async fn request_reset(&self, email: &str) -> AppResult<Option<String>> {
+        let Ok(email) = Email::parse(email) else {
+            return Ok(None);
+        };
+        let Some(user) = self.users.get_user_by_email(&email).await? else {
+            return Ok(None);
+        };
+
+        let token = generate_token();
+        let expires_at = self.clock.now().naive_utc()
+            + chrono::Duration::seconds(self.token_ttl.whole_seconds());
+        self.repository
+            .create_token(user.id, &hash_token(&token), expires_at)
+            .await?;
+
+        // Nothing delivers email yet, so the token is only logged
+        info!(user_id = %user.id, %token, "Password reset token issued");
+
+        Ok(Some(token))
+    }
+
+    /// Consume a reset token and replace the user's password, returning whose it was
+    #[instrument(skip(self, token, new_password))]
+    pub async fn confirm_reset(&self, token: &str, new_password: &SecretString) -> AppResult<Uuid> {
+        check_password_size(new_password, self.max_password_bytes)?;
+        validate_password_strength(new_password.expose_secret())?;
+
+        let hasher = self.hasher.clone();
+        let new_password = new_password.clone();
+        let hash = self
+            .hash_limiter
+            .run(move || hasher.hash_password(new_password.expose_secret()))
+            .await?;
+        let user_id = self
+            .repository
+            .consume_token(&hash_token(token), self.clock.now().naive_utc(), &hash)
+            .await?
+            .ok_or_else(|| AppError::Validation("Invalid or expired reset token".into()))?;
+
+        info!(%user_id, "Password reset completed");
+
+        Ok(user_id)
+    }
+}
+
+// The fixtures run against in-memory SQLite
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::username::Username,
+        persistence::{SqlitePasswordResetRepository, SqliteUserRepository},
+    };
+
+    struct MockPasswordHasher;
+
+    impl PasswordHasher for MockPasswordHasher {
+        fn hash_password(&self, password: &str) -> AppResult<String> {
+            Ok(format!("{}_hashed", password))
+        }
+        fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
+            Ok(format!("{}_hashed", password) == hash)
+        }
+    }
+
+    async fn setup(token_ttl: Duration) -> (PasswordResetService, Arc<dyn UserRepository>) {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        let users: Arc<dyn UserRepository> = Arc::new(SqliteUserRepository::new(pool.clone()));
+        users
+            .create_user(
+                &Username::parse("testuser").unwrap(),
+                &Email::parse("testuser@gmail.com").unwrap(),
+                "old_hash",
+            )
+            .await
+            .unwrap();
+
+        let service = PasswordResetService::new(
+            Arc::new(MockPasswordHasher),
+            users.clone(),
+            Arc::new(SqlitePasswordResetRepository::new(pool)),
+            token_ttl,
+        );
+        (service, users)
+    }
+
+    async fn current_hash(users: &Arc<dyn UserRepository>) -> String {
+        users
+            .get_user_by_username("testuser")
+            .await
+            .unwrap()
+            .unwrap()
+            .password_hash
+            .expose()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_password_reset_happy_path() {
+        let (service, users) = setup(Duration::minutes(30)).await;
+
+        let token = service
+            .request_reset("testuser@gmail.com")
+            .await
+            .unwrap()
+            .expect("token should be issued for a known email");
+        service
+            .confirm_reset(&token, &"newpassword1".into())
+            .await
+            .unwrap();
+
+        assert_eq!(current_hash(&users).await, "newpassword1_hashed");
+    }
+
+    #[tokio::test]
+    async fn test_request_reset_for_unknown_email_issues_nothing() {
+        let (service, _) = setup(Duration::minutes(30)).await;
+
+        assert!(
+            service
+                .request_reset("nobody@gmail.com")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            service
+                .request_reset("not-an-email")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confirm_reset_rejects_expired_token() {
+        let (service, users) = setup(Duration::minutes(-1)).await;
+
+        let token = service
+            .request_reset("testuser@gmail.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let result = service.confirm_reset(&token, &"newpassword1".into()).await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+        assert_eq!(current_hash(&users).await, "old_hash");
+    }
+
+    #[tokio::test]
+    async fn test_confirm_reset_rejects_reused_token() {
+        let (service, users) = setup(Duration::minutes(30)).await;
+
+        let token = service
+            .request_reset("testuser@gmail.com")
+            .await
+            .unwrap()
+            .unwrap();
+        service
+            .confirm_reset(&token, &"newpassword1".into())
+            .await
+            .unwrap();
+        let result = service.confirm_reset(&token, &"newpassword2".into()).await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+        assert_eq!(current_hash(&users).await, "newpassword1_hashed");
+    }
+
+    #[tokio::test]
+    async fn test_confirm_reset_enforces_password_strength() {
+        let (service, _) = setup(Duration::minutes(30)).await;
+
+        let token = service
+            .request_reset("testuser@gmail.com")
+            .await
+            .unwrap()
+            .unwrap();
+        let result = service.confirm_reset(&token, &"weak".into()).await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
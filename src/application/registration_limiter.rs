@@ -0,0 +1,128 @@
+use chrono::Duration;
+use std::sync::Arc;
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        clock::{Clock, SystemClock},
+    },
+    domain::email::Email,
+    persistence::{InMemoryRateLimitStore, RateLimitStore},
+};
+
+/// Length of the window registrations are counted over
+const WINDOW: Duration = Duration::hours(1);
+
+// ============================================================================
+// Registration Limiter
+// ============================================================================
+
+/// Caps registrations per email domain per hour to blunt bursts from disposable-email providers
+/// Counters live in memory unless a shared store is given with `with_store`
+pub struct RegistrationLimiter {
+    max_per_domain: u32,
+    store: Arc<dyn RateLimitStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RegistrationLimiter {
+    pub fn new(max_per_domain: u32) -> Self {
+        Self {
+            max_per_domain,
+            store: Arc::new(InMemoryRateLimitStore::new()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Keep counters in `store`, such as the database so every instance shares them
+    pub fn with_store(mut self, store: Arc<dyn RateLimitStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Read the time windows are measured against from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Count a registration attempt for the email's domain
+    /// Fails with `TooManyRequests` once the domain has used up its hourly allowance
+    pub async fn check(&self, email: &Email) -> AppResult<()> {
+        let key = format!("registration:{}", email.domain());
+        let allowed = self
+            .store
+            .incr_and_check(
+                &key,
+                self.max_per_domain,
+                WINDOW,
+                self.clock.now().naive_utc(),
+            )
+            .await?;
+        if !allowed {
+            return Err(AppError::TooManyRequests(
+                "Too many registrations from this email domain, try again later".into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::clock::MockClock;
+
+    fn email(value: &str) -> Email {
+        Email::parse(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_allows_burst_under_limit() {
+        let limiter = RegistrationLimiter::new(3);
+
+        for i in 0..3 {
+            let result = limiter.check(&email(&format!("user{}@spam.test", i))).await;
+            assert!(result.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_burst_over_limit() {
+        let limiter = RegistrationLimiter::new(3);
+        for i in 0..3 {
+            limiter
+                .check(&email(&format!("user{}@spam.test", i)))
+                .await
+                .unwrap();
+        }
+
+        let result = limiter.check(&email("user3@SPAM.test")).await;
+
+        assert!(matches!(result, Err(AppError::TooManyRequests(_))));
+    }
+
+    #[tokio::test]
+    async fn test_domains_are_counted_separately() {
+        let limiter = RegistrationLimiter::new(1);
+        limiter.check(&email("a@spam.test")).await.unwrap();
+
+        let result = limiter.check(&email("a@example.com")).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_limit_resets_after_window() {
+        let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+        let limiter = RegistrationLimiter::new(1).with_clock(clock.clone());
+        limiter.check(&email("a@spam.test")).await.unwrap();
+        assert!(limiter.check(&email("b@spam.test")).await.is_err());
+
+        clock.advance(WINDOW);
+        let result = limiter.check(&email("c@spam.test")).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,384 @@
+use std::sync::Arc;
+use time::Duration;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        clock::{Clock, SystemClock},
+        token_service::TokenService,
+    },
+    config::RefreshTokenReusePolicy,
+    crypto::token::{generate_token, hash_token},
+    domain::{refresh_token::RefreshToken, user::User},
+    persistence::{
+        refresh_token_repo::{RefreshTokenRepository, SessionMetadata},
+        user_repo::UserRepository,
+    },
+};
+
+/// Longest User-Agent kept as a session label, matching the column width
+const MAX_USER_AGENT_LENGTH: usize = 255;
+
+// ============================================================================
+// Token Pair
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+// ============================================================================
+// Session Service
+// ============================================================================
+
+/// Issues and rotates refresh tokens alongside access tokens
+pub struct SessionService {
+    token_service: Arc<TokenService>,
+    users: Arc<dyn UserRepository>,
+    repository: Arc<dyn RefreshTokenRepository>,
+    refresh_token_ttl: Duration,
+    reuse_policy: RefreshTokenReusePolicy,
+    clock: Arc<dyn Clock>,
+}
+
+impl SessionService {
+    pub fn new(
+        token_service: Arc<TokenService>,
+        users: Arc<dyn UserRepository>,
+        repository: Arc<dyn RefreshTokenRepository>,
+        refresh_token_ttl: Duration,
+        reuse_policy: RefreshTokenReusePolicy,
+    ) -> Self {
+        Self {
+            token_service,
+            users,
+            repository,
+            refresh_token_ttl,
+            reuse_policy,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read the time for refresh token expiry and revocation from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Start a new token family for a freshly authenticated user
+    /// `user_agent` labels the session when it's listed later
+    #[instrument(skip(self, user, user_agent), fields(user_id = %user.id))]
+    pub async fn start_session(
+        &self,
+        user: &User,
+        user_agent: Option<&str>,
+    ) -> AppResult<TokenPair> {
+        let user_agent: Option<String> =
+            user_agent.map(|agent| agent.chars().take(MAX_USER_AGENT_LENGTH).collect());
+        let session = SessionMetadata {
+            user_agent: user_agent.as_deref(),
+            last_used_at: None,
+        };
+        self.issue_pair(user, Uuid::new_v4(), session).await
+    }
+
+    /// Exchange a refresh token for a new pair, revoking the presented token
+    #[instrument(skip(self, refresh_token))]
+    pub async fn refresh(&self, refresh_token: &str) -> AppResult<TokenPair> {
+        let token = self
+            .repository
+            .get_token_by_hash(&hash_token(refresh_token))
+            .await?
+            .ok_or(AppError::InvalidCredentials)?;
+        let now = self.clock.now().naive_utc();
+
+        if token.is_revoked() {
+            return Err(self.handle_reuse(&token).await);
+        }
+        if token.is_expired(now) {
+            return Err(AppError::InvalidCredentials);
+        }
+        // Losing this race means another request rotated the same token first
+        if !self.repository.revoke_token(token.id, now).await? {
+            return Err(self.handle_reuse(&token).await);
+        }
+
+        let user = self
+            .users
+            .get_user_by_id(&token.user_id)
+            .await?
+            .ok_or(AppError::InvalidCredentials)?;
+
+        let session = SessionMetadata {
+            user_agent: token.user_agent.as_deref(),
+            last_used_at: Some(now),
+        };
+        self.issue_pair(&user, token.family_id, session).await
+    }
+
+    /// Live sessions of a user, one per token family, newest first
+    #[instrument(skip(self))]
+    pub async fn list_sessions(&self, user_id: Uuid) -> AppResult<Vec<RefreshToken>> {
+        self.repository
+            .list_active_tokens(user_id, self.clock.now().naive_utc())
+            .await
+    }
+
+    /// A page of a user's live sessions, newest first, with how many there are in total
+    #[instrument(skip(self))]
+    pub async fn list_sessions_page(
+        &self,
+        user_id: Uuid,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<(Vec<RefreshToken>, u64)> {
+        let now = self.clock.now().naive_utc();
+        let sessions = self
+            .repository
+            .list_active_tokens_page(user_id, now, limit, offset)
+            .await?;
+        let total = self.repository.count_active_tokens(user_id, now).await?;
+
+        Ok((sessions, total))
+    }
+
+    /// Revoke one of the user's sessions by the id of its live token
+    /// Sessions belonging to someone else are reported as not found
+    #[instrument(skip(self))]
+    pub async fn revoke_session(&self, user_id: Uuid, session_id: Uuid) -> AppResult<()> {
+        let session = self
+            .list_sessions(user_id)
+            .await?
+            .into_iter()
+            .find(|token| token.id == session_id)
+            .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+
+        self.repository
+            .revoke_family(session.family_id, self.clock.now().naive_utc())
+            .await?;
+        Ok(())
+    }
+
+    /// Revoke every session of a user, forcing them to log in again everywhere
+    /// Returns how many live refresh tokens were revoked
+    #[instrument(skip(self))]
+    pub async fn revoke_all_sessions(&self, user_id: Uuid) -> AppResult<u64> {
+        let revoked = self
+            .repository
+            .revoke_all_for_user(user_id, self.clock.now().naive_utc())
+            .await?;
+
+        info!(%user_id, revoked, "Revoked all sessions");
+
+        Ok(revoked)
+    }
+
+    async fn issue_pair(
+        &self,
+        user: &User,
+        family_id: Uuid,
+        session: SessionMetadata<'_>,
+    ) -> AppResult<TokenPair> {
+        let refresh_token = generate_token();
+        let expires_at = self.clock.now().naive_utc()
+            + chrono::Duration::seconds(self.refresh_token_ttl.whole_seconds());
+        self.repository
+            .create_token(
+                user.id,
+                family_id,
+                &hash_token(&refresh_token),
+                expires_at,
+                session,
+            )
+            .await?;
+
+        Ok(TokenPair {
+            access_token: self.token_service.issue_access_token(user)?,
+            refresh_token,
+        })
+    }
+
+    /// Apply the reuse policy to a replayed token and return the error to report
+    async fn handle_reuse(&self, token: &RefreshToken) -> AppError {
+        warn!(
+            user_id = %token.user_id,
+            family_id = %token.family_id,
+            "Revoked refresh token was presented again"
+        );
+
+        if self.reuse_policy == RefreshTokenReusePolicy::RevokeFamily {
+            match self
+                .repository
+                .revoke_family(token.family_id, self.clock.now().naive_utc())
+                .await
+            {
+                Ok(revoked) => warn!(revoked, family_id = %token.family_id, "Revoked token family"),
+                Err(e) => return e,
+            }
+        }
+
+        AppError::InvalidCredentials
+    }
+}
+
+// The fixtures run against in-memory SQLite
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::{
+        application::clock::MockClock,
+        domain::{email::Email, username::Username},
+        persistence::{SqliteRefreshTokenRepository, SqliteUserRepository},
+    };
+
+    async fn setup(reuse_policy: RefreshTokenReusePolicy) -> (SessionService, User) {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        let users: Arc<dyn UserRepository> = Arc::new(SqliteUserRepository::new(pool.clone()));
+        let user_id = users
+            .create_user(
+                &Username::parse("testuser").unwrap(),
+                &Email::parse("testuser@gmail.com").unwrap(),
+                "hash",
+            )
+            .await
+            .unwrap();
+        let user = users.get_user_by_id(&user_id).await.unwrap().unwrap();
+
+        let service = SessionService::new(
+            Arc::new(TokenService::new(
+                "secret",
+                "sultan",
+                "sultan",
+                Duration::minutes(15),
+            )),
+            users,
+            Arc::new(SqliteRefreshTokenRepository::new(pool)),
+            Duration::days(30),
+            reuse_policy,
+        );
+        (service, user)
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_token() {
+        let (service, user) = setup(RefreshTokenReusePolicy::RevokeFamily).await;
+
+        let first = service.start_session(&user, None).await.unwrap();
+        let second = service.refresh(&first.refresh_token).await.unwrap();
+
+        assert_ne!(first.refresh_token, second.refresh_token);
+        assert!(service.refresh(&second.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_replayed_token_revokes_family() {
+        let (service, user) = setup(RefreshTokenReusePolicy::RevokeFamily).await;
+
+        let first = service.start_session(&user, None).await.unwrap();
+        let second = service.refresh(&first.refresh_token).await.unwrap();
+
+        let replay = service.refresh(&first.refresh_token).await;
+        assert!(matches!(replay, Err(AppError::InvalidCredentials)));
+
+        // The legitimately rotated token is gone too, forcing a fresh login
+        let after = service.refresh(&second.refresh_token).await;
+        assert!(matches!(after, Err(AppError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn test_replay_leaves_other_families_alone() {
+        let (service, user) = setup(RefreshTokenReusePolicy::RevokeFamily).await;
+
+        let stolen = service.start_session(&user, None).await.unwrap();
+        let other_device = service.start_session(&user, None).await.unwrap();
+        service.refresh(&stolen.refresh_token).await.unwrap();
+        let _ = service.refresh(&stolen.refresh_token).await;
+
+        assert!(service.refresh(&other_device.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reject_policy_keeps_family() {
+        let (service, user) = setup(RefreshTokenReusePolicy::Reject).await;
+
+        let first = service.start_session(&user, None).await.unwrap();
+        let second = service.refresh(&first.refresh_token).await.unwrap();
+
+        let replay = service.refresh(&first.refresh_token).await;
+        assert!(matches!(replay, Err(AppError::InvalidCredentials)));
+        assert!(service.refresh(&second.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_session_label() {
+        let (service, user) = setup(RefreshTokenReusePolicy::RevokeFamily).await;
+
+        let first = service
+            .start_session(&user, Some("Phone/1.0"))
+            .await
+            .unwrap();
+        service.refresh(&first.refresh_token).await.unwrap();
+
+        let sessions = service.list_sessions(user.id).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].user_agent.as_deref(), Some("Phone/1.0"));
+        assert!(sessions[0].last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rejects_token_once_clock_passes_expiry() {
+        let (service, user) = setup(RefreshTokenReusePolicy::RevokeFamily).await;
+        let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+        let service = service.with_clock(clock.clone());
+        let first = service.start_session(&user, None).await.unwrap();
+
+        clock.advance(chrono::Duration::days(29));
+        let second = service.refresh(&first.refresh_token).await.unwrap();
+
+        clock.advance(chrono::Duration::days(31));
+        let result = service.refresh(&second.refresh_token).await;
+
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+        assert!(service.list_sessions(user.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_sessions_logs_out_every_device() {
+        let (service, user) = setup(RefreshTokenReusePolicy::RevokeFamily).await;
+        let sessions = [
+            service.start_session(&user, Some("laptop")).await.unwrap(),
+            service.start_session(&user, Some("phone")).await.unwrap(),
+            service.start_session(&user, None).await.unwrap(),
+        ];
+
+        let revoked = service.revoke_all_sessions(user.id).await.unwrap();
+
+        assert_eq!(revoked, 3);
+        assert!(service.list_sessions(user.id).await.unwrap().is_empty());
+        for session in &sessions {
+            let refreshed = service.refresh(&session.refresh_token).await;
+            assert!(matches!(refreshed, Err(AppError::InvalidCredentials)));
+        }
+        assert_eq!(service.revoke_all_sessions(user.id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_token_is_rejected() {
+        let (service, _) = setup(RefreshTokenReusePolicy::RevokeFamily).await;
+
+        let result = service.refresh("not-a-token").await;
+
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+}
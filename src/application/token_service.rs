@@ -0,0 +1,342 @@
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        clock::{Clock, SystemClock},
+    },
+    config::JwtKey,
+    domain::user::{Role, User},
+};
+
+// ============================================================================
+// Access Token Claims
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: Uuid,
+    pub username: String,
+    pub role: Role,
+    pub iss: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+// ============================================================================
+// Token Service
+// ============================================================================
+
+pub struct TokenService {
+    encoding_key: EncodingKey,
+    /// Written to the `kid` header of issued tokens, `None` while signing with the plain secret
+    key_id: Option<String>,
+    /// Verifies tokens that carry no `kid`, while no `kid` keys are configured
+    decoding_key: DecodingKey,
+    /// Verifies tokens by their `kid`
+    decoding_keys: HashMap<String, DecodingKey>,
+    /// Key from `decoding_keys` that verifies tokens without a `kid` once there are any
+    legacy_key_id: Option<String>,
+    issuer: String,
+    audience: String,
+    access_token_ttl: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl TokenService {
+    pub fn new(secret: &str, issuer: &str, audience: &str, access_token_ttl: Duration) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            key_id: None,
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            decoding_keys: HashMap::new(),
+            legacy_key_id: None,
+            issuer: issuer.to_string(),
+            audience: audience.to_string(),
+            access_token_ttl,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Read the time for `iat` and expiry checks from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Accept tokens whose `kid` names one of `keys`
+    pub fn with_verification_keys(mut self, keys: &[JwtKey]) -> Self {
+        for key in keys {
+            let secret = key.secret.expose_secret().as_bytes();
+            self.decoding_keys
+                .insert(key.id.clone(), DecodingKey::from_secret(secret));
+        }
+        self
+    }
+
+    /// Verify tokens without a `kid` against the key named `id`, instead of refusing them
+    /// Only consulted once keys are configured, so dropping that key retires those tokens
+    pub fn with_legacy_key_id(mut self, id: Option<&str>) -> Self {
+        self.legacy_key_id = id.map(str::to_string);
+        self
+    }
+
+    /// Sign new tokens with `key`, naming it in their `kid` header
+    pub fn with_signing_key(mut self, key: &JwtKey) -> Self {
+        self.encoding_key = EncodingKey::from_secret(key.secret.expose_secret().as_bytes());
+        self.key_id = Some(key.id.clone());
+        self.with_verification_keys(std::slice::from_ref(key))
+    }
+
+    /// Sign a short-lived access token for the given user
+    pub fn issue_access_token(&self, user: &User) -> AppResult<String> {
+        let iat = self.clock.now().timestamp();
+        let claims = Claims {
+            sub: user.id,
+            username: user.username.to_string(),
+            role: user.role,
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            iat,
+            exp: iat + self.access_token_ttl.whole_seconds(),
+        };
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = self.key_id.clone();
+        encode(&header, &claims, &self.encoding_key)
+            .map_err(|e| AppError::Internal(format!("Token signing failed: {}", e)))
+    }
+
+    /// Check the signature, expiry, issuer and audience of an access token and return its claims
+    pub fn verify_access_token(&self, token: &str) -> AppResult<Claims> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        // jsonwebtoken reads the system clock, so expiry is checked against ours below
+        validation.validate_exp = false;
+
+        let kid = decode_header(token)
+            .map_err(|_| AppError::InvalidCredentials)?
+            .kid;
+        let decoding_key = match (kid, &self.legacy_key_id) {
+            (Some(kid), _) => self.decoding_keys.get(&kid),
+            (None, _) if self.decoding_keys.is_empty() => Some(&self.decoding_key),
+            (None, Some(legacy)) => self.decoding_keys.get(legacy),
+            // A rotated secret must stop verifying, so its tokens can't be let through here
+            (None, None) => None,
+        }
+        .ok_or(AppError::InvalidCredentials)?;
+
+        let claims = decode::<Claims>(token, decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|_| AppError::InvalidCredentials)?;
+
+        if claims.exp < self.clock.now().timestamp() - validation.leeway as i64 {
+            return Err(AppError::InvalidCredentials);
+        }
+
+        Ok(claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::clock::MockClock,
+        domain::{email::Email, username::Username},
+    };
+    use chrono::Utc;
+
+    fn test_user(role: Role) -> User {
+        User {
+            id: Uuid::new_v4(),
+            username: Username::parse("testuser").unwrap(),
+            email: Email::parse("testuser@gmail.com").unwrap(),
+            password_hash: "hash".into(),
+            role,
+            email_verified: false,
+            last_login_at: None,
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+        }
+    }
+
+    fn test_service(secret: &str, audience: &str) -> TokenService {
+        TokenService::new(secret, "sultan", audience, Duration::minutes(15))
+    }
+
+    fn jwt_key(id: &str, secret: &str) -> JwtKey {
+        JwtKey {
+            id: id.to_string(),
+            secret: secret.into(),
+        }
+    }
+
+    #[test]
+    fn test_issue_and_verify_access_token() {
+        let service = test_service("secret", "sultan-api");
+        let user = test_user(Role::Admin);
+
+        let token = service.issue_access_token(&user).unwrap();
+        let claims = service.verify_access_token(&token).unwrap();
+
+        assert_eq!(claims.sub, user.id);
+        assert_eq!(claims.username, "testuser");
+        assert_eq!(claims.role, Role::Admin);
+    }
+
+    #[test]
+    fn test_verify_rejects_token_signed_with_other_secret() {
+        let token = test_service("other", "sultan-api")
+            .issue_access_token(&test_user(Role::User))
+            .unwrap();
+
+        let result = test_service("secret", "sultan-api").verify_access_token(&token);
+
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn test_verify_accepts_matching_issuer_and_audience() {
+        let token = test_service("secret", "sultan-api")
+            .issue_access_token(&test_user(Role::User))
+            .unwrap();
+
+        let claims = test_service("secret", "sultan-api")
+            .verify_access_token(&token)
+            .unwrap();
+
+        assert_eq!(claims.iss, "sultan");
+        assert_eq!(claims.aud, "sultan-api");
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_audience() {
+        let token = test_service("secret", "billing-api")
+            .issue_access_token(&test_user(Role::User))
+            .unwrap();
+
+        let result = test_service("secret", "sultan-api").verify_access_token(&token);
+
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_issuer() {
+        let token = TokenService::new(
+            "secret",
+            "someone-else",
+            "sultan-api",
+            Duration::minutes(15),
+        )
+        .issue_access_token(&test_user(Role::User))
+        .unwrap();
+
+        let result = test_service("secret", "sultan-api").verify_access_token(&token);
+
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn test_verify_rejects_token_once_clock_passes_expiry() {
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let service = test_service("secret", "sultan-api").with_clock(clock.clone());
+        let token = service.issue_access_token(&test_user(Role::User)).unwrap();
+
+        clock.advance(chrono::Duration::minutes(14));
+        assert!(service.verify_access_token(&token).is_ok());
+
+        // Past the 15 minute TTL and the default 60 second leeway
+        clock.advance(chrono::Duration::minutes(3));
+        let result = service.verify_access_token(&token);
+
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn test_rotated_keys_verify_by_kid() {
+        let old_key = jwt_key("2025-01", "old-secret");
+        let new_key = jwt_key("2025-06", "new-secret");
+        let user = test_user(Role::User);
+        let before_rotation = test_service("secret", "sultan-api")
+            .with_signing_key(&old_key)
+            .issue_access_token(&user)
+            .unwrap();
+        let service = test_service("secret", "sultan-api")
+            .with_verification_keys(&[old_key, new_key.clone()])
+            .with_signing_key(&new_key);
+
+        let token = service.issue_access_token(&user).unwrap();
+
+        assert_eq!(
+            decode_header(&token).unwrap().kid.as_deref(),
+            Some("2025-06")
+        );
+        assert_eq!(service.verify_access_token(&token).unwrap().sub, user.id);
+        assert_eq!(
+            service.verify_access_token(&before_rotation).unwrap().sub,
+            user.id
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_unknown_kid() {
+        let token = test_service("secret", "sultan-api")
+            .with_signing_key(&jwt_key("retired", "secret"))
+            .issue_access_token(&test_user(Role::User))
+            .unwrap();
+
+        // Same secret, but the key id is no longer configured
+        let result = test_service("secret", "sultan-api")
+            .with_verification_keys(&[jwt_key("current", "secret")])
+            .verify_access_token(&token);
+
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn test_tokens_without_kid_are_refused_once_keys_are_configured() {
+        let token = test_service("secret", "sultan-api")
+            .issue_access_token(&test_user(Role::User))
+            .unwrap();
+
+        let service = test_service("secret", "sultan-api")
+            .with_signing_key(&jwt_key("2025-06", "new-secret"));
+
+        let result = service.verify_access_token(&token);
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+
+    #[test]
+    fn test_tokens_without_kid_verify_until_legacy_key_is_dropped() {
+        let legacy_key = jwt_key("legacy", "secret");
+        let new_key = jwt_key("2025-06", "new-secret");
+        let token = test_service("secret", "sultan-api")
+            .issue_access_token(&test_user(Role::User))
+            .unwrap();
+
+        let during_rotation = test_service("secret", "sultan-api")
+            .with_verification_keys(&[legacy_key, new_key.clone()])
+            .with_legacy_key_id(Some("legacy"))
+            .with_signing_key(&new_key);
+        assert!(during_rotation.verify_access_token(&token).is_ok());
+
+        // Still named as the legacy key, but no longer configured
+        let after_rotation = test_service("secret", "sultan-api")
+            .with_verification_keys(std::slice::from_ref(&new_key))
+            .with_legacy_key_id(Some("legacy"))
+            .with_signing_key(&new_key);
+        let result = after_rotation.verify_access_token(&token);
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+}
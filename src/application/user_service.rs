@@ -1,11 +1,40 @@
 use secrecy::{ExposeSecret, SecretString};
-use std::sync::Arc;
-use tracing::{info, instrument};
+use std::{
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+use time::Duration;
+use tracing::{Span, info, instrument, warn};
+use uuid::Uuid;
 
 #[cfg(test)]
 use async_trait::async_trait;
 
-use crate::{application::app_error::AppResult, persistence::user_repo::UserRepository};
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        clock::{Clock, SystemClock},
+        disposable_domains::DisposableDomains,
+        email_verification_service::EmailVerificationService,
+        events::{DomainEvent, EventSink, NoopEventSink},
+        failed_login_cache::FailedLoginCache,
+        hash_limiter::HashLimiter,
+        login_lockout::LoginLockout,
+        registration_limiter::RegistrationLimiter,
+    },
+    config::LoginIdentifier,
+    crypto::token::hash_token,
+    domain::{
+        email::Email,
+        password::{DEFAULT_PASSWORD_MAX_BYTES, check_password_size, validate_password_strength},
+        user::{Role, User},
+        username::Username,
+    },
+    persistence::{
+        invite_code_repo::InviteCodeRepository,
+        user_repo::{UserFilter, UserRepository},
+    },
+};
 
 // ============================================================================
 // Port Traits (Interfaces for dependencies)
@@ -13,6 +42,9 @@ use crate::{application::app_error::AppResult, persistence::user_repo::UserRepos
 
 pub trait PasswordHasher: Send + Sync {
     fn hash_password(&self, password: &str) -> AppResult<String>;
+
+    /// Check a password against a stored hash
+    fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool>;
 }
 
 // ============================================================================
@@ -23,29 +55,545 @@ pub trait PasswordHasher: Send + Sync {
 pub struct UserService {
     hasher: Arc<dyn PasswordHasher>,
     repository: Arc<dyn UserRepository>,
+    email_verification: Option<Arc<EmailVerificationService>>,
+    registration_limiter: Option<Arc<RegistrationLimiter>>,
+    registration_enabled: bool,
+    allowed_email_domains: Arc<[String]>,
+    disposable_domains: Option<Arc<DisposableDomains>>,
+    invite_codes: Option<Arc<dyn InviteCodeRepository>>,
+    login_identifier: LoginIdentifier,
+    default_role: Role,
+    login_lockout: Option<Arc<LoginLockout>>,
+    failed_login_cache: Option<Arc<FailedLoginCache>>,
+    failed_login_delay: Duration,
+    max_password_bytes: usize,
+    hash_limiter: Arc<HashLimiter>,
+    events: Arc<dyn EventSink>,
+    clock: Arc<dyn Clock>,
+    dummy_hash: Arc<OnceLock<String>>,
 }
 
 impl UserService {
     pub fn new(hasher: Arc<dyn PasswordHasher>, repository: Arc<dyn UserRepository>) -> Self {
-        Self { hasher, repository }
+        Self {
+            hasher,
+            repository,
+            email_verification: None,
+            registration_limiter: None,
+            registration_enabled: true,
+            allowed_email_domains: Arc::new([]),
+            disposable_domains: None,
+            invite_codes: None,
+            login_identifier: LoginIdentifier::Username,
+            default_role: Role::User,
+            login_lockout: None,
+            failed_login_cache: None,
+            failed_login_delay: Duration::ZERO,
+            max_password_bytes: DEFAULT_PASSWORD_MAX_BYTES,
+            hash_limiter: Arc::new(HashLimiter::unbounded()),
+            events: Arc::new(NoopEventSink),
+            clock: Arc::new(SystemClock),
+            dummy_hash: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Issue an email verification token for every new registration
+    pub fn with_email_verification(mut self, service: Arc<EmailVerificationService>) -> Self {
+        self.email_verification = Some(service);
+        self
+    }
+
+    /// Send domain events, such as new registrations, to `sink`
+    pub fn with_event_sink(mut self, sink: Arc<dyn EventSink>) -> Self {
+        self.events = sink;
+        self
+    }
+
+    /// Open or close self-registration, closed registration is refused before any hashing
+    pub fn with_registration_enabled(mut self, enabled: bool) -> Self {
+        self.registration_enabled = enabled;
+        self
+    }
+
+    /// Only admit registrations whose email is at one of `domains`, given lowercase
+    /// An empty list admits every domain
+    pub fn with_allowed_email_domains(mut self, domains: Vec<String>) -> Self {
+        self.allowed_email_domains = domains.into();
+        self
+    }
+
+    /// Refuse registrations with an email at one of `domains`
+    pub fn with_disposable_domains(mut self, domains: Arc<DisposableDomains>) -> Self {
+        self.disposable_domains = Some(domains);
+        self
+    }
+
+    /// Only admit registrations that bring an unused invite code from `invite_codes`
+    pub fn with_invite_codes(mut self, invite_codes: Arc<dyn InviteCodeRepository>) -> Self {
+        self.invite_codes = Some(invite_codes);
+        self
+    }
+
+    /// Cap how many accounts can be registered per email domain per hour
+    pub fn with_registration_limiter(mut self, limiter: Arc<RegistrationLimiter>) -> Self {
+        self.registration_limiter = Some(limiter);
+        self
+    }
+
+    /// Find the user logging in by username, email, or whichever the identifier looks like
+    pub fn with_login_identifier(mut self, identifier: LoginIdentifier) -> Self {
+        self.login_identifier = identifier;
+        self
+    }
+
+    /// Give every registered user `role` instead of `user`
+    pub fn with_default_role(mut self, role: Role) -> Self {
+        self.default_role = role;
+        self
+    }
+
+    /// Lock accounts after repeated failed logins
+    pub fn with_login_lockout(mut self, lockout: Arc<LoginLockout>) -> Self {
+        self.login_lockout = Some(lockout);
+        self
+    }
+
+    /// Answer identical rapid retries of a failed login without verifying the password again
+    pub fn with_failed_login_cache(mut self, cache: Arc<FailedLoginCache>) -> Self {
+        self.failed_login_cache = Some(cache);
+        self
+    }
+
+    /// Pause this long before answering a failed login
+    /// Only the failing request waits, other requests are served meanwhile
+    pub fn with_failed_login_delay(mut self, delay: Duration) -> Self {
+        self.failed_login_delay = delay;
+        self
+    }
+
+    /// Refuse new passwords longer than this many bytes with a 422, before hashing them
+    pub fn with_max_password_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_password_bytes = max_bytes;
+        self
+    }
+
+    /// Share a cap on concurrent password hashes with other services
+    pub fn with_hash_limiter(mut self, limiter: Arc<HashLimiter>) -> Self {
+        self.hash_limiter = limiter;
+        self
+    }
+
+    /// Read the time for soft deletion and purging from `clock`
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 
-    #[instrument(skip(self, password))]
     pub async fn register_user(
         &self,
         username: &str,
         email: &str,
         password: &SecretString,
-    ) -> AppResult<()> {
+    ) -> AppResult<Uuid> {
+        self.register_user_with_invite(username, email, password, None)
+            .await
+    }
+
+    /// Register a user and return their id, consuming `invite_code` when registration is
+    /// invite-only. The code is ignored otherwise
+    #[instrument(skip(self, password, invite_code), fields(hash_ms, db_ms))]
+    pub async fn register_user_with_invite(
+        &self,
+        username: &str,
+        email: &str,
+        password: &SecretString,
+        invite_code: Option<&str>,
+    ) -> AppResult<Uuid> {
+        if !self.registration_enabled {
+            return Err(AppError::Forbidden("Registration is closed".into()));
+        }
+        let invite = match &self.invite_codes {
+            Some(invite_codes) => {
+                let code = invite_code.ok_or_else(|| {
+                    AppError::Forbidden("Registration requires an invite code".into())
+                })?;
+                Some((invite_codes, hash_token(code)))
+            }
+            None => None,
+        };
         info!("Registering user: {}", username);
 
-        let hash = self.hasher.hash_password(password.expose_secret())?;
-        self.repository.create_user(username, email, &hash).await?;
+        let username = Username::parse(username)?;
+        let email = Email::parse(email)?;
+        if !self.allowed_email_domains.is_empty()
+            && !self
+                .allowed_email_domains
+                .iter()
+                .any(|domain| domain == email.domain())
+        {
+            return Err(AppError::Forbidden(
+                "Registration is not open to this email domain".into(),
+            ));
+        }
+        if let Some(disposable_domains) = &self.disposable_domains
+            && disposable_domains.contains(email.domain())
+        {
+            return Err(AppError::Unprocessable(
+                "Disposable email addresses are not accepted".into(),
+            ));
+        }
+        check_password_size(password, self.max_password_bytes)?;
+        validate_password_strength(password.expose_secret())?;
+        if let Some(limiter) = &self.registration_limiter {
+            limiter.check(&email).await?;
+        }
+
+        let started = Instant::now();
+        let hash = self.hash_password(password).await?;
+        Span::current().record("hash_ms", started.elapsed().as_millis() as u64);
+
+        let started = Instant::now();
+        let user_id = match invite {
+            Some((invite_codes, code_hash)) => invite_codes
+                .register_with_code(
+                    &code_hash,
+                    self.clock.now().naive_utc(),
+                    &username,
+                    &email,
+                    &hash,
+                    self.default_role,
+                )
+                .await?
+                .ok_or_else(|| {
+                    AppError::Forbidden("Invite code is invalid or already used".into())
+                })?,
+            None => {
+                self.repository
+                    .create_user_with_role(&username, &email, &hash, self.default_role)
+                    .await?
+            }
+        };
+        Span::current().record("db_ms", started.elapsed().as_millis() as u64);
+
+        self.events
+            .emit(DomainEvent::UserRegistered {
+                id: user_id,
+                username: username.clone(),
+                email: email.clone(),
+            })
+            .await;
+
+        if let Some(email_verification) = &self.email_verification {
+            // Nothing delivers email yet, so the token is only logged
+            let token = email_verification.issue_token(user_id).await?;
+            info!(%user_id, %token, "Email verification token issued");
+        }
 
         info!("User registered successfully: {}", username);
 
+        Ok(user_id)
+    }
+
+    /// Register a user, or return the existing one if the username is already theirs under the
+    /// same email, so provisioning can be retried. An existing user keeps their password, and a
+    /// username taken with a different email is still a conflict
+    #[instrument(skip(self, password))]
+    pub async fn get_or_create_user(
+        &self,
+        username: &str,
+        email: &str,
+        password: &SecretString,
+    ) -> AppResult<User> {
+        let conflict = match self.register_user(username, email, password).await {
+            Ok(user_id) => return self.get_user(&user_id).await,
+            Err(AppError::Conflict(message)) => message,
+            Err(e) => return Err(e),
+        };
+
+        let email = Email::parse(email)?;
+        match self.repository.get_user_by_username(username).await? {
+            Some(user) if user.email == email => {
+                info!(user_id = %user.id, "User already provisioned: {}", username);
+                Ok(user)
+            }
+            _ => Err(AppError::Conflict(conflict)),
+        }
+    }
+
+    /// Log in with a username or email, as `with_login_identifier` allows
+    #[instrument(skip(self, password))]
+    pub async fn login(&self, identifier: &str, password: &SecretString) -> AppResult<User> {
+        // Bounds the hashing work like registration does, before any lookup
+        check_password_size(password, self.max_password_bytes)?;
+        let result = self.authenticate(identifier, password).await;
+        // Applied after the dummy-hash path too, so unknown users wait just as long
+        if matches!(result, Err(AppError::InvalidCredentials))
+            && self.failed_login_delay.is_positive()
+        {
+            tokio::time::sleep(self.failed_login_delay.unsigned_abs()).await;
+        }
+        result
+    }
+
+    async fn authenticate(&self, identifier: &str, password: &SecretString) -> AppResult<User> {
+        let Some(mut user) = self.find_login_user(identifier).await? else {
+            // Spend the same hashing time as a real check so unknown users can't be timed
+            let dummy_hash = self.dummy_hash().await?.to_string();
+            self.verify_login_attempt(identifier, password, dummy_hash)
+                .await?;
+            return Err(AppError::InvalidCredentials);
+        };
+        // Lock the account rather than the identifier, so switching to the email doesn't help
+        let username = user.username.to_string();
+
+        // Verify even when locked so a locked account answers in the same time and shape
+        // as a wrong password, rather than revealing the lockout. Cached failures still
+        // count towards the lockout below
+        let verified = self
+            .verify_login_attempt(
+                identifier,
+                password,
+                user.password_hash.expose().to_string(),
+            )
+            .await?;
+        if let Some(lockout) = &self.login_lockout {
+            if lockout.is_locked(&username).await? {
+                warn!(user_id = %user.id, "Login attempt on locked account");
+                return Err(AppError::InvalidCredentials);
+            }
+            if verified {
+                lockout.record_success(&username).await?;
+            } else {
+                lockout.record_failure(&username).await?;
+            }
+        }
+        if !verified {
+            return Err(AppError::InvalidCredentials);
+        }
+
+        // Losing the timestamp isn't worth failing an otherwise valid login
+        let now = self.clock.now().naive_utc();
+        match self.repository.touch_last_login(&user.id, now).await {
+            Ok(()) => user.last_login_at = Some(now),
+            Err(e) => warn!(error = ?e, user_id = %user.id, "Failed to record last login"),
+        }
+
+        info!("User logged in: {}", username);
+
+        Ok(user)
+    }
+
+    /// Load a user by id
+    pub async fn get_user(&self, id: &Uuid) -> AppResult<User> {
+        self.repository
+            .get_user_by_id(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))
+    }
+
+    /// List a page of users matching every set filter, newest first
+    pub async fn list_users(
+        &self,
+        filter: &UserFilter,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<Vec<User>> {
+        self.repository.list_users(filter, limit, offset).await
+    }
+
+    /// Give a user another role, on behalf of an admin, returning them and whether it changed
+    /// Demoting the last admin is a conflict, nobody could promote anyone again
+    #[instrument(skip(self))]
+    pub async fn set_role(&self, id: &Uuid, role: Role) -> AppResult<(User, bool)> {
+        let mut user = self.get_user(id).await?;
+        if user.role == role {
+            return Ok((user, false));
+        }
+        if !self.repository.set_role(id, role).await? {
+            // The check ran in the same statement as the write, this only says why it failed
+            return Err(match user.role {
+                Role::Admin => AppError::Conflict("Cannot demote the last admin".into()),
+                Role::User => AppError::NotFound("User not found".into()),
+            });
+        }
+        user.role = role;
+
+        info!(user_id = %id, %role, "User role changed");
+
+        Ok((user, true))
+    }
+
+    /// Get a user for admin tooling, soft-deleted or not
+    /// `NotFound` only when the user never existed or has been purged
+    #[instrument(skip(self))]
+    pub async fn get_user_including_deleted(&self, id: &Uuid) -> AppResult<User> {
+        self.repository
+            .get_user_by_id_including_deleted(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".into()))
+    }
+
+    /// Undo a soft delete, letting the user log in again
+    #[instrument(skip(self))]
+    pub async fn restore_user(&self, id: &Uuid) -> AppResult<()> {
+        let restored = self.repository.restore_user(id).await?;
+        if !restored {
+            return Err(AppError::NotFound("No deleted user with this id".into()));
+        }
+
+        info!(user_id = %id, "User restored");
+
+        Ok(())
+    }
+
+    /// Soft-delete a user, they can no longer log in or be looked up
+    /// Deleting the last admin is a conflict, like demoting them
+    #[instrument(skip(self))]
+    pub async fn delete_user(&self, id: &Uuid) -> AppResult<()> {
+        let deleted = self
+            .repository
+            .soft_delete_user(id, self.clock.now().naive_utc())
+            .await?;
+        if !deleted {
+            let user = self.repository.get_user_by_id(id).await?;
+            return Err(match user {
+                Some(user) if user.role == Role::Admin => {
+                    AppError::Conflict("Cannot delete the last admin".into())
+                }
+                _ => AppError::NotFound("User not found".into()),
+            });
+        }
+
+        info!(user_id = %id, "User soft-deleted");
+
         Ok(())
     }
+
+    /// Permanently remove users soft-deleted more than `older_than` ago
+    #[instrument(skip(self))]
+    pub async fn purge_deleted_users(&self, older_than: Duration) -> AppResult<u64> {
+        let purged = self
+            .repository
+            .purge_deleted_users(older_than, self.clock.now().naive_utc())
+            .await?;
+
+        info!(purged, "Purged soft-deleted users");
+
+        Ok(purged)
+    }
+
+    /// Create an admin account unless one already exists
+    /// Returns whether an account was created, so running it on every startup is safe
+    #[instrument(skip(self, password))]
+    pub async fn seed_admin(
+        &self,
+        username: &str,
+        email: &str,
+        password: &SecretString,
+    ) -> AppResult<bool> {
+        let admins = UserFilter {
+            role: Some(Role::Admin),
+            ..Default::default()
+        };
+        if !self.repository.list_users(&admins, 1, 0).await?.is_empty() {
+            return Ok(false);
+        }
+
+        let username = Username::parse(username)?;
+        let email = Email::parse(email)?;
+        check_password_size(password, self.max_password_bytes)?;
+        validate_password_strength(password.expose_secret())?;
+
+        let hash = self.hash_password(password).await?;
+        // One insert, so a crash can't leave a plain user holding the admin's name
+        let user_id = self
+            .repository
+            .create_user_with_role(&username, &email, &hash, Role::Admin)
+            .await?;
+
+        info!(%user_id, "Seeded admin user: {}", username);
+
+        Ok(true)
+    }
+
+    /// The user an identifier from the login form names, if any
+    async fn find_login_user(&self, identifier: &str) -> AppResult<Option<User>> {
+        let by_email = match self.login_identifier {
+            LoginIdentifier::Username => false,
+            LoginIdentifier::Email => true,
+            // Trimmed like `Email::parse` does, so padding matches in either mode
+            LoginIdentifier::Either => {
+                return self
+                    .repository
+                    .get_user_by_identifier(identifier.trim())
+                    .await;
+            }
+        };
+        if !by_email {
+            return self.repository.get_user_by_username(identifier).await;
+        }
+
+        // Nobody can hold a malformed address, so it goes down the unknown user path
+        match Email::parse(identifier) {
+            Ok(email) => self.repository.get_user_by_email(&email).await,
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Compute the throwaway hash unknown logins verify against ahead of time, through
+    /// the hash limiter like every other hash, so the first request doesn't pay for it
+    pub async fn warm_up_hasher(&self) -> AppResult<()> {
+        self.dummy_hash().await.map(|_| ())
+    }
+
+    /// Hash of a throwaway password, computed once, to verify against when no user matches
+    async fn dummy_hash(&self) -> AppResult<&str> {
+        if let Some(hash) = self.dummy_hash.get() {
+            return Ok(hash);
+        }
+        let hash = self
+            .hash_password(&"dummy-password-for-timing".into())
+            .await?;
+        Ok(self.dummy_hash.get_or_init(|| hash))
+    }
+
+    async fn hash_password(&self, password: &SecretString) -> AppResult<String> {
+        let hasher = self.hasher.clone();
+        let password = password.clone();
+        self.hash_limiter
+            .run(move || hasher.hash_password(password.expose_secret()))
+            .await
+    }
+
+    /// Verify a login, skipping the hash for an attempt that failed moments ago
+    async fn verify_login_attempt(
+        &self,
+        username: &str,
+        password: &SecretString,
+        hash: String,
+    ) -> AppResult<bool> {
+        let Some(cache) = &self.failed_login_cache else {
+            return self.verify_password(password, hash).await;
+        };
+        if cache.is_known_failure(username, &hash, password.expose_secret()) {
+            return Ok(false);
+        }
+
+        let verified = self.verify_password(password, hash.clone()).await?;
+        if !verified {
+            cache.remember_failure(username, &hash, password.expose_secret());
+        }
+
+        Ok(verified)
+    }
+
+    async fn verify_password(&self, password: &SecretString, hash: String) -> AppResult<bool> {
+        let hasher = self.hasher.clone();
+        let password = password.clone();
+        self.hash_limiter
+            .run(move || hasher.verify_password(password.expose_secret(), &hash))
+            .await
+    }
 }
 
 // ============================================================================
@@ -55,20 +603,50 @@ impl UserService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
+    use tracing::{
+        Subscriber,
+        field::{Field, Visit},
+        span::{Id, Record},
+    };
+    use tracing_subscriber::{
+        Layer,
+        layer::{Context, SubscriberExt},
+        registry::Registry,
+    };
 
     struct MockUserRepository;
 
     #[async_trait]
     impl UserRepository for MockUserRepository {
-        async fn create_user(
+        async fn create_user_with_role(
             &self,
-            username: &str,
-            email: &str,
+            username: &Username,
+            email: &Email,
             _password_hash: &str,
-        ) -> AppResult<()> {
-            assert_eq!(username, "testuser");
-            assert_eq!(email, "testuser@gmail.com");
-            Ok(())
+            _role: Role,
+        ) -> AppResult<uuid::Uuid> {
+            assert_eq!(username.as_str(), "testuser");
+            assert_eq!(email.as_str(), "testuser@gmail.com");
+            Ok(uuid::Uuid::new_v4())
+        }
+        async fn get_user_by_id(
+            &self,
+            _id: &uuid::Uuid,
+        ) -> AppResult<Option<crate::domain::user::User>> {
+            Ok(None)
+        }
+        async fn get_user_by_id_including_deleted(
+            &self,
+            _id: &uuid::Uuid,
+        ) -> AppResult<Option<crate::domain::user::User>> {
+            Ok(None)
+        }
+        async fn get_users_by_ids(
+            &self,
+            _ids: &[uuid::Uuid],
+        ) -> AppResult<Vec<crate::domain::user::User>> {
+            Ok(Vec::new())
         }
         async fn get_user_by_username(
             &self,
@@ -76,6 +654,53 @@ mod tests {
         ) -> AppResult<Option<crate::domain::user::User>> {
             Ok(None)
         }
+        async fn get_user_by_email(
+            &self,
+            _email: &Email,
+        ) -> AppResult<Option<crate::domain::user::User>> {
+            Ok(None)
+        }
+        async fn get_user_by_identifier(
+            &self,
+            _identifier: &str,
+        ) -> AppResult<Option<crate::domain::user::User>> {
+            Ok(None)
+        }
+        async fn touch_last_login(
+            &self,
+            _id: &uuid::Uuid,
+            _at: chrono::NaiveDateTime,
+        ) -> AppResult<()> {
+            Ok(())
+        }
+        async fn set_role(&self, _id: &uuid::Uuid, _role: Role) -> AppResult<bool> {
+            Ok(false)
+        }
+        async fn list_users(
+            &self,
+            _filter: &UserFilter,
+            _limit: u32,
+            _offset: u32,
+        ) -> AppResult<Vec<crate::domain::user::User>> {
+            Ok(Vec::new())
+        }
+        async fn soft_delete_user(
+            &self,
+            _id: &uuid::Uuid,
+            _deleted_at: chrono::NaiveDateTime,
+        ) -> AppResult<bool> {
+            Ok(false)
+        }
+        async fn restore_user(&self, _id: &uuid::Uuid) -> AppResult<bool> {
+            Ok(false)
+        }
+        async fn purge_deleted_users(
+            &self,
+            _older_than: Duration,
+            _now: chrono::NaiveDateTime,
+        ) -> AppResult<u64> {
+            Ok(0)
+        }
     }
 
     struct MockPasswordHasher;
@@ -84,6 +709,20 @@ mod tests {
         fn hash_password(&self, password: &str) -> AppResult<String> {
             Ok(format!("{}_hashed", password))
         }
+        fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
+            Ok(format!("{}_hashed", password) == hash)
+        }
+    }
+
+    struct PanickingPasswordHasher;
+
+    impl PasswordHasher for PanickingPasswordHasher {
+        fn hash_password(&self, _password: &str) -> AppResult<String> {
+            panic!("hash_password must not be called for invalid input");
+        }
+        fn verify_password(&self, _password: &str, _hash: &str) -> AppResult<bool> {
+            panic!("verify_password must not be called for invalid input");
+        }
     }
 
     #[tokio::test]
@@ -96,4 +735,604 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[cfg(feature = "sqlite")]
+    async fn setup_sqlite_service() -> UserService {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        UserService::new(
+            Arc::new(MockPasswordHasher),
+            Arc::new(crate::persistence::SqliteUserRepository::new(pool)),
+        )
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_login_sets_last_login_at() {
+        let now = chrono::Utc::now() - chrono::Duration::days(3);
+        let clock = Arc::new(crate::application::clock::MockClock::new(now));
+        let service = setup_sqlite_service().await.with_clock(clock);
+        service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+
+        let before = service
+            .repository
+            .get_user_by_username("testuser")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(before.last_login_at.is_none());
+
+        let user = service
+            .login("testuser", &"password123".into())
+            .await
+            .unwrap();
+        assert_eq!(user.last_login_at, Some(now.naive_utc()));
+
+        let after = service
+            .repository
+            .get_user_by_username("testuser")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(after.last_login_at, Some(now.naive_utc()));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_login_rejects_wrong_password_and_unknown_user() {
+        let service = setup_sqlite_service().await;
+        service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+
+        let wrong_password = service.login("testuser", &"password124".into()).await;
+        assert!(matches!(wrong_password, Err(AppError::InvalidCredentials)));
+
+        let unknown_user = service.login("nobody", &"password123".into()).await;
+        assert!(matches!(unknown_user, Err(AppError::InvalidCredentials)));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_get_or_create_user_creates_then_returns_existing() {
+        let service = setup_sqlite_service().await;
+
+        let created = service
+            .get_or_create_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+        assert_eq!(created.username.as_str(), "testuser");
+
+        // A retry, with the email spelled differently, gets the same user back
+        let existing = service
+            .get_or_create_user("testuser", "TestUser@Gmail.com", &"password456".into())
+            .await
+            .unwrap();
+        assert_eq!(existing.id, created.id);
+        assert!(
+            service
+                .login("testuser", &"password123".into())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_get_or_create_user_conflicts_on_other_email() {
+        let service = setup_sqlite_service().await;
+        service
+            .get_or_create_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+
+        let result = service
+            .get_or_create_user("testuser", "someone@gmail.com", &"password123".into())
+            .await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+
+        // Taking another user's email under a new name is a conflict too
+        let result = service
+            .get_or_create_user("otheruser", "testuser@gmail.com", &"password123".into())
+            .await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_failed_login_waits_out_the_delay() {
+        let delay = Duration::milliseconds(200);
+        let service = setup_sqlite_service().await.with_failed_login_delay(delay);
+        service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+
+        for identifier in ["testuser", "nobody"] {
+            let start = Instant::now();
+            let result = service.login(identifier, &"password124".into()).await;
+            assert!(matches!(result, Err(AppError::InvalidCredentials)));
+            assert!(start.elapsed() >= delay.unsigned_abs(), "{}", identifier);
+        }
+
+        let start = Instant::now();
+        service
+            .login("testuser", &"password123".into())
+            .await
+            .unwrap();
+        assert!(start.elapsed() < delay.unsigned_abs());
+    }
+
+    /// Slow enough that a skipped verification would show up in the elapsed time
+    #[cfg(feature = "sqlite")]
+    #[derive(Default)]
+    struct SlowPasswordHasher(std::sync::atomic::AtomicUsize);
+
+    #[cfg(feature = "sqlite")]
+    const SLOW_VERIFY: std::time::Duration = std::time::Duration::from_millis(20);
+
+    #[cfg(feature = "sqlite")]
+    impl PasswordHasher for SlowPasswordHasher {
+        fn hash_password(&self, password: &str) -> AppResult<String> {
+            Ok(format!("{}_hashed", password))
+        }
+        fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::thread::sleep(SLOW_VERIFY);
+            Ok(format!("{}_hashed", password) == hash)
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_locked_login_looks_like_wrong_password() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .unwrap();
+        let hasher = Arc::new(SlowPasswordHasher::default());
+        let service = UserService::new(
+            hasher.clone(),
+            Arc::new(crate::persistence::SqliteUserRepository::new(pool)),
+        )
+        .with_login_lockout(Arc::new(LoginLockout::new(2)));
+        for username in ["locked", "unlocked"] {
+            service
+                .register_user(
+                    username,
+                    &format!("{}@gmail.com", username),
+                    &"password123".into(),
+                )
+                .await
+                .unwrap();
+        }
+        for _ in 0..2 {
+            let _ = service.login("locked", &"wrong-password1".into()).await;
+        }
+        let calls_before = hasher.0.load(std::sync::atomic::Ordering::SeqCst);
+
+        let started = Instant::now();
+        let wrong_password = service.login("unlocked", &"wrong-password1".into()).await;
+        let wrong_password_elapsed = started.elapsed();
+
+        // Even the right password is refused while locked
+        let started = Instant::now();
+        let locked = service.login("locked", &"password123".into()).await;
+        let locked_elapsed = started.elapsed();
+
+        assert!(matches!(wrong_password, Err(AppError::InvalidCredentials)));
+        assert!(matches!(locked, Err(AppError::InvalidCredentials)));
+        assert_eq!(
+            hasher.0.load(std::sync::atomic::Ordering::SeqCst) - calls_before,
+            2
+        );
+        assert!(wrong_password_elapsed >= SLOW_VERIFY);
+        assert!(locked_elapsed >= SLOW_VERIFY);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_identical_failed_logins_verify_once_within_ttl() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .unwrap();
+        let hasher = Arc::new(SlowPasswordHasher::default());
+        let service = UserService::new(
+            hasher.clone(),
+            Arc::new(crate::persistence::SqliteUserRepository::new(pool)),
+        )
+        .with_login_lockout(Arc::new(LoginLockout::new(3)))
+        .with_failed_login_cache(Arc::new(FailedLoginCache::new(
+            std::time::Duration::from_secs(60),
+        )));
+        service
+            .register_user("alice", "alice@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+        let verify_calls = || hasher.0.load(std::sync::atomic::Ordering::SeqCst);
+
+        for _ in 0..2 {
+            let result = service.login("alice", &"wrong-password1".into()).await;
+            assert!(matches!(result, Err(AppError::InvalidCredentials)));
+        }
+        assert_eq!(verify_calls(), 1);
+
+        let result = service.login("alice", &"password123".into()).await;
+        assert!(result.is_ok(), "a different attempt is verified");
+        assert_eq!(verify_calls(), 2);
+
+        // Cached failures still count towards the lockout
+        for _ in 0..3 {
+            let _ = service.login("alice", &"wrong-password1".into()).await;
+        }
+        let result = service.login("alice", &"password123".into()).await;
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_login_identifier_picks_username_or_email_lookup() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .unwrap();
+        let repository = Arc::new(crate::persistence::SqliteUserRepository::new(pool));
+        let hasher = Arc::new(SlowPasswordHasher::default());
+        UserService::new(hasher.clone(), repository.clone())
+            .register_user("alice", "alice@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+        let verify_calls = || hasher.0.load(std::sync::atomic::Ordering::SeqCst);
+
+        let cases = [
+            (LoginIdentifier::Username, "alice", true),
+            (LoginIdentifier::Username, "alice@gmail.com", false),
+            (LoginIdentifier::Email, "Alice@GMail.com", true),
+            (LoginIdentifier::Email, "alice", false),
+            (LoginIdentifier::Either, "alice", true),
+            (LoginIdentifier::Either, "alice@gmail.com", true),
+            (LoginIdentifier::Either, " Alice@GMail.com ", true),
+            (LoginIdentifier::Either, "nobody@gmail.com", false),
+            (LoginIdentifier::Either, "not@an@email", false),
+        ];
+        for (mode, identifier, found) in cases {
+            let service =
+                UserService::new(hasher.clone(), repository.clone()).with_login_identifier(mode);
+            let calls_before = verify_calls();
+
+            let result = service.login(identifier, &"password123".into()).await;
+
+            if found {
+                assert_eq!(result.unwrap().username.as_str(), "alice");
+            } else {
+                assert!(
+                    matches!(result, Err(AppError::InvalidCredentials)),
+                    "{:?} {}",
+                    mode,
+                    identifier
+                );
+            }
+            // Misses still verify against the dummy hash, so they take as long as hits
+            assert_eq!(
+                verify_calls() - calls_before,
+                1,
+                "{:?} {}",
+                mode,
+                identifier
+            );
+        }
+    }
+
+    /// Records the most hashes that were ever running at the same time
+    #[cfg(feature = "sqlite")]
+    #[derive(Default)]
+    struct ConcurrencyTrackingHasher {
+        running: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[cfg(feature = "sqlite")]
+    impl PasswordHasher for ConcurrencyTrackingHasher {
+        fn hash_password(&self, password: &str) -> AppResult<String> {
+            use std::sync::atomic::Ordering;
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(format!("{}_hashed", password))
+        }
+        fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
+            Ok(format!("{}_hashed", password) == hash)
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_concurrent_registrations_respect_hash_limit() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .unwrap();
+        let hasher = Arc::new(ConcurrencyTrackingHasher::default());
+        let service = Arc::new(
+            UserService::new(
+                hasher.clone(),
+                Arc::new(crate::persistence::SqliteUserRepository::new(pool)),
+            )
+            .with_hash_limiter(Arc::new(HashLimiter::new(3))),
+        );
+
+        let registrations: Vec<_> = (0..12)
+            .map(|i| {
+                let service = service.clone();
+                tokio::spawn(async move {
+                    service
+                        .register_user(
+                            &format!("user{}", i),
+                            &format!("user{}@gmail.com", i),
+                            &"password123".into(),
+                        )
+                        .await
+                })
+            })
+            .collect();
+        for registration in registrations {
+            registration.await.unwrap().unwrap();
+        }
+
+        let peak = hasher.peak.load(std::sync::atomic::Ordering::SeqCst);
+        assert!(peak <= 3, "{} hashes ran at once", peak);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[derive(Default)]
+    struct RecordingEventSink(Mutex<Vec<DomainEvent>>);
+
+    #[cfg(feature = "sqlite")]
+    #[async_trait]
+    impl EventSink for RecordingEventSink {
+        async fn emit(&self, event: DomainEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_register_user_emits_user_registered() {
+        let sink = Arc::new(RecordingEventSink::default());
+        let service = setup_sqlite_service().await.with_event_sink(sink.clone());
+
+        service
+            .register_user("testuser", "TestUser@Gmail.com", &"password123".into())
+            .await
+            .unwrap();
+
+        let user = service
+            .repository
+            .get_user_by_username("testuser")
+            .await
+            .unwrap()
+            .unwrap();
+        let events = sink.0.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![DomainEvent::UserRegistered {
+                id: user.id,
+                username: Username::parse("testuser").unwrap(),
+                email: Email::parse("testuser@gmail.com").unwrap(),
+            }]
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_failed_registration_emits_nothing() {
+        let sink = Arc::new(RecordingEventSink::default());
+        let service = setup_sqlite_service().await.with_event_sink(sink.clone());
+        service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+        sink.0.lock().unwrap().clear();
+
+        let duplicate = service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await;
+        let invalid = service
+            .register_user("testuser2", "not-an-email", &"password123".into())
+            .await;
+
+        assert!(duplicate.is_err());
+        assert!(invalid.is_err());
+        assert!(sink.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_user_enforces_domain_limit() {
+        let service = UserService::new(Arc::new(MockPasswordHasher), Arc::new(MockUserRepository))
+            .with_registration_limiter(Arc::new(RegistrationLimiter::new(1)));
+
+        let first = service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await;
+        assert!(first.is_ok());
+
+        let second = service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await;
+        assert!(matches!(second, Err(AppError::TooManyRequests(_))));
+    }
+
+    #[tokio::test]
+    async fn test_register_user_follows_allowed_email_domains() {
+        let unrestricted =
+            UserService::new(Arc::new(MockPasswordHasher), Arc::new(MockUserRepository));
+        let restricted =
+            UserService::new(Arc::new(MockPasswordHasher), Arc::new(MockUserRepository))
+                .with_allowed_email_domains(vec!["gmail.com".into()]);
+        let refusing = UserService::new(
+            Arc::new(PanickingPasswordHasher),
+            Arc::new(MockUserRepository),
+        )
+        .with_allowed_email_domains(vec!["example.com".into()]);
+
+        for service in [&unrestricted, &restricted] {
+            let result = service
+                .register_user("testuser", "TestUser@GMail.com", &"password123".into())
+                .await;
+            assert!(result.is_ok(), "{:?}", result);
+        }
+        // Refused before the password is hashed
+        let result = refusing
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn test_closed_registration_is_refused_before_hashing() {
+        let service = UserService::new(
+            Arc::new(PanickingPasswordHasher),
+            Arc::new(MockUserRepository),
+        )
+        .with_registration_enabled(false);
+
+        let result = service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await;
+
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn test_register_user_rejects_invalid_input_before_hashing() {
+        let service = UserService::new(
+            Arc::new(PanickingPasswordHasher),
+            Arc::new(MockUserRepository),
+        );
+
+        let bad_username = service
+            .register_user(
+                "no spaces allowed",
+                "testuser@gmail.com",
+                &"password123".into(),
+            )
+            .await;
+        assert!(matches!(bad_username, Err(AppError::Validation(_))));
+
+        let bad_email = service
+            .register_user("testuser", "not-an-email", &"password123".into())
+            .await;
+        assert!(matches!(bad_email, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_register_user_rejects_oversized_password_before_hashing() {
+        let service = UserService::new(
+            Arc::new(PanickingPasswordHasher),
+            Arc::new(MockUserRepository),
+        )
+        .with_max_password_bytes(16);
+
+        // At the cap the password moves on to the strength rules
+        let result = service
+            .register_user("testuser", "testuser@gmail.com", &"passwordpassword".into())
+            .await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        let result = service
+            .register_user(
+                "testuser",
+                "testuser@gmail.com",
+                &"password123456789".into(),
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::Unprocessable(_))));
+    }
+
+    // Collects the names of fields recorded on spans after creation
+    #[derive(Clone, Default)]
+    struct RecordedFields(Arc<Mutex<Vec<String>>>);
+
+    struct FieldNameVisitor<'a>(&'a mut Vec<String>);
+
+    impl Visit for FieldNameVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, _value: &dyn std::fmt::Debug) {
+            self.0.push(field.name().to_string());
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for RecordedFields {
+        fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            values.record(&mut FieldNameVisitor(&mut self.0.lock().unwrap()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_user_records_timings() {
+        let recorded = RecordedFields::default();
+        let subscriber = Registry::default().with(recorded.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let service = UserService::new(Arc::new(MockPasswordHasher), Arc::new(MockUserRepository));
+        service
+            .register_user("testuser", "testuser@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+
+        let fields = recorded.0.lock().unwrap();
+        assert!(fields.iter().any(|f| f == "hash_ms"));
+        assert!(fields.iter().any(|f| f == "db_ms"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_purge_reads_the_injected_clock() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .unwrap();
+        let repository = Arc::new(crate::persistence::SqliteUserRepository::new(pool));
+        let clock = Arc::new(crate::application::clock::MockClock::new(chrono::Utc::now()));
+        let service =
+            UserService::new(Arc::new(MockPasswordHasher), repository).with_clock(clock.clone());
+        let id = service
+            .register_user("alice", "alice@gmail.com", &"password123".into())
+            .await
+            .unwrap();
+        service.delete_user(&id).await.unwrap();
+
+        assert_eq!(
+            service
+                .purge_deleted_users(Duration::days(30))
+                .await
+                .unwrap(),
+            0
+        );
+
+        clock.advance(chrono::Duration::days(31));
+        assert_eq!(
+            service
+                .purge_deleted_users(Duration::days(30))
+                .await
+                .unwrap(),
+            1
+        );
+    }
 }
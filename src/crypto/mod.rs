@@ -1,3 +1,4 @@
 pub mod password;
+pub mod token;
 
 pub use password::Argon2PasswordHasher;
@@ -1,27 +1,206 @@
 use argon2::{
-    Argon2,
-    password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
 };
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::ops::RangeInclusive;
 
 use crate::application::{
     app_error::{AppError, AppResult},
     user_service::PasswordHasher as PasswordHasherTrait,
 };
 
-#[derive(Default)]
+/// Salt bytes generated per hash unless configured otherwise
+pub const DEFAULT_SALT_LENGTH: usize = 16;
+
+/// Argon2 needs at least 8 salt bytes, and a PHC string holds at most 64 base64 characters
+pub const SALT_LENGTH_RANGE: RangeInclusive<usize> = 8..=48;
+
 pub struct Argon2PasswordHasher {
     hasher: Argon2<'static>,
+    pepper: Option<Vec<u8>>,
+    salt_length: usize,
+}
+
+impl Default for Argon2PasswordHasher {
+    fn default() -> Self {
+        Self::new(Algorithm::default())
+    }
+}
+
+impl Argon2PasswordHasher {
+    pub fn new(algorithm: Algorithm) -> Self {
+        Self {
+            hasher: Argon2::new(algorithm, Version::V0x13, Params::default()),
+            pepper: None,
+            salt_length: DEFAULT_SALT_LENGTH,
+        }
+    }
+
+    /// Generate `length` random salt bytes per hash, which must be within `SALT_LENGTH_RANGE`
+    pub fn with_salt_length(mut self, length: usize) -> AppResult<Self> {
+        if !SALT_LENGTH_RANGE.contains(&length) {
+            return Err(AppError::Validation(format!(
+                "Salt length must be between {} and {} bytes",
+                SALT_LENGTH_RANGE.start(),
+                SALT_LENGTH_RANGE.end()
+            )));
+        }
+        self.salt_length = length;
+        Ok(self)
+    }
+
+    /// HMAC every password with a server-side secret before hashing
+    /// Hashes made with a different pepper, or none, will no longer verify
+    pub fn with_pepper(mut self, pepper: &str) -> Self {
+        self.pepper = Some(pepper.as_bytes().to_vec());
+        self
+    }
+
+    /// The bytes handed to Argon2, peppered when a pepper is configured
+    fn prepare(&self, password: &str) -> Vec<u8> {
+        match &self.pepper {
+            Some(pepper) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(pepper)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(password.as_bytes());
+                mac.finalize().into_bytes().to_vec()
+            }
+            None => password.as_bytes().to_vec(),
+        }
+    }
 }
 
 impl PasswordHasherTrait for Argon2PasswordHasher {
     fn hash_password(&self, password: &str) -> AppResult<String> {
-        let salt = SaltString::generate(&mut OsRng);
+        let mut salt = vec![0u8; self.salt_length];
+        OsRng.fill_bytes(&mut salt);
+        let salt = SaltString::encode_b64(&salt)
+            .map_err(|_| AppError::Internal("Password salt is out of range".into()))?;
         let hash = self
             .hasher
-            .hash_password(password.as_bytes(), &salt)
+            .hash_password(&self.prepare(password), &salt)
             .map_err(|_| AppError::Internal("Password hashing failed".into()))?
             .to_string();
 
         Ok(hash)
     }
+
+    fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
+        let parsed = PasswordHash::new(hash)
+            .map_err(|_| AppError::Internal("Stored password hash is malformed".into()))?;
+
+        // The PHC string carries its own algorithm and params, so older hashes keep verifying
+        Ok(self
+            .hasher
+            .verify_password(&self.prepare(password), &parsed)
+            .is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_records_selected_variant() {
+        let hasher = Argon2PasswordHasher::new(Algorithm::Argon2i);
+
+        let hash = hasher.hash_password("password123").unwrap();
+        let parsed = PasswordHash::new(&hash).unwrap();
+
+        assert_eq!(parsed.algorithm, Algorithm::Argon2i.ident());
+    }
+
+    #[test]
+    fn test_verify_succeeds_across_variants() {
+        let hash = Argon2PasswordHasher::new(Algorithm::Argon2d)
+            .hash_password("password123")
+            .unwrap();
+        let parsed = PasswordHash::new(&hash).unwrap();
+
+        // The PHC string carries the algorithm, so a default (argon2id) verifier still accepts it
+        assert!(
+            Argon2::default()
+                .verify_password(b"password123", &parsed)
+                .is_ok()
+        );
+        assert!(
+            Argon2PasswordHasher::default()
+                .verify_password("password123", &hash)
+                .unwrap()
+        );
+        assert!(
+            !Argon2PasswordHasher::default()
+                .verify_password("password124", &hash)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_peppered_hash_round_trip() {
+        let hasher = Argon2PasswordHasher::default().with_pepper("pepper");
+
+        let hash = hasher.hash_password("password123").unwrap();
+
+        assert!(hasher.verify_password("password123", &hash).unwrap());
+        assert!(!hasher.verify_password("password124", &hash).unwrap());
+    }
+
+    #[test]
+    fn test_wrong_or_missing_pepper_fails_verification() {
+        let hash = Argon2PasswordHasher::default()
+            .with_pepper("pepper")
+            .hash_password("password123")
+            .unwrap();
+
+        let wrong_pepper = Argon2PasswordHasher::default().with_pepper("other");
+        assert!(!wrong_pepper.verify_password("password123", &hash).unwrap());
+
+        let no_pepper = Argon2PasswordHasher::default();
+        assert!(!no_pepper.verify_password("password123", &hash).unwrap());
+    }
+
+    fn salt_bytes(hash: &str) -> usize {
+        let parsed = PasswordHash::new(hash).unwrap();
+        let mut buffer = [0u8; 64];
+        parsed.salt.unwrap().decode_b64(&mut buffer).unwrap().len()
+    }
+
+    #[test]
+    fn test_hash_encodes_configured_salt_length() {
+        assert_eq!(
+            salt_bytes(
+                &Argon2PasswordHasher::default()
+                    .hash_password("password123")
+                    .unwrap()
+            ),
+            DEFAULT_SALT_LENGTH
+        );
+
+        for length in [*SALT_LENGTH_RANGE.start(), 32, *SALT_LENGTH_RANGE.end()] {
+            let hasher = Argon2PasswordHasher::default()
+                .with_salt_length(length)
+                .unwrap();
+
+            let hash = hasher.hash_password("password123").unwrap();
+
+            assert_eq!(salt_bytes(&hash), length);
+            assert!(hasher.verify_password("password123", &hash).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_salt_length_is_rejected() {
+        for length in [0, 7, 49] {
+            assert!(matches!(
+                Argon2PasswordHasher::default().with_salt_length(length),
+                Err(AppError::Validation(_))
+            ));
+        }
+    }
 }
@@ -0,0 +1,55 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// Number of random bytes in a generated token
+const TOKEN_BYTES: usize = 32;
+
+/// Length of a generated token once hex encoded
+pub const TOKEN_LENGTH: usize = TOKEN_BYTES * 2;
+
+/// Generate a random, URL-safe token to hand out to a user
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hash a token for storage so a database leak doesn't expose usable tokens
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Compare a presented token with the expected one without leaking how much of it matched
+/// Both are hashed first, so their lengths don't show in the timing either
+pub fn tokens_match(expected: &str, presented: &str) -> bool {
+    let expected = Sha256::digest(expected.as_bytes());
+    let presented = Sha256::digest(presented.as_bytes());
+    expected.ct_eq(&presented).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_token_is_unique() {
+        let token = generate_token();
+        assert_eq!(token.len(), TOKEN_LENGTH);
+        assert_ne!(token, generate_token());
+    }
+
+    #[test]
+    fn test_hash_token_is_stable() {
+        assert_eq!(hash_token("abc"), hash_token("abc"));
+        assert_ne!(hash_token("abc"), hash_token("abd"));
+    }
+
+    #[test]
+    fn test_tokens_match_only_identical_tokens() {
+        assert!(tokens_match("abc", "abc"));
+        assert!(!tokens_match("abc", "abd"));
+        assert!(!tokens_match("abc", "abcd"));
+        assert!(!tokens_match("abc", ""));
+    }
+}
@@ -0,0 +1,65 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+use uuid::Uuid;
+
+use crate::application::app_error::AppError;
+
+/// Sensitive actions recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Register,
+    Login,
+    PasswordReset,
+    UserDeleted,
+    UserRestored,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Register => "register",
+            AuditAction::Login => "login",
+            AuditAction::PasswordReset => "password_reset",
+            AuditAction::UserDeleted => "user_deleted",
+            AuditAction::UserRestored => "user_restored",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "register" => Ok(AuditAction::Register),
+            "login" => Ok(AuditAction::Login),
+            "password_reset" => Ok(AuditAction::PasswordReset),
+            "user_deleted" => Ok(AuditAction::UserDeleted),
+            "user_restored" => Ok(AuditAction::UserRestored),
+            other => Err(AppError::Validation(format!(
+                "Unknown audit action '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// One recorded action: who did what to whom, from where and when
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    /// `None` when nobody was signed in, such as a password reset by token
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub created_at: NaiveDateTime,
+}
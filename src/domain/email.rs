@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::application::app_error::{AppError, AppResult};
+
+/// Maximum length accepted by the `email` column, the longest address SMTP allows
+pub const EMAIL_MAX_LENGTH: usize = 254;
+
+/// A syntactically valid, lowercase email address
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Email(String);
+
+impl Email {
+    /// Validate and normalize an email address
+    pub fn parse(value: impl Into<String>) -> AppResult<Self> {
+        let value = value.into().trim().to_lowercase();
+
+        if value.is_empty() {
+            return Err(AppError::Validation("Email must not be empty".into()));
+        }
+        if value.len() > EMAIL_MAX_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Email must be at most {} characters",
+                EMAIL_MAX_LENGTH
+            )));
+        }
+        if value.chars().any(char::is_whitespace) {
+            return Err(AppError::Validation(
+                "Email must not contain whitespace".into(),
+            ));
+        }
+
+        let Some((local, domain)) = value.split_once('@') else {
+            return Err(AppError::Validation("Email must contain '@'".into()));
+        };
+        if local.is_empty() || domain.contains('@') {
+            return Err(AppError::Validation("Email is malformed".into()));
+        }
+        if !domain.contains('.') || domain.split('.').any(str::is_empty) {
+            return Err(AppError::Validation("Email domain is malformed".into()));
+        }
+
+        Ok(Self(value))
+    }
+
+    /// Wrap a value that was already validated, such as a row loaded from storage
+    pub(crate) fn new_unchecked(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The part after the `@`
+    pub fn domain(&self) -> &str {
+        self.0.split_once('@').map(|(_, d)| d).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for Email {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        Self::parse(value)
+    }
+}
+
+impl AsRef<str> for Email {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_email() {
+        let email = Email::parse("user@example.com").unwrap();
+        assert_eq!(email.as_str(), "user@example.com");
+        assert_eq!(email.domain(), "example.com");
+    }
+
+    #[test]
+    fn test_parse_normalizes_case_and_whitespace() {
+        let email = Email::try_from("  User.Name@Example.COM ".to_string()).unwrap();
+        assert_eq!(email.as_str(), "user.name@example.com");
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        for input in [
+            "",
+            "userexample.com",
+            "@example.com",
+            "user@",
+            "user@example",
+            "user@@example.com",
+            "user@example..com",
+            "us er@example.com",
+        ] {
+            assert!(
+                matches!(Email::parse(input), Err(AppError::Validation(_))),
+                "expected '{}' to be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_too_long() {
+        let input = format!("{}@example.com", "a".repeat(EMAIL_MAX_LENGTH));
+        assert!(Email::parse(input).is_err());
+    }
+}
@@ -1 +1,11 @@
+pub mod audit;
+pub mod email;
+pub mod password;
+pub mod redacted_hash;
+pub mod refresh_token;
 pub mod user;
+pub mod username;
+
+pub use email::Email;
+pub use redacted_hash::RedactedHash;
+pub use username::Username;
@@ -0,0 +1,79 @@
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::application::app_error::{AppError, AppResult};
+
+/// Minimum password length
+pub const PASSWORD_MIN_LENGTH: usize = 8;
+
+/// Default cap on the bytes of a password, Argon2 hashes every one of them
+pub const DEFAULT_PASSWORD_MAX_BYTES: usize = 1024;
+
+/// Reject a password over `max_bytes` before it reaches the hasher
+/// Only its length is read, so the error never carries the password
+pub fn check_password_size(password: &SecretString, max_bytes: usize) -> AppResult<()> {
+    if password.expose_secret().len() > max_bytes {
+        return Err(AppError::Unprocessable(format!(
+            "Password must be at most {} bytes",
+            max_bytes
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check that a password is long enough and mixes letters and digits
+/// Its upper bound is left to `check_password_size`, which knows the configured cap
+pub fn validate_password_strength(password: &str) -> AppResult<()> {
+    if password.chars().count() < PASSWORD_MIN_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Password must be at least {} characters",
+            PASSWORD_MIN_LENGTH
+        )));
+    }
+    if !password.chars().any(char::is_alphabetic) || !password.chars().any(|c| c.is_ascii_digit()) {
+        return Err(AppError::Validation(
+            "Password must contain both letters and digits".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strong_password_is_accepted() {
+        assert!(validate_password_strength("password123").is_ok());
+    }
+
+    #[test]
+    fn test_long_password_is_left_to_the_size_check() {
+        let long = format!("{}1", "a".repeat(199));
+        assert!(validate_password_strength(&long).is_ok());
+    }
+
+    #[test]
+    fn test_password_size_is_counted_in_bytes() {
+        assert!(check_password_size(&"é".repeat(4).into(), 8).is_ok());
+        assert!(matches!(
+            check_password_size(&"é".repeat(5).into(), 8),
+            Err(AppError::Unprocessable(_))
+        ));
+    }
+
+    #[test]
+    fn test_weak_passwords_are_rejected() {
+        for input in ["", "pass1", "passwordonly", "12345678901"] {
+            assert!(
+                matches!(
+                    validate_password_strength(input),
+                    Err(AppError::Validation(_))
+                ),
+                "expected '{}' to be rejected",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+
+/// Characters of the hash output kept in the fingerprint
+const FINGERPRINT_LENGTH: usize = 6;
+
+/// A stored password hash whose `Debug` and `Display` only show the algorithm and a fingerprint
+/// Anything holding one, such as `User`, can be logged without leaking the hash
+#[derive(Clone, PartialEq, Eq)]
+pub struct RedactedHash(String);
+
+impl RedactedHash {
+    pub fn new(hash: impl Into<String>) -> Self {
+        Self(hash.into())
+    }
+
+    /// The full hash, only for verifying a password against it
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// The PHC algorithm identifier, such as `argon2id`, or `unknown` for anything else
+    pub fn algorithm(&self) -> &str {
+        match self.0.strip_prefix('$') {
+            Some(rest) => rest.split('$').next().unwrap_or_default(),
+            None => "unknown",
+        }
+    }
+
+    /// The first few characters of the hash output, enough to tell two hashes apart
+    pub fn fingerprint(&self) -> String {
+        let output = self.0.rsplit('$').next().unwrap_or_default();
+        output.chars().take(FINGERPRINT_LENGTH).collect()
+    }
+}
+
+impl From<String> for RedactedHash {
+    fn from(hash: String) -> Self {
+        Self(hash)
+    }
+}
+
+impl From<&str> for RedactedHash {
+    fn from(hash: &str) -> Self {
+        Self(hash.to_string())
+    }
+}
+
+impl fmt::Display for RedactedHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}…", self.algorithm(), self.fingerprint())
+    }
+}
+
+impl fmt::Debug for RedactedHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RedactedHash({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHRzb21lc2FsdA$\
+                        Zm9vYmFyYmF6cXV4Zm9vYmFyYmF6cXV4Zm9vYmFyYmF6";
+
+    #[test]
+    fn test_formatting_never_reveals_the_full_hash() {
+        let hash = RedactedHash::new(HASH);
+
+        for output in [format!("{}", hash), format!("{:?}", hash)] {
+            assert!(!output.contains(HASH), "{}", output);
+            assert!(!output.contains("c29tZXNhbHRzb21lc2FsdA"), "{}", output);
+            assert!(!output.contains("Zm9vYmFyYmF6cXV4"), "{}", output);
+        }
+        assert_eq!(hash.to_string(), "argon2id:Zm9vYm…");
+        assert_eq!(hash.expose(), HASH);
+    }
+
+    #[test]
+    fn test_non_phc_hash_is_still_redacted() {
+        let hash = RedactedHash::new("plaintext-looking-value");
+
+        assert_eq!(hash.algorithm(), "unknown");
+        assert_eq!(format!("{:?}", hash), "RedactedHash(unknown:plaint…)");
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+/// A stored refresh token
+/// Tokens rotated from the same login share a `family_id`
+#[derive(Debug, Clone)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    /// User-Agent of the login that started the family, carried across rotations
+    pub user_agent: Option<String>,
+    /// When the previous token of the family was exchanged for this one
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl RefreshToken {
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expires_at <= now
+    }
+}
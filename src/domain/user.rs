@@ -1,10 +1,61 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use uuid::Uuid;
 
+use crate::{
+    application::app_error::AppError,
+    domain::{email::Email, redacted_hash::RedactedHash, username::Username},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Every self-registered account gets this role, only an admin can grant another
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(Role::User),
+            "admin" => Ok(Role::Admin),
+            _ => Err(AppError::Validation(format!("Unknown role '{}'", s))),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct User {
     pub id: Uuid,
-    pub username: String,
-    pub email: String,
-    pub password_hash: String,
+    pub username: Username,
+    pub email: Email,
+    /// Prints redacted, so logging a `User` never leaks the hash
+    pub password_hash: RedactedHash,
+    pub role: Role,
+    pub email_verified: bool,
+    pub last_login_at: Option<chrono::NaiveDateTime>,
     pub created_at: chrono::NaiveDateTime,
+    /// Last time any column of the user changed
+    pub updated_at: chrono::NaiveDateTime,
+    /// Set once soft-deleted, only ever seen through lookups that include deleted users
+    pub deleted_at: Option<chrono::NaiveDateTime>,
 }
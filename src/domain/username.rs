@@ -0,0 +1,107 @@
+use std::fmt;
+
+use crate::application::app_error::{AppError, AppResult};
+
+/// Minimum username length
+pub const USERNAME_MIN_LENGTH: usize = 3;
+
+/// Maximum length, also enforced by the `users_username_length` constraint
+pub const USERNAME_MAX_LENGTH: usize = 32;
+
+/// Longest username the `users` column ever held, which older accounts may still use
+/// Only new usernames are held to `USERNAME_MAX_LENGTH`
+pub const LEGACY_USERNAME_MAX_LENGTH: usize = 50;
+
+/// A username made of ASCII letters, digits, `_`, `.` and `-`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Username(String);
+
+impl Username {
+    /// Validate a username against the length and charset rules
+    pub fn parse(value: impl Into<String>) -> AppResult<Self> {
+        let value = value.into().trim().to_string();
+
+        let length = value.chars().count();
+        if !(USERNAME_MIN_LENGTH..=USERNAME_MAX_LENGTH).contains(&length) {
+            return Err(AppError::Validation(format!(
+                "Username must be between {} and {} characters",
+                USERNAME_MIN_LENGTH, USERNAME_MAX_LENGTH
+            )));
+        }
+        if !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+        {
+            return Err(AppError::Validation(
+                "Username may only contain letters, digits, '_', '.' and '-'".into(),
+            ));
+        }
+
+        Ok(Self(value))
+    }
+
+    /// Wrap a value that was already validated, such as a row loaded from storage
+    pub(crate) fn new_unchecked(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Username {
+    type Error = AppError;
+
+    fn try_from(value: String) -> AppResult<Self> {
+        Self::parse(value)
+    }
+}
+
+impl AsRef<str> for Username {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_username() {
+        for input in [
+            "bob",
+            "john_doe",
+            "jane.doe-99",
+            &"a".repeat(USERNAME_MAX_LENGTH),
+        ] {
+            let username = Username::try_from(input.to_string()).unwrap();
+            assert_eq!(username.as_ref(), input);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_username() {
+        for input in [
+            "",
+            "ab",
+            "has space",
+            "semi;colon",
+            "émile",
+            &"a".repeat(USERNAME_MAX_LENGTH + 1),
+        ] {
+            assert!(
+                matches!(Username::parse(input), Err(AppError::Validation(_))),
+                "expected '{}' to be rejected",
+                input
+            );
+        }
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+compile_error!("Enable at least one database backend with the `postgres` or `sqlite` feature");
+
 pub mod application;
 pub mod config;
 pub mod crypto;
@@ -6,4 +9,4 @@ pub mod persistence;
 pub mod server;
 pub mod web;
 
-pub use server::create_app;
+pub use server::{create_app, create_app_with_pool, migrate, run};
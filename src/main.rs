@@ -1,17 +1,17 @@
 use dotenvy::dotenv;
-use tracing::info;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
 
-    let app = sultan::create_app().await?;
+    match std::env::args().nth(1).as_deref() {
+        None | Some("serve") => {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:3001").await?;
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3001").await?;
-
-    info!("Server listening on {}", listener.local_addr()?);
-
-    axum::serve(listener, app).await?;
-
-    Ok(())
+            sultan::run(listener).await
+        }
+        // Apply migrations and exit, for deployments that set RUN_MIGRATIONS=false
+        Some("migrate") => sultan::migrate().await,
+        Some(other) => anyhow::bail!("Unknown command '{}', expected serve or migrate", other),
+    }
 }
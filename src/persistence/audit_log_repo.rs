@@ -0,0 +1,128 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+use crate::{application::app_error::AppResult, domain::audit::AuditEntry};
+
+/// Optional time range for reading the audit log
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuditLogFilter {
+    /// Only entries at or after this moment
+    pub from: Option<NaiveDateTime>,
+    /// Only entries strictly before this moment
+    pub until: Option<NaiveDateTime>,
+}
+
+// ============================================================================
+// Audit Log Repository Trait
+// ============================================================================
+
+/// Trait for audit log storage
+/// Entries are only ever appended
+#[async_trait]
+pub trait AuditLogRepository: Send + Sync {
+    /// Append an entry
+    async fn append(&self, entry: &AuditEntry) -> AppResult<()>;
+
+    /// A page of entries, newest first
+    async fn list_entries(&self, limit: u32, offset: u32) -> AppResult<Vec<AuditEntry>>;
+
+    /// Up to `limit` entries in `filter`, oldest first, starting after the entry with
+    /// `after` as its `(created_at, id)`. Paging by key stays cheap however deep it goes
+    async fn list_entries_after(
+        &self,
+        filter: &AuditLogFilter,
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: u32,
+    ) -> AppResult<Vec<AuditEntry>>;
+
+    /// How many entries there are
+    async fn count_entries(&self) -> AppResult<u64>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::audit::AuditAction;
+    #[cfg(feature = "postgres")]
+    use crate::persistence::postgres::audit_log::PostgresAuditLogRepository;
+    #[cfg(feature = "sqlite")]
+    use crate::persistence::sqlite::audit_log::SqliteAuditLogRepository;
+    use std::sync::Arc;
+
+    async fn test_entries_are_paged_by_key_within_range_impl(repo: Arc<dyn AuditLogRepository>) {
+        // A marker no other test uses, since the Postgres table is shared
+        let target_id = Some(Uuid::new_v4());
+        let start =
+            NaiveDateTime::parse_from_str("2031-01-01 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let mut ids = Vec::new();
+        for minutes in [0, 1, 1, 2, 3] {
+            let entry = AuditEntry {
+                id: Uuid::new_v4(),
+                actor_id: None,
+                action: AuditAction::Login,
+                target_id,
+                ip: None,
+                created_at: start + chrono::Duration::minutes(minutes),
+            };
+            repo.append(&entry).await.unwrap();
+            ids.push((entry.created_at, entry.id));
+        }
+        ids.sort();
+        let filter = AuditLogFilter {
+            from: Some(start + chrono::Duration::minutes(1)),
+            until: Some(start + chrono::Duration::minutes(3)),
+        };
+
+        let mut seen = Vec::new();
+        let mut after = None;
+        loop {
+            let page = repo.list_entries_after(&filter, after, 2).await.unwrap();
+            let Some(last) = page.last() else { break };
+            after = Some((last.created_at, last.id));
+            seen.extend(
+                page.iter()
+                    .filter(|entry| entry.target_id == target_id)
+                    .map(|entry| (entry.created_at, entry.id)),
+            );
+        }
+
+        assert_eq!(seen, ids[1..4]);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_entries_are_paged_by_key_within_range() {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        test_entries_are_paged_by_key_within_range_impl(Arc::new(SqliteAuditLogRepository::new(
+            pool,
+        )))
+        .await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_entries_are_paged_by_key_within_range() {
+        let database_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL environment variable must be set for PostgreSQL tests");
+        let pool = sqlx::PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to PostgreSQL");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run PostgreSQL migrations");
+
+        test_entries_are_paged_by_key_within_range_impl(Arc::new(PostgresAuditLogRepository::new(
+            pool,
+        )))
+        .await;
+    }
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::AppResult,
+    domain::{email::Email, user::Role, username::Username},
+};
+
+// ============================================================================
+// Invite Code Repository Trait
+// ============================================================================
+
+/// Trait for registration invite code storage
+/// Only hashes of codes are ever stored
+#[async_trait]
+pub trait InviteCodeRepository: Send + Sync {
+    /// Store a new, unused invite code minted by `created_by`
+    async fn create_code(&self, code_hash: &str, created_by: Uuid) -> AppResult<()>;
+
+    /// Mark an unused code as used and create the user it admits with `role`, in one transaction
+    /// Returns the new user's id, or `None` without creating anyone if the code is unknown
+    /// or already used
+    #[allow(clippy::too_many_arguments)]
+    async fn register_with_code(
+        &self,
+        code_hash: &str,
+        now: NaiveDateTime,
+        username: &Username,
+        email: &Email,
+        password_hash: &str,
+        role: Role,
+    ) -> AppResult<Option<Uuid>>;
+}
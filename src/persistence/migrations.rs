@@ -0,0 +1,262 @@
+use sqlx::migrate::Migrator;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    persistence::user_repo::DbPool,
+};
+
+// ============================================================================
+// Bundled Migrations
+// ============================================================================
+
+#[cfg(feature = "postgres")]
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+#[cfg(feature = "sqlite")]
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations-sqlite");
+
+/// Apply every bundled migration `pool` hasn't seen yet, picking the set for its backend
+pub async fn run_migrations(pool: &DbPool) -> anyhow::Result<()> {
+    match pool {
+        #[cfg(feature = "postgres")]
+        DbPool::Postgres(pool) => {
+            tracing::info!("Running PostgreSQL migrations");
+            POSTGRES_MIGRATOR.run(pool).await?;
+        }
+        #[cfg(feature = "sqlite")]
+        DbPool::Sqlite(pool) => {
+            tracing::info!("Running SQLite migrations");
+            SQLITE_MIGRATOR.run(pool).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Migration Status
+// ============================================================================
+
+/// Return the versions of bundled migrations that have not been applied yet
+///
+/// A database without the `_sqlx_migrations` table reports every bundled migration as pending.
+pub async fn pending_migrations(pool: &DbPool) -> AppResult<Vec<i64>> {
+    let (migrator, applied) = match pool {
+        #[cfg(feature = "postgres")]
+        DbPool::Postgres(pg_pool) => {
+            let table_exists: bool =
+                sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+                    .fetch_one(pg_pool)
+                    .await
+                    .map_err(AppError::from)?;
+
+            let applied: Vec<i64> = if table_exists {
+                sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+                    .fetch_all(pg_pool)
+                    .await
+                    .map_err(AppError::from)?
+            } else {
+                Vec::new()
+            };
+
+            (&POSTGRES_MIGRATOR, applied)
+        }
+        #[cfg(feature = "sqlite")]
+        DbPool::Sqlite(sqlite_pool) => {
+            let table_exists: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+            )
+            .fetch_one(sqlite_pool)
+            .await
+            .map_err(AppError::from)?;
+
+            let applied: Vec<i64> = if table_exists {
+                sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+                    .fetch_all(sqlite_pool)
+                    .await
+                    .map_err(AppError::from)?
+            } else {
+                Vec::new()
+            };
+
+            (&SQLITE_MIGRATOR, applied)
+        }
+    };
+
+    Ok(migrator
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| m.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    async fn setup_sqlite_pool() -> sqlx::SqlitePool {
+        sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database")
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_no_pending_after_migrate() {
+        let pool = setup_sqlite_pool().await;
+        SQLITE_MIGRATOR
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        let pending = pending_migrations(&DbPool::Sqlite(pool)).await.unwrap();
+
+        assert!(pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_brings_sqlite_up_to_date() {
+        let pool = DbPool::Sqlite(setup_sqlite_pool().await);
+        assert!(!pending_migrations(&pool).await.unwrap().is_empty());
+
+        run_migrations(&pool).await.unwrap();
+        assert!(pending_migrations(&pool).await.unwrap().is_empty());
+
+        // A second run finds nothing left to apply
+        run_migrations(&pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_reports_unapplied_migration() {
+        let pool = setup_sqlite_pool().await;
+        SQLITE_MIGRATOR
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        // Forget the latest migration so it looks like the schema is behind the code
+        let latest = SQLITE_MIGRATOR.iter().map(|m| m.version).max().unwrap();
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = ?")
+            .bind(latest)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let pending = pending_migrations(&DbPool::Sqlite(pool)).await.unwrap();
+
+        assert_eq!(pending, vec![latest]);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_all_pending_without_migrations_table() {
+        let pool = setup_sqlite_pool().await;
+
+        let pending = pending_migrations(&DbPool::Sqlite(pool)).await.unwrap();
+
+        assert_eq!(pending.len(), SQLITE_MIGRATOR.iter().count());
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn test_backends_share_migration_versions() {
+        let versions = |migrator: &Migrator| migrator.iter().map(|m| m.version).collect::<Vec<_>>();
+
+        assert_eq!(versions(&POSTGRES_MIGRATOR), versions(&SQLITE_MIGRATOR));
+    }
+
+    /// Application tables and their column names, sorted, as SQLite sees them
+    async fn sqlite_schema(pool: &sqlx::SqlitePool) -> Vec<(String, Vec<String>)> {
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master \
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '_sqlx%' \
+             ORDER BY name",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        let mut schema = Vec::new();
+        for table in tables {
+            let mut columns: Vec<String> =
+                sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+                    .bind(&table)
+                    .fetch_all(pool)
+                    .await
+                    .unwrap();
+            columns.sort();
+            schema.push((table, columns));
+        }
+        schema
+    }
+
+    /// Application tables and their column names, sorted, as Postgres sees them
+    #[cfg(feature = "postgres")]
+    async fn postgres_schema(pool: &sqlx::PgPool) -> Vec<(String, Vec<String>)> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT table_name::TEXT, column_name::TEXT FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name NOT LIKE '\\_sqlx%' \
+             ORDER BY table_name, column_name",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        let mut schema: Vec<(String, Vec<String>)> = Vec::new();
+        for (table, column) in rows {
+            match schema.last_mut() {
+                Some((last, columns)) if *last == table => columns.push(column),
+                _ => schema.push((table, vec![column])),
+            }
+        }
+        schema
+    }
+
+    // The migration directories are written by hand, so a column added to one backend
+    // but forgotten in the other only shows up when both are migrated and compared.
+    // The users table is also pinned here, so SQLite alone still catches a stray column
+    #[tokio::test]
+    async fn test_backends_share_the_same_schema() {
+        let sqlite = setup_sqlite_pool().await;
+        SQLITE_MIGRATOR
+            .run(&sqlite)
+            .await
+            .expect("Failed to run SQLite migrations");
+        let sqlite_schema = sqlite_schema(&sqlite).await;
+
+        let users = sqlite_schema
+            .iter()
+            .find(|(table, _)| table == "users")
+            .map(|(_, columns)| columns.as_slice());
+        assert_eq!(
+            users,
+            Some(
+                [
+                    "created_at",
+                    "deleted_at",
+                    "email",
+                    "email_verified",
+                    "id",
+                    "last_login_at",
+                    "password_hash",
+                    "role",
+                    "updated_at",
+                    "username",
+                ]
+                .map(String::from)
+                .as_slice()
+            )
+        );
+
+        #[cfg(feature = "postgres")]
+        if let Ok(database_url) = std::env::var("DATABASE_URL") {
+            let postgres = sqlx::PgPool::connect(&database_url)
+                .await
+                .expect("Failed to connect to PostgreSQL");
+            POSTGRES_MIGRATOR
+                .run(&postgres)
+                .await
+                .expect("Failed to run PostgreSQL migrations");
+
+            assert_eq!(sqlite_schema, postgres_schema(&postgres).await);
+        }
+    }
+}
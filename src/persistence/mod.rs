@@ -1,7 +1,47 @@
+pub mod audit_log_repo;
+pub mod email_verification_repo;
+pub mod invite_code_repo;
+pub mod migrations;
+pub mod password_reset_repo;
+pub mod pool_metrics;
+#[cfg(feature = "postgres")]
 pub mod postgres;
+pub mod rate_limit_store;
+pub mod refresh_token_repo;
+pub mod repositories;
+pub mod retry;
+pub mod slow_db_events;
+#[cfg(feature = "sqlite")]
 pub mod sqlite;
+pub mod token_cleanup;
 pub mod user_repo;
 
-pub use postgres::PostgresUserRepository;
-pub use sqlite::SqliteUserRepository;
-pub use user_repo::{DbPool, UserRepository};
+pub use audit_log_repo::{AuditLogFilter, AuditLogRepository};
+pub use email_verification_repo::EmailVerificationRepository;
+pub use invite_code_repo::InviteCodeRepository;
+#[cfg(feature = "postgres")]
+pub use migrations::POSTGRES_MIGRATOR;
+#[cfg(feature = "sqlite")]
+pub use migrations::SQLITE_MIGRATOR;
+pub use migrations::{pending_migrations, run_migrations};
+pub use password_reset_repo::PasswordResetRepository;
+pub use pool_metrics::{record_db_up, record_pool_stats, spawn_pool_sampler};
+#[cfg(feature = "postgres")]
+pub use postgres::{
+    PostgresAuditLogRepository, PostgresEmailVerificationRepository, PostgresInviteCodeRepository,
+    PostgresPasswordResetRepository, PostgresRateLimitStore, PostgresRefreshTokenRepository,
+    PostgresUserRepository,
+};
+pub use rate_limit_store::{InMemoryRateLimitStore, RateLimitStore};
+pub use refresh_token_repo::RefreshTokenRepository;
+pub use repositories::Repositories;
+pub use retry::{DEFAULT_MAX_ATTEMPTS, is_retryable, is_sqlite_busy, retry_transient};
+pub use slow_db_events::{SlowDbEventLayer, record_slow_db_events, slow_db_event_counts};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{
+    SqliteAuditLogRepository, SqliteEmailVerificationRepository, SqliteInviteCodeRepository,
+    SqlitePasswordResetRepository, SqliteRateLimitStore, SqliteRefreshTokenRepository,
+    SqliteUserRepository,
+};
+pub use token_cleanup::{PurgedTokens, purge_expired_tokens, spawn_token_cleanup};
+pub use user_repo::{DbPool, UserFilter, UserRepository};
@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+use crate::application::app_error::AppResult;
+
+// ============================================================================
+// Password Reset Repository Trait
+// ============================================================================
+
+/// Trait for password reset token storage
+/// Only hashes of tokens are ever stored
+#[async_trait]
+pub trait PasswordResetRepository: Send + Sync {
+    /// Store a new reset token for a user
+    async fn create_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> AppResult<()>;
+
+    /// Mark an unused, unexpired token as used and replace its user's password hash
+    /// Returns the user's id, or `None` if the token is unknown, expired or already used
+    async fn consume_token(
+        &self,
+        token_hash: &str,
+        now: NaiveDateTime,
+        new_password_hash: &str,
+    ) -> AppResult<Option<Uuid>>;
+
+    /// Delete every token that expired at or before `now`, used or not
+    /// Returns how many were deleted
+    async fn delete_expired(&self, now: NaiveDateTime) -> AppResult<u64>;
+}
@@ -0,0 +1,145 @@
+use std::{sync::Arc, time::Duration};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    application::metrics::Metrics,
+    persistence::{DbPool, record_slow_db_events},
+};
+
+pub const POOL_SIZE_GAUGE: &str = "db_pool_connections";
+pub const POOL_IDLE_GAUGE: &str = "db_pool_idle_connections";
+pub const POOL_IN_USE_GAUGE: &str = "db_pool_in_use_connections";
+pub const DB_UP_GAUGE: &str = "db_up";
+
+// ============================================================================
+// Pool Metrics
+// ============================================================================
+
+/// Record how many pooled connections are open, idle and checked out
+pub fn record_pool_stats(pool: &DbPool, metrics: &Metrics) {
+    let (size, idle) = match pool {
+        #[cfg(feature = "postgres")]
+        DbPool::Postgres(pool) => (pool.size(), pool.num_idle()),
+        #[cfg(feature = "sqlite")]
+        DbPool::Sqlite(pool) => (pool.size(), pool.num_idle()),
+    };
+    let size = i64::from(size);
+    let idle = idle as i64;
+
+    metrics.set_gauge(POOL_SIZE_GAUGE, "Open database connections", size);
+    metrics.set_gauge(
+        POOL_IDLE_GAUGE,
+        "Database connections waiting in the pool",
+        idle,
+    );
+    // Both counts are read separately, so a connection moving in between could make this negative
+    metrics.set_gauge(
+        POOL_IN_USE_GAUGE,
+        "Database connections checked out of the pool",
+        (size - idle).max(0),
+    );
+}
+
+/// Ping the database and record whether it answered
+pub async fn record_db_up(pool: &DbPool, metrics: &Metrics) {
+    let up = pool.ping().await.is_ok();
+    metrics.set_gauge(
+        DB_UP_GAUGE,
+        "Whether the database answered the last ping",
+        i64::from(up),
+    );
+}
+
+/// Sample the pool every `every` until `shutdown` is cancelled
+pub fn spawn_pool_sampler(
+    pool: DbPool,
+    metrics: Arc<Metrics>,
+    every: Duration,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(every);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticks.tick() => {}
+            }
+            record_pool_stats(&pool, &metrics);
+            record_slow_db_events(&metrics);
+            record_db_up(&pool, &metrics).await;
+        }
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_gauges_follow_checked_out_connections() {
+        let sqlite_pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(2)
+            .connect(":memory:")
+            .await
+            .unwrap();
+        let pool = DbPool::Sqlite(sqlite_pool.clone());
+        let metrics = Metrics::new();
+
+        record_pool_stats(&pool, &metrics);
+        assert_eq!(metrics.gauge(POOL_SIZE_GAUGE), Some(1));
+        assert_eq!(metrics.gauge(POOL_IDLE_GAUGE), Some(1));
+        assert_eq!(metrics.gauge(POOL_IN_USE_GAUGE), Some(0));
+
+        let connection = sqlite_pool.acquire().await.unwrap();
+        record_pool_stats(&pool, &metrics);
+        assert_eq!(metrics.gauge(POOL_IDLE_GAUGE), Some(0));
+        assert_eq!(metrics.gauge(POOL_IN_USE_GAUGE), Some(1));
+        drop(connection);
+    }
+
+    #[tokio::test]
+    async fn test_db_up_follows_ping() {
+        let pool = DbPool::Sqlite(sqlx::SqlitePool::connect(":memory:").await.unwrap());
+        let metrics = Metrics::new();
+
+        record_db_up(&pool, &metrics).await;
+        assert_eq!(metrics.gauge(DB_UP_GAUGE), Some(1));
+
+        pool.close().await;
+        record_db_up(&pool, &metrics).await;
+        assert_eq!(metrics.gauge(DB_UP_GAUGE), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_sampler_registers_gauges() {
+        let pool = DbPool::Sqlite(sqlx::SqlitePool::connect(":memory:").await.unwrap());
+        let metrics = Arc::new(Metrics::new());
+
+        let shutdown = CancellationToken::new();
+        let sampler = spawn_pool_sampler(
+            pool,
+            metrics.clone(),
+            Duration::from_millis(10),
+            shutdown.clone(),
+        );
+        for _ in 0..100 {
+            if metrics.gauge(POOL_SIZE_GAUGE).is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), sampler)
+            .await
+            .expect("sampler did not stop")
+            .unwrap();
+
+        assert!(
+            metrics
+                .render()
+                .contains("# TYPE db_pool_in_use_connections gauge")
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    domain::audit::{AuditAction, AuditEntry},
+    persistence::audit_log_repo::{AuditLogFilter, AuditLogRepository},
+};
+
+// ============================================================================
+// PostgreSQL Audit Log Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct PostgresAuditLogRepository {
+    pool: PgPool,
+}
+
+impl PostgresAuditLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+// Database model for AuditEntry - PostgreSQL
+#[derive(sqlx::FromRow, Debug)]
+pub struct AuditEntryDbPg {
+    pub id: Uuid,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl TryFrom<AuditEntryDbPg> for AuditEntry {
+    type Error = AppError;
+
+    fn try_from(entry_db: AuditEntryDbPg) -> Result<Self, Self::Error> {
+        Ok(AuditEntry {
+            id: entry_db.id,
+            actor_id: entry_db.actor_id,
+            action: entry_db.action.parse::<AuditAction>()?,
+            target_id: entry_db.target_id,
+            ip: entry_db.ip,
+            created_at: entry_db.created_at,
+        })
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for PostgresAuditLogRepository {
+    async fn append(&self, entry: &AuditEntry) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO audit_log (id, actor_id, action, target_id, ip, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(entry.id)
+        .bind(entry.actor_id)
+        .bind(entry.action.as_str())
+        .bind(entry.target_id)
+        .bind(entry.ip.as_deref())
+        .bind(entry.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn list_entries(&self, limit: u32, offset: u32) -> AppResult<Vec<AuditEntry>> {
+        let entries = sqlx::query_as::<_, AuditEntryDbPg>(
+            "SELECT id, actor_id, action, target_id, ip, created_at FROM audit_log \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $1 OFFSET $2",
+        )
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        entries.into_iter().map(AuditEntry::try_from).collect()
+    }
+
+    async fn list_entries_after(
+        &self,
+        filter: &AuditLogFilter,
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: u32,
+    ) -> AppResult<Vec<AuditEntry>> {
+        let mut query = QueryBuilder::<Postgres>::new(
+            "SELECT id, actor_id, action, target_id, ip, created_at FROM audit_log WHERE TRUE",
+        );
+        if let Some(from) = filter.from {
+            query.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(until) = filter.until {
+            query.push(" AND created_at < ").push_bind(until);
+        }
+        if let Some((created_at, id)) = after {
+            query
+                .push(" AND (created_at, id) > (")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(id)
+                .push(")");
+        }
+        query
+            .push(" ORDER BY created_at, id LIMIT ")
+            .push_bind(i64::from(limit));
+
+        let entries = query
+            .build_query_as::<AuditEntryDbPg>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        entries.into_iter().map(AuditEntry::try_from).collect()
+    }
+
+    async fn count_entries(&self) -> AppResult<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(count as u64)
+    }
+}
@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    persistence::email_verification_repo::EmailVerificationRepository,
+};
+
+// ============================================================================
+// PostgreSQL Email Verification Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct PostgresEmailVerificationRepository {
+    pool: PgPool,
+}
+
+impl PostgresEmailVerificationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EmailVerificationRepository for PostgresEmailVerificationRepository {
+    async fn create_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO email_verification_tokens (token_hash, user_id, expires_at) VALUES ($1, $2, $3)",
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn consume_token(&self, token_hash: &str, now: NaiveDateTime) -> AppResult<Option<Uuid>> {
+        let mut tx = self.pool.begin().await.map_err(AppError::from)?;
+
+        let user_id: Option<Uuid> = sqlx::query_scalar(
+            "UPDATE email_verification_tokens SET used_at = $2 \
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2 \
+             RETURNING user_id",
+        )
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        if let Some(user_id) = user_id {
+            sqlx::query("UPDATE users SET email_verified = TRUE, updated_at = CURRENT_TIMESTAMP WHERE id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+        }
+
+        tx.commit().await.map_err(AppError::from)?;
+
+        Ok(user_id)
+    }
+
+    async fn delete_expired(&self, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM email_verification_tokens WHERE expires_at <= $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+}
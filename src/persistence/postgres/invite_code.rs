@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    domain::{email::Email, user::Role, username::Username},
+    persistence::invite_code_repo::InviteCodeRepository,
+};
+
+// ============================================================================
+// PostgreSQL Invite Code Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct PostgresInviteCodeRepository {
+    pool: PgPool,
+}
+
+impl PostgresInviteCodeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl InviteCodeRepository for PostgresInviteCodeRepository {
+    async fn create_code(&self, code_hash: &str, created_by: Uuid) -> AppResult<()> {
+        sqlx::query("INSERT INTO invite_codes (code_hash, created_by) VALUES ($1, $2)")
+            .bind(code_hash)
+            .bind(created_by)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn register_with_code(
+        &self,
+        code_hash: &str,
+        now: NaiveDateTime,
+        username: &Username,
+        email: &Email,
+        password_hash: &str,
+        role: Role,
+    ) -> AppResult<Option<Uuid>> {
+        let mut tx = self.pool.begin().await.map_err(AppError::from)?;
+
+        // Claiming the code first means a concurrent registration with it finds it used
+        let claimed = sqlx::query(
+            "UPDATE invite_codes SET used_at = $2 WHERE code_hash = $1 AND used_at IS NULL",
+        )
+        .bind(code_hash)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+        if claimed.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let user_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO users (id, username, email, password_hash, role) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(user_id)
+        .bind(username.as_ref())
+        .bind(email.as_str())
+        .bind(password_hash)
+        .bind(role.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        sqlx::query("UPDATE invite_codes SET used_by = $2 WHERE code_hash = $1")
+            .bind(code_hash)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+
+        tx.commit().await.map_err(AppError::from)?;
+
+        Ok(Some(user_id))
+    }
+}
@@ -1,3 +1,15 @@
+pub mod audit_log;
+pub mod email_verification;
+pub mod invite_code;
+pub mod password_reset;
+pub mod rate_limit;
+pub mod refresh_token;
 pub mod user;
 
+pub use audit_log::PostgresAuditLogRepository;
+pub use email_verification::PostgresEmailVerificationRepository;
+pub use invite_code::PostgresInviteCodeRepository;
+pub use password_reset::PostgresPasswordResetRepository;
+pub use rate_limit::PostgresRateLimitStore;
+pub use refresh_token::PostgresRefreshTokenRepository;
 pub use user::PostgresUserRepository;
@@ -0,0 +1,210 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    domain::refresh_token::RefreshToken,
+    persistence::refresh_token_repo::{RefreshTokenRepository, SessionMetadata},
+};
+
+// ============================================================================
+// PostgreSQL Refresh Token Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct PostgresRefreshTokenRepository {
+    pool: PgPool,
+}
+
+impl PostgresRefreshTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+// Database model for RefreshToken - PostgreSQL
+#[derive(sqlx::FromRow, Debug)]
+pub struct RefreshTokenDbPg {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl From<RefreshTokenDbPg> for RefreshToken {
+    fn from(token_db: RefreshTokenDbPg) -> Self {
+        RefreshToken {
+            id: token_db.id,
+            user_id: token_db.user_id,
+            family_id: token_db.family_id,
+            expires_at: token_db.expires_at,
+            revoked_at: token_db.revoked_at,
+            created_at: token_db.created_at,
+            user_agent: token_db.user_agent,
+            last_used_at: token_db.last_used_at,
+        }
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for PostgresRefreshTokenRepository {
+    async fn create_token(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+        session: SessionMetadata<'_>,
+    ) -> AppResult<Uuid> {
+        let id = Uuid::new_v4();
+
+        sqlx::query(
+            "INSERT INTO refresh_tokens \
+             (id, user_id, family_id, token_hash, expires_at, user_agent, last_used_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(family_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .bind(session.user_agent)
+        .bind(session.last_used_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(id)
+    }
+
+    async fn get_token_by_hash(&self, token_hash: &str) -> AppResult<Option<RefreshToken>> {
+        let token = sqlx::query_as::<_, RefreshTokenDbPg>(
+            "SELECT id, user_id, family_id, expires_at, revoked_at, created_at, \
+             user_agent, last_used_at \
+             FROM refresh_tokens WHERE token_hash = $1",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(token.map(|t| t.into()))
+    }
+
+    async fn revoke_token(&self, id: Uuid, now: NaiveDateTime) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn revoke_family(&self, family_id: Uuid, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $2 WHERE family_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(family_id)
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $2 \
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2",
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn list_active_tokens(
+        &self,
+        user_id: Uuid,
+        now: NaiveDateTime,
+    ) -> AppResult<Vec<RefreshToken>> {
+        let tokens = sqlx::query_as::<_, RefreshTokenDbPg>(
+            "SELECT id, user_id, family_id, expires_at, revoked_at, created_at, \
+             user_agent, last_used_at \
+             FROM refresh_tokens \
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2 \
+             ORDER BY created_at DESC, id DESC",
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(tokens.into_iter().map(|t| t.into()).collect())
+    }
+
+    async fn list_active_tokens_page(
+        &self,
+        user_id: Uuid,
+        now: NaiveDateTime,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<Vec<RefreshToken>> {
+        let tokens = sqlx::query_as::<_, RefreshTokenDbPg>(
+            "SELECT id, user_id, family_id, expires_at, revoked_at, created_at, \
+             user_agent, last_used_at \
+             FROM refresh_tokens \
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2 \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT $3 OFFSET $4",
+        )
+        .bind(user_id)
+        .bind(now)
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(tokens.into_iter().map(|t| t.into()).collect())
+    }
+
+    async fn count_active_tokens(&self, user_id: Uuid, now: NaiveDateTime) -> AppResult<u64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM refresh_tokens \
+             WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2",
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(count as u64)
+    }
+
+    async fn delete_expired(&self, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM refresh_tokens WHERE expires_at <= $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,13 +1,18 @@
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use serde::Serialize;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use time::Duration;
 use uuid::Uuid;
 
 use crate::{
     application::app_error::{AppError, AppResult},
-    domain::user::User,
-    persistence::user_repo::UserRepository,
+    domain::{
+        email::Email,
+        user::{Role, User},
+        username::Username,
+    },
+    persistence::user_repo::{UserFilter, UserRepository, escape_like, purge_cutoff},
 };
 
 // ============================================================================
@@ -25,6 +30,13 @@ impl PostgresUserRepository {
     }
 }
 
+const USER_COLUMNS: &str = "id, username, email, password_hash, role, email_verified, last_login_at, created_at, updated_at, deleted_at";
+
+// Holds unless the row is the only live admin. Locking every admin row makes concurrent
+// demotions or deletes of two admins wait for each other instead of both passing
+const KEEPS_AN_ADMIN: &str = "(role <> 'admin' OR (SELECT COUNT(*) FROM \
+     (SELECT id FROM users WHERE role = 'admin' AND deleted_at IS NULL FOR UPDATE) AS admins) > 1)";
+
 // Database model for User - PostgreSQL
 #[derive(sqlx::FromRow, Debug, Serialize)]
 pub struct UserDbPg {
@@ -32,17 +44,27 @@ pub struct UserDbPg {
     pub username: String,
     pub email: String,
     pub password_hash: String,
+    pub role: String,
+    pub email_verified: bool,
+    pub last_login_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl From<UserDbPg> for User {
     fn from(user_db: UserDbPg) -> Self {
         User {
             id: user_db.id,
-            username: user_db.username,
-            email: user_db.email,
-            password_hash: user_db.password_hash,
+            username: Username::new_unchecked(user_db.username),
+            email: Email::new_unchecked(user_db.email),
+            password_hash: user_db.password_hash.into(),
+            role: user_db.role.parse().unwrap_or_default(),
+            email_verified: user_db.email_verified,
+            last_login_at: user_db.last_login_at,
             created_at: user_db.created_at,
+            updated_at: user_db.updated_at,
+            deleted_at: user_db.deleted_at,
         }
     }
 }
@@ -50,32 +72,225 @@ impl From<UserDbPg> for User {
 // Implement the UserRepository trait for PostgreSQL
 #[async_trait]
 impl UserRepository for PostgresUserRepository {
-    async fn create_user(&self, username: &str, email: &str, password_hash: &str) -> AppResult<()> {
+    async fn create_user_with_role(
+        &self,
+        username: &Username,
+        email: &Email,
+        password_hash: &str,
+        role: Role,
+    ) -> AppResult<Uuid> {
         let uuid = Uuid::new_v4();
 
         sqlx::query(
-            "INSERT INTO users (id, username, email, password_hash) VALUES ($1, $2, $3, $4)",
+            "INSERT INTO users (id, username, email, password_hash, role) VALUES ($1, $2, $3, $4, $5)",
         )
         .bind(uuid)
-        .bind(username)
-        .bind(email)
+        .bind(username.as_ref())
+        .bind(email.as_str())
         .bind(password_hash)
+        .bind(role.as_str())
         .execute(&self.pool)
         .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
-        Ok(())
+        Ok(uuid)
+    }
+
+    async fn get_user_by_id(&self, id: &Uuid) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbPg>(&format!(
+            "SELECT {} FROM users WHERE id = $1 AND deleted_at IS NULL",
+            USER_COLUMNS
+        ))
+        .bind(*id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_user_by_id_including_deleted(&self, id: &Uuid) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbPg>(&format!(
+            "SELECT {} FROM users WHERE id = $1",
+            USER_COLUMNS
+        ))
+        .bind(*id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_users_by_ids(&self, ids: &[Uuid]) -> AppResult<Vec<User>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let users = sqlx::query_as::<_, UserDbPg>(&format!(
+            "SELECT {} FROM users WHERE id = ANY($1) AND deleted_at IS NULL",
+            USER_COLUMNS
+        ))
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(users.into_iter().map(|u| u.into()).collect())
     }
 
     async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>> {
-        let user = sqlx::query_as::<_, UserDbPg>(
-            "SELECT id, username, email, password_hash, created_at FROM users WHERE username = $1",
-        )
+        let user = sqlx::query_as::<_, UserDbPg>(&format!(
+            "SELECT {} FROM users WHERE LOWER(username) = LOWER($1) AND deleted_at IS NULL",
+            USER_COLUMNS
+        ))
         .bind(username)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_user_by_email(&self, email: &Email) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbPg>(&format!(
+            "SELECT {} FROM users WHERE email = $1 AND deleted_at IS NULL",
+            USER_COLUMNS
+        ))
+        .bind(email.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_user_by_identifier(&self, identifier: &str) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbPg>(&format!(
+            "SELECT {} FROM users \
+             WHERE (LOWER(username) = LOWER($1) OR email = LOWER($1)) AND deleted_at IS NULL \
+             ORDER BY created_at, id LIMIT 1",
+            USER_COLUMNS
+        ))
+        .bind(identifier)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
 
         Ok(user.map(|u| u.into()))
     }
+
+    async fn touch_last_login(&self, id: &Uuid, at: NaiveDateTime) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE users SET last_login_at = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+        )
+        .bind(*id)
+        .bind(at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn set_role(&self, id: &Uuid, role: Role) -> AppResult<bool> {
+        let sql = format!(
+            "UPDATE users SET role = $2, updated_at = CURRENT_TIMESTAMP \
+             WHERE id = $1 AND deleted_at IS NULL AND ($2 = 'admin' OR {})",
+            KEEPS_AN_ADMIN
+        );
+        let result = sqlx::query(&sql)
+            .bind(*id)
+            .bind(role.as_str())
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<Vec<User>> {
+        let mut query = QueryBuilder::<Postgres>::new(format!(
+            "SELECT {} FROM users WHERE deleted_at IS NULL",
+            USER_COLUMNS
+        ));
+        if let Some(prefix) = &filter.username_prefix {
+            query
+                .push(" AND LOWER(username) LIKE ")
+                .push_bind(format!("{}%", escape_like(&prefix.to_lowercase())))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(email_verified) = filter.email_verified {
+            query
+                .push(" AND email_verified = ")
+                .push_bind(email_verified);
+        }
+        if let Some(role) = filter.role {
+            query.push(" AND role = ").push_bind(role.as_str());
+        }
+        if let Some(created_after) = filter.created_after {
+            query.push(" AND created_at > ").push_bind(created_after);
+        }
+        query
+            .push(" ORDER BY created_at DESC LIMIT ")
+            .push_bind(i64::from(limit))
+            .push(" OFFSET ")
+            .push_bind(i64::from(offset));
+
+        let users = query
+            .build_query_as::<UserDbPg>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(users.into_iter().map(User::from).collect())
+    }
+
+    async fn soft_delete_user(&self, id: &Uuid, deleted_at: NaiveDateTime) -> AppResult<bool> {
+        let sql = format!(
+            "UPDATE users SET deleted_at = $2 WHERE id = $1 AND deleted_at IS NULL AND {}",
+            KEEPS_AN_ADMIN
+        );
+        let result = sqlx::query(&sql)
+            .bind(*id)
+            .bind(deleted_at)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn restore_user(&self, id: &Uuid) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET deleted_at = NULL, updated_at = CURRENT_TIMESTAMP \
+             WHERE id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(*id)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_deleted_users(
+        &self,
+        older_than: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<u64> {
+        let cutoff = purge_cutoff(older_than, now)?;
+        let result = sqlx::query("DELETE FROM users WHERE deleted_at < $1")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
 }
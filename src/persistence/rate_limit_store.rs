@@ -0,0 +1,332 @@
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime};
+use std::{collections::HashMap, sync::Mutex};
+
+use crate::application::app_error::AppResult;
+
+/// Longest window any limiter counts over, so a window that opened before it has expired
+/// whatever its key
+pub const MAX_WINDOW: Duration = Duration::hours(1);
+
+// ============================================================================
+// Rate Limit Store Trait
+// ============================================================================
+
+/// Trait for rate limit counters, kept per key over fixed windows
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Count a hit against `key` and report whether it is within `limit` for the current window
+    /// A window opens on the first hit and lasts `window`, later hits past that open a new one
+    async fn incr_and_check(
+        &self,
+        key: &str,
+        limit: u32,
+        window: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<bool>;
+
+    /// Hits counted against `key` in its current window, without counting another
+    async fn current_count(
+        &self,
+        key: &str,
+        window: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<u32>;
+
+    /// Forget `key`'s window, so its next hit opens a fresh one
+    async fn reset(&self, key: &str) -> AppResult<()>;
+
+    /// Delete every window that opened at or before `cutoff`, returning how many
+    async fn delete_expired(&self, cutoff: NaiveDateTime) -> AppResult<u64>;
+}
+
+// ============================================================================
+// In-Memory Rate Limit Store
+// ============================================================================
+
+struct Window {
+    started: NaiveDateTime,
+    count: u32,
+}
+
+/// Counters held in this process, so each instance of the server counts separately
+/// The database stores share counters between instances behind a load balancer
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    windows: Mutex<HashMap<String, Window>>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn incr_and_check(
+        &self,
+        key: &str,
+        limit: u32,
+        window: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<bool> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Expired windows would restart on their next hit anyway, so drop them to bound memory.
+        // Other keys may count over longer windows than this one, so only the longest is safe
+        windows.retain(|_, entry| now - entry.started < MAX_WINDOW);
+
+        let entry = windows.entry(key.to_string()).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+        if now - entry.started >= window {
+            *entry = Window {
+                started: now,
+                count: 0,
+            };
+        }
+        entry.count = entry.count.saturating_add(1);
+
+        Ok(entry.count <= limit)
+    }
+
+    async fn current_count(
+        &self,
+        key: &str,
+        window: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<u32> {
+        let windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(windows
+            .get(key)
+            .filter(|entry| now - entry.started < window)
+            .map_or(0, |entry| entry.count))
+    }
+
+    async fn reset(&self, key: &str) -> AppResult<()> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        windows.remove(key);
+        Ok(())
+    }
+
+    async fn delete_expired(&self, cutoff: NaiveDateTime) -> AppResult<u64> {
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        let before = windows.len();
+        windows.retain(|_, entry| entry.started > cutoff);
+        Ok((before - windows.len()) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "postgres")]
+    use crate::persistence::PostgresRateLimitStore;
+    #[cfg(feature = "sqlite")]
+    use crate::persistence::SqliteRateLimitStore;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn window() -> Duration {
+        Duration::hours(1)
+    }
+
+    // Keys are unique per run so the shared Postgres table needs no cleanup
+    async fn test_counts_within_window_impl(store: Arc<dyn RateLimitStore>) {
+        let key = format!("test:{}", Uuid::new_v4());
+        let other_key = format!("test:{}", Uuid::new_v4());
+        let now = chrono::Utc::now().naive_utc();
+
+        for _ in 0..3 {
+            assert!(store.incr_and_check(&key, 3, window(), now).await.unwrap());
+        }
+        assert!(!store.incr_and_check(&key, 3, window(), now).await.unwrap());
+        assert!(
+            !store
+                .incr_and_check(&key, 3, window(), now + Duration::minutes(59))
+                .await
+                .unwrap()
+        );
+
+        assert!(
+            store
+                .incr_and_check(&other_key, 3, window(), now)
+                .await
+                .unwrap()
+        );
+    }
+
+    async fn test_window_resets_after_expiry_impl(store: Arc<dyn RateLimitStore>) {
+        let key = format!("test:{}", Uuid::new_v4());
+        let now = chrono::Utc::now().naive_utc();
+        assert!(store.incr_and_check(&key, 1, window(), now).await.unwrap());
+        assert!(!store.incr_and_check(&key, 1, window(), now).await.unwrap());
+
+        let later = now + window();
+
+        assert!(
+            store
+                .incr_and_check(&key, 1, window(), later)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .incr_and_check(&key, 1, window(), later)
+                .await
+                .unwrap()
+        );
+    }
+
+    async fn test_count_reset_and_prune_impl(store: Arc<dyn RateLimitStore>) {
+        let key = format!("test:{}", Uuid::new_v4());
+        let stale_key = format!("test:{}", Uuid::new_v4());
+        let now = chrono::Utc::now().naive_utc();
+        let long_ago = now - Duration::days(2);
+        assert_eq!(store.current_count(&key, window(), now).await.unwrap(), 0);
+
+        for _ in 0..2 {
+            store.incr_and_check(&key, 5, window(), now).await.unwrap();
+        }
+        assert_eq!(store.current_count(&key, window(), now).await.unwrap(), 2);
+        assert_eq!(
+            store
+                .current_count(&key, window(), now + window())
+                .await
+                .unwrap(),
+            0
+        );
+
+        store.reset(&key).await.unwrap();
+        assert_eq!(store.current_count(&key, window(), now).await.unwrap(), 0);
+
+        store.incr_and_check(&key, 5, window(), now).await.unwrap();
+        store
+            .incr_and_check(&stale_key, 5, window(), long_ago)
+            .await
+            .unwrap();
+        let deleted = store.delete_expired(now - Duration::days(1)).await.unwrap();
+        assert!(deleted >= 1);
+        assert_eq!(
+            store
+                .current_count(&stale_key, window(), long_ago)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(store.current_count(&key, window(), now).await.unwrap(), 1);
+    }
+
+    #[cfg(feature = "sqlite")]
+    async fn setup_sqlite_store() -> Arc<dyn RateLimitStore> {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        Arc::new(SqliteRateLimitStore::new(pool))
+    }
+
+    #[cfg(feature = "postgres")]
+    async fn setup_postgres_store() -> Arc<dyn RateLimitStore> {
+        let database_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL environment variable must be set for PostgreSQL tests");
+        let pool = sqlx::PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to PostgreSQL database");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run PostgreSQL migrations");
+
+        Arc::new(PostgresRateLimitStore::new(pool))
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_counts_within_window() {
+        test_counts_within_window_impl(Arc::new(InMemoryRateLimitStore::new())).await;
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_window_resets_after_expiry() {
+        test_window_resets_after_expiry_impl(Arc::new(InMemoryRateLimitStore::new())).await;
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_prunes_only_windows_past_the_longest() {
+        let store = InMemoryRateLimitStore::new();
+        let now = chrono::Utc::now().naive_utc();
+        store
+            .incr_and_check("registration", 5, window(), now)
+            .await
+            .unwrap();
+
+        // A shorter window hit later must not cut the longer one short
+        let later = now + Duration::minutes(30);
+        store
+            .incr_and_check("login", 5, Duration::minutes(15), later)
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .current_count("registration", window(), later)
+                .await
+                .unwrap(),
+            1
+        );
+
+        store
+            .incr_and_check("login", 5, Duration::minutes(15), now + MAX_WINDOW)
+            .await
+            .unwrap();
+        let windows = store.windows.lock().unwrap();
+        assert!(!windows.contains_key("registration"));
+        assert_eq!(windows["login"].count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_count_reset_and_prune() {
+        test_count_reset_and_prune_impl(Arc::new(InMemoryRateLimitStore::new())).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_count_reset_and_prune() {
+        test_count_reset_and_prune_impl(setup_sqlite_store().await).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_count_reset_and_prune() {
+        test_count_reset_and_prune_impl(setup_postgres_store().await).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_counts_within_window() {
+        test_counts_within_window_impl(setup_sqlite_store().await).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_window_resets_after_expiry() {
+        test_window_resets_after_expiry_impl(setup_sqlite_store().await).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_counts_within_window() {
+        test_counts_within_window_impl(setup_postgres_store().await).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_window_resets_after_expiry() {
+        test_window_resets_after_expiry_impl(setup_postgres_store().await).await;
+    }
+}
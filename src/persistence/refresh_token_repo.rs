@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+use crate::{application::app_error::AppResult, domain::refresh_token::RefreshToken};
+
+/// Session details stored alongside a refresh token
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionMetadata<'a> {
+    pub user_agent: Option<&'a str>,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+// ============================================================================
+// Refresh Token Repository Trait
+// ============================================================================
+
+/// Trait for refresh token storage
+/// Only hashes of tokens are ever stored
+#[async_trait]
+pub trait RefreshTokenRepository: Send + Sync {
+    /// Store a new refresh token and return its id
+    async fn create_token(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+        session: SessionMetadata<'_>,
+    ) -> AppResult<Uuid>;
+
+    /// Get a token by the hash of its value, whether or not it's revoked
+    async fn get_token_by_hash(&self, token_hash: &str) -> AppResult<Option<RefreshToken>>;
+
+    /// Revoke a single token
+    /// Returns `false` if it was already revoked
+    async fn revoke_token(&self, id: Uuid, now: NaiveDateTime) -> AppResult<bool>;
+
+    /// Revoke every live token in a family and return how many were revoked
+    async fn revoke_family(&self, family_id: Uuid, now: NaiveDateTime) -> AppResult<u64>;
+
+    /// Revoke every live token of a user and return how many were revoked
+    async fn revoke_all_for_user(&self, user_id: Uuid, now: NaiveDateTime) -> AppResult<u64>;
+
+    /// Unrevoked, unexpired tokens of a user, newest first and then by id
+    async fn list_active_tokens(
+        &self,
+        user_id: Uuid,
+        now: NaiveDateTime,
+    ) -> AppResult<Vec<RefreshToken>>;
+
+    /// A page of the tokens `list_active_tokens` returns, in the same order
+    async fn list_active_tokens_page(
+        &self,
+        user_id: Uuid,
+        now: NaiveDateTime,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<Vec<RefreshToken>>;
+
+    /// How many tokens `list_active_tokens` returns
+    async fn count_active_tokens(&self, user_id: Uuid, now: NaiveDateTime) -> AppResult<u64>;
+
+    /// Delete every token that expired at or before `now`, revoked or not
+    /// Returns how many were deleted
+    async fn delete_expired(&self, now: NaiveDateTime) -> AppResult<u64>;
+}
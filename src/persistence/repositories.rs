@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use crate::persistence::{
+    AuditLogRepository, DbPool, EmailVerificationRepository, InviteCodeRepository,
+    PasswordResetRepository, RateLimitStore, RefreshTokenRepository, UserRepository,
+};
+#[cfg(feature = "postgres")]
+use crate::persistence::{
+    PostgresAuditLogRepository, PostgresEmailVerificationRepository, PostgresInviteCodeRepository,
+    PostgresPasswordResetRepository, PostgresRateLimitStore, PostgresRefreshTokenRepository,
+    PostgresUserRepository,
+};
+#[cfg(feature = "sqlite")]
+use crate::persistence::{
+    SqliteAuditLogRepository, SqliteEmailVerificationRepository, SqliteInviteCodeRepository,
+    SqlitePasswordResetRepository, SqliteRateLimitStore, SqliteRefreshTokenRepository,
+    SqliteUserRepository,
+};
+
+// ============================================================================
+// Repository Set
+// ============================================================================
+
+/// Every repository, backed by the same database pool
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub email_verification: Arc<dyn EmailVerificationRepository>,
+    pub password_reset: Arc<dyn PasswordResetRepository>,
+    pub refresh_tokens: Arc<dyn RefreshTokenRepository>,
+    pub invite_codes: Arc<dyn InviteCodeRepository>,
+    pub audit_log: Arc<dyn AuditLogRepository>,
+    pub rate_limits: Arc<dyn RateLimitStore>,
+}
+
+impl Repositories {
+    /// Create repositories based on database type
+    pub fn new(pool: &DbPool) -> Self {
+        match pool {
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pg_pool) => Self {
+                users: Arc::new(PostgresUserRepository::new(pg_pool.clone())),
+                email_verification: Arc::new(PostgresEmailVerificationRepository::new(
+                    pg_pool.clone(),
+                )),
+                password_reset: Arc::new(PostgresPasswordResetRepository::new(pg_pool.clone())),
+                refresh_tokens: Arc::new(PostgresRefreshTokenRepository::new(pg_pool.clone())),
+                invite_codes: Arc::new(PostgresInviteCodeRepository::new(pg_pool.clone())),
+                audit_log: Arc::new(PostgresAuditLogRepository::new(pg_pool.clone())),
+                rate_limits: Arc::new(PostgresRateLimitStore::new(pg_pool.clone())),
+            },
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(sqlite_pool) => Self {
+                users: Arc::new(SqliteUserRepository::new(sqlite_pool.clone())),
+                email_verification: Arc::new(SqliteEmailVerificationRepository::new(
+                    sqlite_pool.clone(),
+                )),
+                password_reset: Arc::new(SqlitePasswordResetRepository::new(sqlite_pool.clone())),
+                refresh_tokens: Arc::new(SqliteRefreshTokenRepository::new(sqlite_pool.clone())),
+                invite_codes: Arc::new(SqliteInviteCodeRepository::new(sqlite_pool.clone())),
+                audit_log: Arc::new(SqliteAuditLogRepository::new(sqlite_pool.clone())),
+                rate_limits: Arc::new(SqliteRateLimitStore::new(sqlite_pool.clone())),
+            },
+        }
+    }
+}
+
+// Run by CI once per single-backend build, via `--no-default-features --features <backend>`
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(feature = "postgres", feature = "sqlite"))]
+    #[test]
+    fn test_default_build_has_both_backends() {
+        assert_eq!(DbPool::COMPILED_BACKENDS, ["postgres", "sqlite"]);
+    }
+
+    #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+    #[tokio::test]
+    async fn test_postgres_only_build_has_only_postgres() {
+        assert_eq!(DbPool::COMPILED_BACKENDS, ["postgres"]);
+
+        // A lazy pool never connects, so this runs without a server
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        Repositories::new(&DbPool::Postgres(pool));
+    }
+
+    #[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+    #[tokio::test]
+    async fn test_sqlite_only_build_has_only_sqlite() {
+        assert_eq!(DbPool::COMPILED_BACKENDS, ["sqlite"]);
+
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        Repositories::new(&DbPool::Sqlite(pool));
+    }
+}
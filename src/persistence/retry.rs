@@ -0,0 +1,227 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use std::{future::Future, time::Duration};
+
+/// Attempts made by `retry_transient` callers that have no reason to pick their own
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Upper bound of the random pause between attempts
+const MAX_JITTER_MS: u64 = 50;
+
+/// Pause added per attempt already made, so a held lock gets longer to clear each time
+const BACKOFF_STEP_MS: u64 = 20;
+
+/// Postgres codes for `serialization_failure` and `deadlock_detected`
+const RETRYABLE_CODES: [&str; 2] = ["40001", "40P01"];
+
+/// SQLite primary result codes `SQLITE_BUSY` and `SQLITE_LOCKED`
+/// Extended codes such as `SQLITE_BUSY_SNAPSHOT` keep the primary code in their low byte
+#[cfg(feature = "sqlite")]
+const SQLITE_BUSY_CODES: [i64; 2] = [5, 6];
+
+// ============================================================================
+// Transient Failure Retry
+// ============================================================================
+
+/// Whether SQLite gave up on a write because another connection holds the database lock
+/// Only a `SqliteError` qualifies, Postgres SQLSTATEs such as `42501` would otherwise
+/// parse as numbers whose low byte looks busy
+#[cfg(feature = "sqlite")]
+pub fn is_sqlite_busy(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .filter(|db_error| {
+            db_error
+                .try_downcast_ref::<sqlx::sqlite::SqliteError>()
+                .is_some()
+        })
+        .and_then(|db_error| db_error.code())
+        .and_then(|code| code.parse::<i64>().ok())
+        .is_some_and(|code| SQLITE_BUSY_CODES.contains(&(code & 0xff)))
+}
+
+/// Without SQLite support no error can come from it
+#[cfg(not(feature = "sqlite"))]
+pub fn is_sqlite_busy(_error: &sqlx::Error) -> bool {
+    false
+}
+
+/// Whether the database aborted the statement in a way the client is expected to retry
+pub fn is_retryable(error: &sqlx::Error) -> bool {
+    is_sqlite_busy(error)
+        || error
+            .as_database_error()
+            .and_then(|db_error| db_error.code())
+            .is_some_and(|code| RETRYABLE_CODES.contains(&code.as_ref()))
+}
+
+/// Run a transaction, rerunning it from the start on serialization failures, deadlocks
+/// and SQLite lock contention
+/// `transaction` must begin and commit its own transaction so every attempt starts clean
+pub async fn retry_transient<T, F, Fut>(
+    max_attempts: u32,
+    mut transaction: F,
+) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match transaction().await {
+            Err(error) if attempt < max_attempts && is_retryable(&error) => {
+                tracing::warn!(
+                    attempt,
+                    error = %error,
+                    "Retrying transaction after transient failure"
+                );
+                // Jitter keeps colliding transactions from retrying in lockstep
+                let jitter = OsRng.next_u64() % (MAX_JITTER_MS + 1);
+                let backoff = BACKOFF_STEP_MS * u64::from(attempt);
+                tokio::time::sleep(Duration::from_millis(backoff + jitter)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::error::{DatabaseError, ErrorKind};
+    use std::{borrow::Cow, error::Error as StdError, fmt};
+
+    #[derive(Debug)]
+    struct FakeDbError(&'static str);
+
+    impl fmt::Display for FakeDbError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "database error {}", self.0)
+        }
+    }
+
+    impl StdError for FakeDbError {}
+
+    impl DatabaseError for FakeDbError {
+        fn message(&self) -> &str {
+            "fake"
+        }
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    fn db_error(code: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(FakeDbError(code)))
+    }
+
+    #[tokio::test]
+    async fn test_retries_serialization_failure_until_success() {
+        let mut calls = 0;
+
+        let result = retry_transient(3, || {
+            calls += 1;
+            let outcome = if calls < 3 {
+                Err(db_error("40001"))
+            } else {
+                Ok(calls)
+            };
+            async move { outcome }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let mut calls = 0;
+
+        let result: Result<(), _> = retry_transient(2, || {
+            calls += 1;
+            async { Err(db_error("40P01")) }
+        })
+        .await;
+
+        assert!(is_retryable(&result.unwrap_err()));
+        assert_eq!(calls, 2);
+    }
+
+    /// A real `SQLITE_BUSY` from writing while another connection holds the lock
+    #[cfg(feature = "sqlite")]
+    async fn sqlite_busy_error() -> sqlx::Error {
+        use sqlx::{Connection, SqliteConnection, sqlite::SqliteConnectOptions};
+        use std::str::FromStr;
+
+        let path =
+            std::env::temp_dir().join(format!("serverust-retry-{}.db", uuid::Uuid::new_v4()));
+        let options = SqliteConnectOptions::from_str(path.to_str().unwrap())
+            .unwrap()
+            .create_if_missing(true)
+            .busy_timeout(Duration::ZERO);
+        let mut holder = SqliteConnection::connect_with(&options).await.unwrap();
+        let mut writer = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::query("CREATE TABLE items (id INTEGER)")
+            .execute(&mut holder)
+            .await
+            .unwrap();
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut holder)
+            .await
+            .unwrap();
+
+        let error = sqlx::query("INSERT INTO items VALUES (1)")
+            .execute(&mut writer)
+            .await
+            .unwrap_err();
+        drop((holder, writer));
+        let _ = std::fs::remove_file(path);
+        error
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_busy_is_retryable() {
+        let error = sqlite_busy_error().await;
+
+        assert!(is_sqlite_busy(&error), "{}", error);
+        assert!(is_retryable(&error), "{}", error);
+    }
+
+    #[test]
+    fn test_postgres_codes_are_not_sqlite_busy() {
+        // insufficient_privilege, character_not_in_repertoire and indicator_overflow have a low
+        // byte of SQLITE_BUSY or SQLITE_LOCKED, and bare SQLite codes only count from SQLite
+        for code in ["42501", "22021", "22022", "5", "517"] {
+            assert!(!is_sqlite_busy(&db_error(code)), "{}", code);
+            assert!(!is_retryable(&db_error(code)), "{}", code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_other_errors() {
+        let mut calls = 0;
+
+        let result: Result<(), _> = retry_transient(3, || {
+            calls += 1;
+            async { Err(db_error("23505")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{Layer, filter::Targets, layer::Context};
+
+use crate::application::metrics::Metrics;
+
+pub const SLOW_ACQUIRES_COUNTER: &str = "db_slow_acquires_total";
+pub const SLOW_QUERIES_COUNTER: &str = "db_slow_queries_total";
+
+/// Where sqlx logs a connection that took longer than `acquire_slow_threshold` to check out
+const ACQUIRE_TARGET: &str = "sqlx::pool::acquire";
+/// Where sqlx logs a statement that ran longer than its `log_slow_statements` threshold
+const QUERY_TARGET: &str = "sqlx::query";
+
+// Process-wide, like the tracing subscriber that feeds them
+static SLOW_ACQUIRES: AtomicU64 = AtomicU64::new(0);
+static SLOW_QUERIES: AtomicU64 = AtomicU64::new(0);
+
+// ============================================================================
+// Slow Database Events
+// ============================================================================
+
+/// Counts the warnings sqlx logs for slow connection acquires and slow statements
+/// The warnings themselves come from the thresholds set on the pool and connection options
+pub struct SlowDbEventLayer;
+
+impl SlowDbEventLayer {
+    /// Only the sqlx warnings this layer counts, regardless of what `RUST_LOG` lets through
+    pub fn filter() -> Targets {
+        Targets::new()
+            .with_target(ACQUIRE_TARGET, Level::WARN)
+            .with_target(QUERY_TARGET, Level::WARN)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SlowDbEventLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() != Level::WARN {
+            return;
+        }
+        match metadata.target() {
+            ACQUIRE_TARGET => SLOW_ACQUIRES.fetch_add(1, Ordering::Relaxed),
+            QUERY_TARGET => SLOW_QUERIES.fetch_add(1, Ordering::Relaxed),
+            _ => return,
+        };
+    }
+}
+
+/// Slow acquires and slow statements counted since the process started
+pub fn slow_db_event_counts() -> (u64, u64) {
+    (
+        SLOW_ACQUIRES.load(Ordering::Relaxed),
+        SLOW_QUERIES.load(Ordering::Relaxed),
+    )
+}
+
+/// Publish the slow acquire and slow statement counts as counters
+pub fn record_slow_db_events(metrics: &Metrics) {
+    let (acquires, queries) = slow_db_event_counts();
+    metrics.set_counter(
+        SLOW_ACQUIRES_COUNTER,
+        "Connection acquires slower than DB_SLOW_ACQUIRE_MS",
+        acquires,
+    );
+    metrics.set_counter(
+        SLOW_QUERIES_COUNTER,
+        "Statements slower than DB_SLOW_QUERY_MS",
+        queries,
+    );
+}
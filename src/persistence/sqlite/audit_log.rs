@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    domain::audit::{AuditAction, AuditEntry},
+    persistence::{
+        audit_log_repo::{AuditLogFilter, AuditLogRepository},
+        sqlite::parse_timestamp,
+    },
+};
+
+// ============================================================================
+// SQLite Audit Log Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct SqliteAuditLogRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteAuditLogRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+// Database model for AuditEntry - SQLite
+#[derive(sqlx::FromRow, Debug)]
+pub struct AuditEntryDbSqlite {
+    pub id: String,
+    pub actor_id: Option<String>,
+    pub action: String,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: String,
+}
+
+impl TryFrom<AuditEntryDbSqlite> for AuditEntry {
+    type Error = AppError;
+
+    fn try_from(entry_db: AuditEntryDbSqlite) -> Result<Self, Self::Error> {
+        let parse_id = |id: &str| Uuid::parse_str(id).unwrap_or_else(|_| Uuid::nil());
+
+        Ok(AuditEntry {
+            id: parse_id(&entry_db.id),
+            actor_id: entry_db.actor_id.as_deref().map(parse_id),
+            action: entry_db.action.parse::<AuditAction>()?,
+            target_id: entry_db.target_id.as_deref().map(parse_id),
+            ip: entry_db.ip,
+            created_at: parse_timestamp(&entry_db.created_at)
+                .unwrap_or(chrono::DateTime::UNIX_EPOCH.naive_utc()),
+        })
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for SqliteAuditLogRepository {
+    async fn append(&self, entry: &AuditEntry) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO audit_log (id, actor_id, action, target_id, ip, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(entry.id.to_string())
+        .bind(entry.actor_id.map(|id| id.to_string()))
+        .bind(entry.action.as_str())
+        .bind(entry.target_id.map(|id| id.to_string()))
+        .bind(entry.ip.as_deref())
+        .bind(entry.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn list_entries(&self, limit: u32, offset: u32) -> AppResult<Vec<AuditEntry>> {
+        let entries = sqlx::query_as::<_, AuditEntryDbSqlite>(
+            "SELECT id, actor_id, action, target_id, ip, created_at FROM audit_log \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT ? OFFSET ?",
+        )
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        entries.into_iter().map(AuditEntry::try_from).collect()
+    }
+
+    async fn list_entries_after(
+        &self,
+        filter: &AuditLogFilter,
+        after: Option<(NaiveDateTime, Uuid)>,
+        limit: u32,
+    ) -> AppResult<Vec<AuditEntry>> {
+        // Entries are written with one timestamp format, so comparing the text keeps
+        // sub-millisecond order that julianday would round away
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT id, actor_id, action, target_id, ip, created_at FROM audit_log WHERE 1",
+        );
+        if let Some(from) = filter.from {
+            query.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(until) = filter.until {
+            query.push(" AND created_at < ").push_bind(until);
+        }
+        if let Some((created_at, id)) = after {
+            query
+                .push(" AND (created_at, id) > (")
+                .push_bind(created_at)
+                .push(", ")
+                .push_bind(id.to_string())
+                .push(")");
+        }
+        query
+            .push(" ORDER BY created_at, id LIMIT ")
+            .push_bind(i64::from(limit));
+
+        let entries = query
+            .build_query_as::<AuditEntryDbSqlite>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        entries.into_iter().map(AuditEntry::try_from).collect()
+    }
+
+    async fn count_entries(&self) -> AppResult<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(count as u64)
+    }
+}
@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    persistence::{email_verification_repo::EmailVerificationRepository, sqlite::NOW_MILLIS},
+};
+
+// ============================================================================
+// SQLite Email Verification Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct SqliteEmailVerificationRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteEmailVerificationRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EmailVerificationRepository for SqliteEmailVerificationRepository {
+    async fn create_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO email_verification_tokens (token_hash, user_id, expires_at) VALUES (?, ?, ?)",
+        )
+        .bind(token_hash)
+        .bind(user_id.to_string())
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn consume_token(&self, token_hash: &str, now: NaiveDateTime) -> AppResult<Option<Uuid>> {
+        let mut tx = self.pool.begin().await.map_err(AppError::from)?;
+
+        let user_id: Option<String> = sqlx::query_scalar(
+            "UPDATE email_verification_tokens SET used_at = ?2 \
+             WHERE token_hash = ?1 AND used_at IS NULL AND expires_at > ?2 \
+             RETURNING user_id",
+        )
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        if let Some(user_id) = &user_id {
+            sqlx::query(&format!(
+                "UPDATE users SET email_verified = 1, updated_at = {} WHERE id = ?",
+                NOW_MILLIS
+            ))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        }
+
+        tx.commit().await.map_err(AppError::from)?;
+
+        Ok(user_id.and_then(|id| Uuid::parse_str(&id).ok()))
+    }
+
+    async fn delete_expired(&self, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM email_verification_tokens WHERE expires_at <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    domain::{email::Email, user::Role, username::Username},
+    persistence::{invite_code_repo::InviteCodeRepository, sqlite::NOW_MILLIS},
+};
+
+// ============================================================================
+// SQLite Invite Code Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct SqliteInviteCodeRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteInviteCodeRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl InviteCodeRepository for SqliteInviteCodeRepository {
+    async fn create_code(&self, code_hash: &str, created_by: Uuid) -> AppResult<()> {
+        sqlx::query("INSERT INTO invite_codes (code_hash, created_by) VALUES (?, ?)")
+            .bind(code_hash)
+            .bind(created_by.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn register_with_code(
+        &self,
+        code_hash: &str,
+        now: NaiveDateTime,
+        username: &Username,
+        email: &Email,
+        password_hash: &str,
+        role: Role,
+    ) -> AppResult<Option<Uuid>> {
+        let mut tx = self.pool.begin().await.map_err(AppError::from)?;
+
+        // Claiming the code first means a concurrent registration with it finds it used
+        let claimed = sqlx::query(
+            "UPDATE invite_codes SET used_at = ?2 WHERE code_hash = ?1 AND used_at IS NULL",
+        )
+        .bind(code_hash)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+        if claimed.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let user_id = Uuid::new_v4();
+        sqlx::query(&format!(
+            "INSERT INTO users (id, username, email, password_hash, role, updated_at) VALUES (?, ?, ?, ?, ?, {})",
+            NOW_MILLIS
+        ))
+        .bind(user_id.to_string())
+        .bind(username.as_ref())
+        .bind(email.as_str())
+        .bind(password_hash)
+        .bind(role.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        sqlx::query("UPDATE invite_codes SET used_by = ? WHERE code_hash = ?")
+            .bind(user_id.to_string())
+            .bind(code_hash)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+
+        tx.commit().await.map_err(AppError::from)?;
+
+        Ok(Some(user_id))
+    }
+}
@@ -1,3 +1,27 @@
+use chrono::NaiveDateTime;
+
+pub mod audit_log;
+pub mod email_verification;
+pub mod invite_code;
+pub mod password_reset;
+pub mod rate_limit;
+pub mod refresh_token;
 pub mod user;
 
+pub use audit_log::SqliteAuditLogRepository;
+pub use email_verification::SqliteEmailVerificationRepository;
+pub use invite_code::SqliteInviteCodeRepository;
+pub use password_reset::SqlitePasswordResetRepository;
+pub use rate_limit::SqliteRateLimitStore;
+pub use refresh_token::SqliteRefreshTokenRepository;
 pub use user::SqliteUserRepository;
+
+/// Current time with millisecond precision, `CURRENT_TIMESTAMP` only has whole seconds
+pub(crate) const NOW_MILLIS: &str = "strftime('%Y-%m-%d %H:%M:%f', 'now')";
+
+// SQLite stores timestamps as text, with or without fractional seconds
+pub(crate) fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .ok()
+}
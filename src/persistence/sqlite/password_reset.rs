@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    persistence::{password_reset_repo::PasswordResetRepository, sqlite::NOW_MILLIS},
+};
+
+// ============================================================================
+// SQLite Password Reset Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct SqlitePasswordResetRepository {
+    pool: SqlitePool,
+}
+
+impl SqlitePasswordResetRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for SqlitePasswordResetRepository {
+    async fn create_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO password_reset_tokens (token_hash, user_id, expires_at) VALUES (?, ?, ?)",
+        )
+        .bind(token_hash)
+        .bind(user_id.to_string())
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn consume_token(
+        &self,
+        token_hash: &str,
+        now: NaiveDateTime,
+        new_password_hash: &str,
+    ) -> AppResult<Option<Uuid>> {
+        let mut tx = self.pool.begin().await.map_err(AppError::from)?;
+
+        let user_id: Option<String> = sqlx::query_scalar(
+            "UPDATE password_reset_tokens SET used_at = ?2 \
+             WHERE token_hash = ?1 AND used_at IS NULL AND expires_at > ?2 \
+             RETURNING user_id",
+        )
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+        if let Some(user_id) = &user_id {
+            sqlx::query(&format!(
+                "UPDATE users SET password_hash = ?, updated_at = {} WHERE id = ?",
+                NOW_MILLIS
+            ))
+            .bind(new_password_hash)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+        }
+
+        tx.commit().await.map_err(AppError::from)?;
+
+        Ok(user_id.and_then(|id| Uuid::parse_str(&id).ok()))
+    }
+
+    async fn delete_expired(&self, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM password_reset_tokens WHERE expires_at <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+}
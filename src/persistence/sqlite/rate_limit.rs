@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime};
+use sqlx::SqlitePool;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    persistence::rate_limit_store::RateLimitStore,
+};
+
+// ============================================================================
+// SQLite Rate Limit Store
+// ============================================================================
+
+#[derive(Clone)]
+pub struct SqliteRateLimitStore {
+    pool: SqlitePool,
+}
+
+impl SqliteRateLimitStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for SqliteRateLimitStore {
+    async fn incr_and_check(
+        &self,
+        key: &str,
+        limit: u32,
+        window: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<bool> {
+        // A single upsert, so concurrent hits from different instances can't lose counts
+        let count: i64 = sqlx::query_scalar(
+            "INSERT INTO rate_limits (key, window_started_at, count) VALUES (?1, ?2, 1) \
+             ON CONFLICT (key) DO UPDATE SET \
+                 count = CASE WHEN rate_limits.window_started_at <= ?3 THEN 1 \
+                              ELSE rate_limits.count + 1 END, \
+                 window_started_at = CASE WHEN rate_limits.window_started_at <= ?3 THEN ?2 \
+                                          ELSE rate_limits.window_started_at END \
+             RETURNING count",
+        )
+        .bind(key)
+        .bind(now)
+        .bind(now - window)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(count <= i64::from(limit))
+    }
+
+    async fn current_count(
+        &self,
+        key: &str,
+        window: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<u32> {
+        let count: Option<i64> = sqlx::query_scalar(
+            "SELECT count FROM rate_limits WHERE key = ?1 AND window_started_at > ?2",
+        )
+        .bind(key)
+        .bind(now - window)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(count.map_or(0, |count| u32::try_from(count).unwrap_or(0)))
+    }
+
+    async fn reset(&self, key: &str) -> AppResult<()> {
+        sqlx::query("DELETE FROM rate_limits WHERE key = ?1")
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn delete_expired(&self, cutoff: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM rate_limits WHERE window_started_at <= ?1")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,217 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    application::app_error::{AppError, AppResult},
+    domain::refresh_token::RefreshToken,
+    persistence::{
+        refresh_token_repo::{RefreshTokenRepository, SessionMetadata},
+        sqlite::parse_timestamp,
+    },
+};
+
+// ============================================================================
+// SQLite Refresh Token Repository
+// ============================================================================
+
+#[derive(Clone)]
+pub struct SqliteRefreshTokenRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRefreshTokenRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+// Database model for RefreshToken - SQLite
+#[derive(sqlx::FromRow, Debug)]
+pub struct RefreshTokenDbSqlite {
+    pub id: String,
+    pub user_id: String,
+    pub family_id: String,
+    pub expires_at: String,
+    pub revoked_at: Option<String>,
+    pub created_at: String,
+    pub user_agent: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+impl From<RefreshTokenDbSqlite> for RefreshToken {
+    fn from(token_db: RefreshTokenDbSqlite) -> Self {
+        let parse_id = |id: &str| Uuid::parse_str(id).unwrap_or_else(|_| Uuid::nil());
+        let now = chrono::Utc::now().naive_utc();
+
+        RefreshToken {
+            id: parse_id(&token_db.id),
+            user_id: parse_id(&token_db.user_id),
+            family_id: parse_id(&token_db.family_id),
+            // An unreadable expiry is treated as already expired
+            expires_at: parse_timestamp(&token_db.expires_at).unwrap_or(now),
+            revoked_at: token_db.revoked_at.as_deref().and_then(parse_timestamp),
+            created_at: parse_timestamp(&token_db.created_at).unwrap_or(now),
+            user_agent: token_db.user_agent,
+            last_used_at: token_db.last_used_at.as_deref().and_then(parse_timestamp),
+        }
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for SqliteRefreshTokenRepository {
+    async fn create_token(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+        session: SessionMetadata<'_>,
+    ) -> AppResult<Uuid> {
+        let id = Uuid::new_v4();
+
+        sqlx::query(
+            "INSERT INTO refresh_tokens \
+             (id, user_id, family_id, token_hash, expires_at, user_agent, last_used_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id.to_string())
+        .bind(user_id.to_string())
+        .bind(family_id.to_string())
+        .bind(token_hash)
+        .bind(expires_at)
+        .bind(session.user_agent)
+        .bind(session.last_used_at)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(id)
+    }
+
+    async fn get_token_by_hash(&self, token_hash: &str) -> AppResult<Option<RefreshToken>> {
+        let token = sqlx::query_as::<_, RefreshTokenDbSqlite>(
+            "SELECT id, user_id, family_id, expires_at, revoked_at, created_at, \
+             user_agent, last_used_at \
+             FROM refresh_tokens WHERE token_hash = ?",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(token.map(|t| t.into()))
+    }
+
+    async fn revoke_token(&self, id: Uuid, now: NaiveDateTime) -> AppResult<bool> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL",
+        )
+        .bind(now)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn revoke_family(&self, family_id: Uuid, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = ? WHERE family_id = ? AND revoked_at IS NULL",
+        )
+        .bind(now)
+        .bind(family_id.to_string())
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = ?2 \
+             WHERE user_id = ?1 AND revoked_at IS NULL AND expires_at > ?2",
+        )
+        .bind(user_id.to_string())
+        .bind(now)
+        .execute(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn list_active_tokens(
+        &self,
+        user_id: Uuid,
+        now: NaiveDateTime,
+    ) -> AppResult<Vec<RefreshToken>> {
+        let tokens = sqlx::query_as::<_, RefreshTokenDbSqlite>(
+            "SELECT id, user_id, family_id, expires_at, revoked_at, created_at, \
+             user_agent, last_used_at \
+             FROM refresh_tokens \
+             WHERE user_id = ? AND revoked_at IS NULL AND expires_at > ? \
+             ORDER BY created_at DESC, id DESC",
+        )
+        .bind(user_id.to_string())
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(tokens.into_iter().map(|t| t.into()).collect())
+    }
+
+    async fn list_active_tokens_page(
+        &self,
+        user_id: Uuid,
+        now: NaiveDateTime,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<Vec<RefreshToken>> {
+        let tokens = sqlx::query_as::<_, RefreshTokenDbSqlite>(
+            "SELECT id, user_id, family_id, expires_at, revoked_at, created_at, \
+             user_agent, last_used_at \
+             FROM refresh_tokens \
+             WHERE user_id = ? AND revoked_at IS NULL AND expires_at > ? \
+             ORDER BY created_at DESC, id DESC \
+             LIMIT ? OFFSET ?",
+        )
+        .bind(user_id.to_string())
+        .bind(now)
+        .bind(i64::from(limit))
+        .bind(i64::from(offset))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(tokens.into_iter().map(|t| t.into()).collect())
+    }
+
+    async fn count_active_tokens(&self, user_id: Uuid, now: NaiveDateTime) -> AppResult<u64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM refresh_tokens \
+             WHERE user_id = ? AND revoked_at IS NULL AND expires_at > ?",
+        )
+        .bind(user_id.to_string())
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(count as u64)
+    }
+
+    async fn delete_expired(&self, now: NaiveDateTime) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM refresh_tokens WHERE expires_at <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,13 +1,22 @@
 use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use serde::Serialize;
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use time::Duration;
 use uuid::Uuid;
 
 use crate::{
     application::app_error::{AppError, AppResult},
-    domain::user::User,
-    persistence::user_repo::UserRepository,
+    domain::{
+        email::Email,
+        user::{Role, User},
+        username::Username,
+    },
+    persistence::{
+        retry::{DEFAULT_MAX_ATTEMPTS, retry_transient},
+        sqlite::{NOW_MILLIS, parse_timestamp},
+        user_repo::{UserFilter, UserRepository, escape_like, purge_cutoff},
+    },
 };
 
 // ============================================================================
@@ -25,6 +34,13 @@ impl SqliteUserRepository {
     }
 }
 
+const USER_COLUMNS: &str = "id, username, email, password_hash, role, email_verified, last_login_at, created_at, updated_at, deleted_at";
+
+// Holds unless the row is the only live admin. SQLite runs one write at a time, so
+// the count can't change between this check and the write it guards
+const KEEPS_AN_ADMIN: &str = "(role <> 'admin' OR \
+     (SELECT COUNT(*) FROM users WHERE role = 'admin' AND deleted_at IS NULL) > 1)";
+
 // Database model for User - SQLite
 #[derive(sqlx::FromRow, Debug, Serialize)]
 pub struct UserDbSqlite {
@@ -32,23 +48,37 @@ pub struct UserDbSqlite {
     pub username: String,
     pub email: String,
     pub password_hash: String,
+    pub role: String,
+    pub email_verified: bool,
+    pub last_login_at: Option<String>,
     pub created_at: String,
+    /// Only missing for rows inserted around the repository, such as test fixtures
+    pub updated_at: Option<String>,
+    pub deleted_at: Option<String>,
 }
 
 impl From<UserDbSqlite> for User {
     fn from(user_db: UserDbSqlite) -> Self {
         let id = Uuid::parse_str(&user_db.id).unwrap_or_else(|_| Uuid::new_v4());
 
-        let created_at = NaiveDateTime::parse_from_str(&user_db.created_at, "%Y-%m-%d %H:%M:%S%.f")
-            .or_else(|_| NaiveDateTime::parse_from_str(&user_db.created_at, "%Y-%m-%d %H:%M:%S"))
-            .unwrap_or_else(|_| chrono::Utc::now().naive_utc());
+        let created_at =
+            parse_timestamp(&user_db.created_at).unwrap_or_else(|| chrono::Utc::now().naive_utc());
 
         User {
             id,
-            username: user_db.username,
-            email: user_db.email,
-            password_hash: user_db.password_hash,
+            username: Username::new_unchecked(user_db.username),
+            email: Email::new_unchecked(user_db.email),
+            password_hash: user_db.password_hash.into(),
+            role: user_db.role.parse().unwrap_or_default(),
+            email_verified: user_db.email_verified,
+            last_login_at: user_db.last_login_at.as_deref().and_then(parse_timestamp),
+            updated_at: user_db
+                .updated_at
+                .as_deref()
+                .and_then(parse_timestamp)
+                .unwrap_or(created_at),
             created_at,
+            deleted_at: user_db.deleted_at.as_deref().and_then(parse_timestamp),
         }
     }
 }
@@ -56,30 +86,253 @@ impl From<UserDbSqlite> for User {
 // Implement the UserRepository trait for SQLite
 #[async_trait]
 impl UserRepository for SqliteUserRepository {
-    async fn create_user(&self, username: &str, email: &str, password_hash: &str) -> AppResult<()> {
-        let uuid = Uuid::new_v4().to_string();
-
-        sqlx::query("INSERT INTO users (id, username, email, password_hash) VALUES (?, ?, ?, ?)")
-            .bind(uuid)
-            .bind(username)
-            .bind(email)
-            .bind(password_hash)
-            .execute(&self.pool)
+    async fn create_user_with_role(
+        &self,
+        username: &Username,
+        email: &Email,
+        password_hash: &str,
+        role: Role,
+    ) -> AppResult<Uuid> {
+        let uuid = Uuid::new_v4();
+        let sql = format!(
+            "INSERT INTO users (id, username, email, password_hash, role, updated_at) VALUES (?, ?, ?, ?, ?, {})",
+            NOW_MILLIS
+        );
+
+        // Writes take the database lock, so they wait out another writer instead of failing
+        retry_transient(DEFAULT_MAX_ATTEMPTS, || {
+            sqlx::query(&sql)
+                .bind(uuid.to_string())
+                .bind(username.as_ref())
+                .bind(email.as_str())
+                .bind(password_hash)
+                .bind(role.as_str())
+                .execute(&self.pool)
+        })
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(uuid)
+    }
+
+    async fn get_user_by_id(&self, id: &Uuid) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbSqlite>(&format!(
+            "SELECT {} FROM users WHERE id = ? AND deleted_at IS NULL",
+            USER_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_user_by_id_including_deleted(&self, id: &Uuid) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbSqlite>(&format!(
+            "SELECT {} FROM users WHERE id = ?",
+            USER_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_users_by_ids(&self, ids: &[Uuid]) -> AppResult<Vec<User>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM users WHERE deleted_at IS NULL AND id IN (",
+            USER_COLUMNS
+        ));
+        let mut bound = query.separated(", ");
+        for id in ids {
+            bound.push_bind(id.to_string());
+        }
+        query.push(")");
+
+        let users = query
+            .build_query_as::<UserDbSqlite>()
+            .fetch_all(&self.pool)
             .await
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
-        Ok(())
+        Ok(users.into_iter().map(|u| u.into()).collect())
     }
 
     async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>> {
-        let user = sqlx::query_as::<_, UserDbSqlite>(
-            "SELECT id, username, email, password_hash, created_at FROM users WHERE username = ?",
-        )
+        let user = sqlx::query_as::<_, UserDbSqlite>(&format!(
+            "SELECT {} FROM users WHERE username = ? COLLATE NOCASE AND deleted_at IS NULL",
+            USER_COLUMNS
+        ))
         .bind(username)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_user_by_email(&self, email: &Email) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbSqlite>(&format!(
+            "SELECT {} FROM users WHERE email = ? AND deleted_at IS NULL",
+            USER_COLUMNS
+        ))
+        .bind(email.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(user.map(|u| u.into()))
+    }
+
+    async fn get_user_by_identifier(&self, identifier: &str) -> AppResult<Option<User>> {
+        let user = sqlx::query_as::<_, UserDbSqlite>(&format!(
+            "SELECT {} FROM users \
+             WHERE (username = ?1 COLLATE NOCASE OR email = lower(?1)) AND deleted_at IS NULL \
+             ORDER BY julianday(created_at), id LIMIT 1",
+            USER_COLUMNS
+        ))
+        .bind(identifier)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(AppError::from)?;
 
         Ok(user.map(|u| u.into()))
     }
+
+    async fn touch_last_login(&self, id: &Uuid, at: NaiveDateTime) -> AppResult<()> {
+        let sql = format!(
+            "UPDATE users SET last_login_at = ?, updated_at = {} WHERE id = ?",
+            NOW_MILLIS
+        );
+        retry_transient(DEFAULT_MAX_ATTEMPTS, || {
+            sqlx::query(&sql)
+                .bind(at.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+                .bind(id.to_string())
+                .execute(&self.pool)
+        })
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(())
+    }
+
+    async fn set_role(&self, id: &Uuid, role: Role) -> AppResult<bool> {
+        let sql = format!(
+            "UPDATE users SET role = ?1, updated_at = {} \
+             WHERE id = ?2 AND deleted_at IS NULL AND (?1 = 'admin' OR {})",
+            NOW_MILLIS, KEEPS_AN_ADMIN
+        );
+        let result = retry_transient(DEFAULT_MAX_ATTEMPTS, || {
+            sqlx::query(&sql)
+                .bind(role.as_str())
+                .bind(id.to_string())
+                .execute(&self.pool)
+        })
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<Vec<User>> {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "SELECT {} FROM users WHERE deleted_at IS NULL",
+            USER_COLUMNS
+        ));
+        if let Some(prefix) = &filter.username_prefix {
+            query
+                .push(" AND LOWER(username) LIKE ")
+                .push_bind(format!("{}%", escape_like(&prefix.to_lowercase())))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(email_verified) = filter.email_verified {
+            query
+                .push(" AND email_verified = ")
+                .push_bind(email_verified);
+        }
+        if let Some(role) = filter.role {
+            query.push(" AND role = ").push_bind(role.as_str());
+        }
+        if let Some(created_after) = filter.created_after {
+            // Timestamps are text, so compare them as julian days rather than strings
+            query
+                .push(" AND julianday(created_at) > julianday(")
+                .push_bind(created_after.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+                .push(")");
+        }
+        query
+            .push(" ORDER BY julianday(created_at) DESC LIMIT ")
+            .push_bind(i64::from(limit))
+            .push(" OFFSET ")
+            .push_bind(i64::from(offset));
+
+        let users = query
+            .build_query_as::<UserDbSqlite>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(users.into_iter().map(User::from).collect())
+    }
+
+    async fn soft_delete_user(&self, id: &Uuid, deleted_at: NaiveDateTime) -> AppResult<bool> {
+        let sql = format!(
+            "UPDATE users SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL AND {}",
+            KEEPS_AN_ADMIN
+        );
+        let result = retry_transient(DEFAULT_MAX_ATTEMPTS, || {
+            sqlx::query(&sql)
+                .bind(deleted_at.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+                .bind(id.to_string())
+                .execute(&self.pool)
+        })
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn restore_user(&self, id: &Uuid) -> AppResult<bool> {
+        let sql = format!(
+            "UPDATE users SET deleted_at = NULL, updated_at = {} \
+             WHERE id = ? AND deleted_at IS NOT NULL",
+            NOW_MILLIS
+        );
+        let result = retry_transient(DEFAULT_MAX_ATTEMPTS, || {
+            sqlx::query(&sql).bind(id.to_string()).execute(&self.pool)
+        })
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_deleted_users(
+        &self,
+        older_than: Duration,
+        now: NaiveDateTime,
+    ) -> AppResult<u64> {
+        let cutoff = purge_cutoff(older_than, now)?.format("%Y-%m-%d %H:%M:%S%.f");
+        let result = retry_transient(DEFAULT_MAX_ATTEMPTS, || {
+            sqlx::query("DELETE FROM users WHERE julianday(deleted_at) < julianday(?)")
+                .bind(cutoff.to_string())
+                .execute(&self.pool)
+        })
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(result.rows_affected())
+    }
 }
@@ -0,0 +1,218 @@
+use chrono::{NaiveDateTime, Utc};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    application::app_error::AppResult,
+    persistence::{DbPool, Repositories, rate_limit_store::MAX_WINDOW},
+};
+
+// ============================================================================
+// Token Cleanup
+// ============================================================================
+
+/// How many expired tokens and rate limit windows one cleanup pass deleted, per table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgedTokens {
+    pub refresh: u64,
+    pub password_reset: u64,
+    pub email_verification: u64,
+    pub rate_limits: u64,
+}
+
+impl PurgedTokens {
+    pub fn total(&self) -> u64 {
+        self.refresh + self.password_reset + self.email_verification + self.rate_limits
+    }
+}
+
+/// Delete every refresh, password reset and email verification token that expired by `now`,
+/// and every rate limit window that no limiter counts over any more
+pub async fn purge_expired_tokens(
+    repositories: &Repositories,
+    now: NaiveDateTime,
+) -> AppResult<PurgedTokens> {
+    let window_cutoff = now - MAX_WINDOW;
+    Ok(PurgedTokens {
+        refresh: repositories.refresh_tokens.delete_expired(now).await?,
+        password_reset: repositories.password_reset.delete_expired(now).await?,
+        email_verification: repositories.email_verification.delete_expired(now).await?,
+        rate_limits: repositories
+            .rate_limits
+            .delete_expired(window_cutoff)
+            .await?,
+    })
+}
+
+/// Purge expired tokens every `every` until `shutdown` is cancelled
+/// A pass already running is finished first, so the task never stops halfway through
+pub fn spawn_token_cleanup(
+    pool: DbPool,
+    every: Duration,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    let repositories = Repositories::new(&pool);
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(every);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticks.tick() => {}
+            }
+
+            match purge_expired_tokens(&repositories, Utc::now().naive_utc()).await {
+                Ok(purged) if purged.total() > 0 => tracing::info!(
+                    refresh = purged.refresh,
+                    password_reset = purged.password_reset,
+                    email_verification = purged.email_verification,
+                    rate_limits = purged.rate_limits,
+                    "Deleted expired tokens"
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "Failed to delete expired tokens"),
+            }
+        }
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::{Email, Username},
+        persistence::refresh_token_repo::SessionMetadata,
+    };
+    use uuid::Uuid;
+
+    async fn setup() -> (DbPool, Repositories, Uuid) {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .unwrap();
+        let pool = DbPool::Sqlite(pool);
+        let repositories = Repositories::new(&pool);
+        let user_id = repositories
+            .users
+            .create_user(
+                &Username::parse("testuser").unwrap(),
+                &Email::parse("testuser@gmail.com").unwrap(),
+                "hash",
+            )
+            .await
+            .unwrap();
+        (pool, repositories, user_id)
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_only_expired_tokens() {
+        let (_pool, repositories, user_id) = setup().await;
+        let now = Utc::now().naive_utc();
+        let past = now - chrono::Duration::minutes(1);
+        let future = now + chrono::Duration::minutes(1);
+
+        let window_opened = |ago: chrono::Duration| now - ago;
+        for (key, started) in [
+            (
+                "stale",
+                window_opened(MAX_WINDOW + chrono::Duration::minutes(1)),
+            ),
+            ("open", window_opened(chrono::Duration::minutes(1))),
+        ] {
+            repositories
+                .rate_limits
+                .incr_and_check(key, 5, MAX_WINDOW, started)
+                .await
+                .unwrap();
+        }
+
+        for (hash, expires_at) in [("expired", past), ("live", future)] {
+            repositories
+                .refresh_tokens
+                .create_token(
+                    user_id,
+                    Uuid::new_v4(),
+                    hash,
+                    expires_at,
+                    SessionMetadata::default(),
+                )
+                .await
+                .unwrap();
+            repositories
+                .password_reset
+                .create_token(user_id, hash, expires_at)
+                .await
+                .unwrap();
+            repositories
+                .email_verification
+                .create_token(user_id, hash, expires_at)
+                .await
+                .unwrap();
+        }
+
+        let purged = purge_expired_tokens(&repositories, now).await.unwrap();
+        assert_eq!(
+            purged,
+            PurgedTokens {
+                refresh: 1,
+                password_reset: 1,
+                email_verification: 1,
+                rate_limits: 1,
+            }
+        );
+
+        let refresh = &repositories.refresh_tokens;
+        assert!(
+            refresh
+                .get_token_by_hash("expired")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(refresh.get_token_by_hash("live").await.unwrap().is_some());
+        assert_eq!(
+            repositories
+                .email_verification
+                .consume_token("live", now)
+                .await
+                .unwrap(),
+            Some(user_id)
+        );
+        assert_eq!(
+            repositories
+                .password_reset
+                .consume_token("live", now, "new_hash")
+                .await
+                .unwrap(),
+            Some(user_id)
+        );
+
+        assert_eq!(
+            repositories
+                .rate_limits
+                .current_count("open", MAX_WINDOW, now)
+                .await
+                .unwrap(),
+            1
+        );
+
+        let purged = purge_expired_tokens(&repositories, now).await.unwrap();
+        assert_eq!(purged.total(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_task_stops_when_cancelled() {
+        let (pool, _repositories, _) = setup().await;
+        let shutdown = CancellationToken::new();
+
+        let cleanup = spawn_token_cleanup(pool, Duration::from_secs(3600), shutdown.clone());
+        shutdown.cancel();
+
+        tokio::time::timeout(Duration::from_secs(5), cleanup)
+            .await
+            .expect("cleanup task did not stop")
+            .unwrap();
+    }
+}
@@ -1,19 +1,110 @@
 use async_trait::async_trait;
-use sqlx::{PgPool, SqlitePool};
+use chrono::NaiveDateTime;
+#[cfg(feature = "postgres")]
+use sqlx::PgPool;
+#[cfg(feature = "sqlite")]
+use sqlx::SqlitePool;
+use time::Duration;
+use uuid::Uuid;
 
-use crate::application::app_error::AppResult;
-use crate::domain::user::User;
+use crate::application::app_error::{AppError, AppResult};
+use crate::domain::{
+    email::Email,
+    user::{Role, User},
+    username::Username,
+};
 
 // ============================================================================
 // Database Pool Enum
 // ============================================================================
 
+/// Longest a ping may take before the database counts as unreachable
+const PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
 #[derive(Clone)]
 pub enum DbPool {
+    #[cfg(feature = "postgres")]
     Postgres(PgPool),
+    #[cfg(feature = "sqlite")]
     Sqlite(SqlitePool),
 }
 
+impl DbPool {
+    /// Names of the backends this build was compiled with
+    pub const COMPILED_BACKENDS: &[&str] = &[
+        #[cfg(feature = "postgres")]
+        "postgres",
+        #[cfg(feature = "sqlite")]
+        "sqlite",
+    ];
+
+    /// Name of the database backend, for diagnostics
+    pub fn backend(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(_) => "postgres",
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(_) => "sqlite",
+        }
+    }
+
+    /// Check the database answers a trivial query within a couple of seconds
+    /// A slow database is reported as `ServiceUnavailable`, the same as an exhausted pool
+    pub async fn ping(&self) -> AppResult<()> {
+        let query = async {
+            match self {
+                #[cfg(feature = "postgres")]
+                DbPool::Postgres(pool) => sqlx::query("SELECT 1").execute(pool).await.map(|_| ()),
+                #[cfg(feature = "sqlite")]
+                DbPool::Sqlite(pool) => sqlx::query("SELECT 1").execute(pool).await.map(|_| ()),
+            }
+        };
+
+        tokio::time::timeout(PING_TIMEOUT, query)
+            .await
+            .map_err(|_| AppError::ServiceUnavailable("Database ping timed out".into()))?
+            .map_err(AppError::from)
+    }
+
+    /// Close the pool, waiting for checked-out connections to be returned first
+    /// Any query made afterwards fails with `PoolClosed`
+    pub async fn close(&self) {
+        match self {
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => pool.close().await,
+            #[cfg(feature = "sqlite")]
+            DbPool::Sqlite(pool) => pool.close().await,
+        }
+    }
+}
+
+// ============================================================================
+// User Filter
+// ============================================================================
+
+/// Optional conditions for listing users, combined with `AND`
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    /// Case-insensitive username prefix
+    pub username_prefix: Option<String>,
+    pub email_verified: Option<bool>,
+    pub role: Option<Role>,
+    /// Only users created strictly after this moment
+    pub created_after: Option<NaiveDateTime>,
+}
+
+/// Escape `%`, `_` and `\` so a value matches literally inside `LIKE ... ESCAPE '\'`
+pub(crate) fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 // ============================================================================
 // User Repository Trait
 // ============================================================================
@@ -22,22 +113,98 @@ pub enum DbPool {
 /// This trait is implemented by both PostgreSQL and SQLite repositories
 #[async_trait]
 pub trait UserRepository: Send + Sync {
-    /// Create a new user in the database
-    async fn create_user(&self, username: &str, email: &str, password_hash: &str) -> AppResult<()>;
+    /// Create a new user with the `user` role and return its id
+    async fn create_user(
+        &self,
+        username: &Username,
+        email: &Email,
+        password_hash: &str,
+    ) -> AppResult<Uuid> {
+        self.create_user_with_role(username, email, password_hash, Role::User)
+            .await
+    }
+
+    /// Create a new user already holding `role` and return its id
+    async fn create_user_with_role(
+        &self,
+        username: &Username,
+        email: &Email,
+        password_hash: &str,
+        role: Role,
+    ) -> AppResult<Uuid>;
+
+    /// Get a user by their id
+    async fn get_user_by_id(&self, id: &Uuid) -> AppResult<Option<User>>;
+
+    /// Get a user by their id even if soft-deleted, with `deleted_at` set when they are
+    /// `None` only when no row has this id, for admin tooling
+    async fn get_user_by_id_including_deleted(&self, id: &Uuid) -> AppResult<Option<User>>;
+
+    /// Get every live user among `ids` in one query, in no particular order
+    /// Missing and soft-deleted ids are left out rather than reported
+    async fn get_users_by_ids(&self, ids: &[Uuid]) -> AppResult<Vec<User>>;
 
     /// Get a user by their username
     async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>>;
+
+    /// Get a user by their email address
+    async fn get_user_by_email(&self, email: &Email) -> AppResult<Option<User>>;
+
+    /// Get a user whose username or email matches `identifier`, ignoring case, in one query
+    /// Emails are stored lowercased, so both sides of the `OR` can use their unique index
+    /// The schema keeps the two apart, but should both match the oldest account wins
+    async fn get_user_by_identifier(&self, identifier: &str) -> AppResult<Option<User>>;
+
+    /// Record that a user logged in at `at`
+    async fn touch_last_login(&self, id: &Uuid, at: NaiveDateTime) -> AppResult<()>;
+
+    /// Change a live user's role, checking and writing in one statement
+    /// Returns false when no live user has this id, or when it would demote the last admin
+    async fn set_role(&self, id: &Uuid, role: Role) -> AppResult<bool>;
+
+    /// List a page of users matching every set filter, newest first
+    async fn list_users(
+        &self,
+        filter: &UserFilter,
+        limit: u32,
+        offset: u32,
+    ) -> AppResult<Vec<User>>;
+
+    /// Mark a user as deleted at `deleted_at`, hiding them from every lookup above
+    /// Returns false when no live user has this id, or when they are the last admin
+    async fn soft_delete_user(&self, id: &Uuid, deleted_at: NaiveDateTime) -> AppResult<bool>;
+
+    /// Undo a soft delete, making the user visible to every lookup again
+    /// Returns false when no soft-deleted user has this id
+    async fn restore_user(&self, id: &Uuid) -> AppResult<bool>;
+
+    /// Permanently remove users soft-deleted more than `older_than` before `now`
+    /// Returns how many rows were removed
+    async fn purge_deleted_users(&self, older_than: Duration, now: NaiveDateTime)
+    -> AppResult<u64>;
+}
+
+/// Latest `deleted_at` a user may have and still be purged
+/// A retention window reaching past the earliest representable date is a validation error
+pub(crate) fn purge_cutoff(older_than: Duration, now: NaiveDateTime) -> AppResult<NaiveDateTime> {
+    chrono::Duration::try_seconds(older_than.whole_seconds())
+        .and_then(|window| now.checked_sub_signed(window))
+        .ok_or_else(|| AppError::Validation("Retention window is too long".into()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::persistence::Repositories;
+    #[cfg(feature = "postgres")]
     use crate::persistence::postgres::user::PostgresUserRepository;
+    #[cfg(feature = "sqlite")]
     use crate::persistence::sqlite::user::SqliteUserRepository;
+    use chrono::Utc;
     use std::sync::Arc;
-    use uuid::Uuid;
 
     // Helper to create SQLite test repository
+    #[cfg(feature = "sqlite")]
     async fn setup_sqlite_repo() -> Arc<dyn UserRepository> {
         let pool = sqlx::SqlitePool::connect(":memory:")
             .await
@@ -52,6 +219,7 @@ mod tests {
         Arc::new(SqliteUserRepository::new(pool))
     }
 
+    #[cfg(feature = "postgres")]
     async fn setup_postgres_repo() -> Arc<dyn UserRepository> {
         let database_url = std::env::var("DATABASE_URL")
             .expect("DATABASE_URL environment variable must be set for PostgreSQL tests");
@@ -95,33 +263,724 @@ mod tests {
 
     async fn test_create_and_get_user_impl(repo: Arc<dyn UserRepository>) {
         // Create a user
-        let username = generate_test_username();
-        let email = format!("{}@example.com", username);
+        let username = Username::parse(generate_test_username()).unwrap();
+        let email = Email::parse(format!("{}@example.com", username)).unwrap();
         let password_hash = "hashed_password";
 
-        repo.create_user(&username, &email, password_hash)
+        let id = repo
+            .create_user(&username, &email, password_hash)
             .await
             .expect("Failed to create user");
 
         // Get user by username
         let user = repo
-            .get_user_by_username(&username)
+            .get_user_by_username(username.as_str())
             .await
             .expect("Failed to get user");
 
         assert!(user.is_some());
         let user = user.unwrap();
+        assert_eq!(user.id, id);
         assert_eq!(user.username, username);
         assert_eq!(user.email, email);
-        assert_eq!(user.password_hash, password_hash);
+        assert_eq!(user.password_hash.expose(), password_hash);
+        assert_eq!(user.role, Role::User);
+        assert!(!user.email_verified);
+
+        // Get user by id
+        let user = repo
+            .get_user_by_id(&id)
+            .await
+            .expect("Failed to get user by id");
+        assert_eq!(user.map(|u| u.username), Some(username.clone()));
+
+        // Get user by email
+        let user = repo
+            .get_user_by_email(&email)
+            .await
+            .expect("Failed to get user by email");
+        assert_eq!(user.map(|u| u.id), Some(id));
+    }
+
+    async fn test_overlong_values_are_validation_errors_impl(repo: Arc<dyn UserRepository>) {
+        // Bypass the domain checks to hit the schema constraints directly
+        let long_username = Username::new_unchecked("u".repeat(33));
+        let email = Email::parse(format!("{}@example.com", generate_test_username())).unwrap();
+        let result = repo
+            .create_user(&long_username, &email, "hashed_password")
+            .await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        let username = Username::parse(generate_test_username()).unwrap();
+        let long_email = Email::new_unchecked(format!("{}@example.com", "e".repeat(250)));
+        let result = repo
+            .create_user(&username, &long_email, "hashed_password")
+            .await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+
+        // Values right at the limits are still accepted
+        let base = generate_test_username();
+        let max_username =
+            Username::new_unchecked(format!("{}{}", base, "u".repeat(32 - base.len())));
+        let domain = "@example.com";
+        let max_email = Email::new_unchecked(format!(
+            "{}{}{}",
+            base,
+            "e".repeat(254 - base.len() - domain.len()),
+            domain
+        ));
+        assert!(
+            repo.create_user(&max_username, &max_email, "hashed_password")
+                .await
+                .is_ok()
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_overlong_values_are_validation_errors() {
+        let repo = setup_sqlite_repo().await;
+        test_overlong_values_are_validation_errors_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_overlong_values_are_validation_errors() {
+        let repo = setup_postgres_repo().await;
+        test_overlong_values_are_validation_errors_impl(repo).await;
+    }
+
+    async fn test_username_is_unique_ignoring_case_impl(repo: Arc<dyn UserRepository>) {
+        let lower = generate_test_username();
+        let upper = lower.to_uppercase();
+        let email = Email::parse(format!("{}@example.com", lower)).unwrap();
+        let id = repo
+            .create_user(
+                &Username::parse(upper.as_str()).unwrap(),
+                &email,
+                "hashed_password",
+            )
+            .await
+            .unwrap();
+
+        let found = repo.get_user_by_username(&lower).await.unwrap();
+        assert_eq!(found.map(|u| u.id), Some(id));
+
+        let other_email = Email::parse(format!("other_{}@example.com", lower)).unwrap();
+        let result = repo
+            .create_user(
+                &Username::parse(lower.as_str()).unwrap(),
+                &other_email,
+                "hashed_password",
+            )
+            .await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_username_is_unique_ignoring_case() {
+        let repo = setup_sqlite_repo().await;
+        test_username_is_unique_ignoring_case_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_username_is_unique_ignoring_case() {
+        let repo = setup_postgres_repo().await;
+        test_username_is_unique_ignoring_case_impl(repo).await;
     }
 
+    async fn test_touch_last_login_impl(repo: Arc<dyn UserRepository>) {
+        let username = Username::parse(generate_test_username()).unwrap();
+        let email = Email::parse(format!("{}@example.com", username)).unwrap();
+        let id = repo
+            .create_user(&username, &email, "hashed_password")
+            .await
+            .expect("Failed to create user");
+
+        let before = repo
+            .get_user_by_username(username.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(before.last_login_at.is_none());
+
+        let at = chrono::NaiveDate::from_ymd_opt(2026, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5)
+            .unwrap();
+        repo.touch_last_login(&id, at)
+            .await
+            .expect("Failed to touch last login");
+
+        let after = repo
+            .get_user_by_username(username.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(after.last_login_at, Some(at));
+    }
+
+    async fn create_test_user(repo: &Arc<dyn UserRepository>) -> Uuid {
+        let username = Username::parse(generate_test_username()).unwrap();
+        let email = Email::parse(format!("{}@example.com", username)).unwrap();
+        repo.create_user(&username, &email, "hashed_password")
+            .await
+            .unwrap()
+    }
+
+    // Postgres tests share a database, so only look at the users this test created
+    async fn list_ids(
+        repo: &Arc<dyn UserRepository>,
+        filter: UserFilter,
+        ids: &[Uuid],
+    ) -> Vec<Uuid> {
+        repo.list_users(&filter, 1000, 0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.id)
+            .filter(|id| ids.contains(id))
+            .collect()
+    }
+
+    async fn test_list_users_filters_impl(repos: Repositories) {
+        let users = repos.users.clone();
+        let unverified = create_test_user(&users).await;
+        let verified = create_test_user(&users).await;
+        repos
+            .email_verification
+            .create_token(
+                verified,
+                &format!("hash-{}", verified),
+                chrono::Utc::now().naive_utc() + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        repos
+            .email_verification
+            .consume_token(
+                &format!("hash-{}", verified),
+                chrono::Utc::now().naive_utc(),
+            )
+            .await
+            .unwrap();
+        let ids = [unverified, verified];
+
+        // No filter
+        let mut all = list_ids(&users, UserFilter::default(), &ids).await;
+        all.sort();
+        let mut expected = ids.to_vec();
+        expected.sort();
+        assert_eq!(all, expected);
+
+        // Verified
+        let only_verified = UserFilter {
+            email_verified: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(list_ids(&users, only_verified, &ids).await, vec![verified]);
+        let only_unverified = UserFilter {
+            email_verified: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            list_ids(&users, only_unverified, &ids).await,
+            vec![unverified]
+        );
+
+        // Role
+        let admins = UserFilter {
+            role: Some(Role::Admin),
+            ..Default::default()
+        };
+        assert!(list_ids(&users, admins, &ids).await.is_empty());
+        let regular = UserFilter {
+            role: Some(Role::User),
+            ..Default::default()
+        };
+        assert_eq!(list_ids(&users, regular, &ids).await.len(), 2);
+
+        // Created after
+        let day_ago = chrono::Utc::now().naive_utc() - chrono::Duration::days(1);
+        let since_yesterday = UserFilter {
+            created_after: Some(day_ago),
+            ..Default::default()
+        };
+        assert_eq!(list_ids(&users, since_yesterday, &ids).await.len(), 2);
+        let tomorrow = chrono::Utc::now().naive_utc() + chrono::Duration::days(1);
+        let from_tomorrow = UserFilter {
+            created_after: Some(tomorrow),
+            ..Default::default()
+        };
+        assert!(list_ids(&users, from_tomorrow, &ids).await.is_empty());
+
+        // Combined
+        let combined = UserFilter {
+            username_prefix: None,
+            email_verified: Some(true),
+            role: Some(Role::User),
+            created_after: Some(day_ago),
+        };
+        assert_eq!(list_ids(&users, combined, &ids).await, vec![verified]);
+        let combined_none = UserFilter {
+            username_prefix: None,
+            email_verified: Some(true),
+            role: Some(Role::Admin),
+            created_after: Some(day_ago),
+        };
+        assert!(list_ids(&users, combined_none, &ids).await.is_empty());
+    }
+
+    #[test]
+    fn test_escape_like_escapes_wildcards() {
+        assert_eq!(escape_like("a_b%c\\d"), "a\\_b\\%c\\\\d");
+        assert_eq!(escape_like("plain"), "plain");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_list_users_filters() {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        test_list_users_filters_impl(Repositories::new(&DbPool::Sqlite(pool))).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_list_users_filters() {
+        let database_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL environment variable must be set for PostgreSQL tests");
+        let pool = sqlx::PgPool::connect(&database_url)
+            .await
+            .expect("Failed to connect to PostgreSQL database");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run PostgreSQL migrations");
+
+        test_list_users_filters_impl(Repositories::new(&DbPool::Postgres(pool))).await;
+    }
+
+    #[cfg(feature = "sqlite")]
     #[tokio::test]
     async fn test_sqlite_create_and_get_user() {
         let repo = setup_sqlite_repo().await;
         test_create_and_get_user_impl(repo).await;
     }
 
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_touch_last_login() {
+        let repo = setup_sqlite_repo().await;
+        test_touch_last_login_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_touch_last_login() {
+        let repo = setup_postgres_repo().await;
+        test_touch_last_login_impl(repo).await;
+    }
+
+    // Only this test makes admins, so the second one is the last admin in the database
+    async fn test_set_role_impl(repo: Arc<dyn UserRepository>) {
+        let first = create_test_user(&repo).await;
+        let second = create_test_user(&repo).await;
+
+        for id in [first, second] {
+            assert!(repo.set_role(&id, Role::Admin).await.unwrap());
+        }
+        let user = repo.get_user_by_id(&first).await.unwrap().unwrap();
+        assert_eq!(user.role, Role::Admin);
+
+        assert!(repo.set_role(&first, Role::User).await.unwrap());
+        assert!(
+            !repo.set_role(&second, Role::User).await.unwrap(),
+            "the last admin cannot be demoted"
+        );
+        assert!(
+            !repo
+                .soft_delete_user(&second, Utc::now().naive_utc())
+                .await
+                .unwrap(),
+            "the last admin cannot be deleted"
+        );
+        let user = repo.get_user_by_id(&second).await.unwrap().unwrap();
+        assert_eq!(user.role, Role::Admin);
+    }
+
+    // Only this test soft-deletes, and it purges everything it deleted before returning
+    async fn test_purge_deleted_users_impl(repo: Arc<dyn UserRepository>) {
+        let now = Utc::now().naive_utc();
+        let old = create_test_user(&repo).await;
+        let recent = create_test_user(&repo).await;
+        let live = create_test_user(&repo).await;
+
+        assert!(
+            repo.soft_delete_user(&old, now - chrono::Duration::days(40))
+                .await
+                .unwrap()
+        );
+        assert!(
+            repo.soft_delete_user(&recent, now - chrono::Duration::days(1))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !repo.soft_delete_user(&recent, now).await.unwrap(),
+            "an already deleted user cannot be deleted again"
+        );
+        assert!(repo.get_user_by_id(&old).await.unwrap().is_none());
+        assert!(repo.get_user_by_id(&recent).await.unwrap().is_none());
+
+        let purged = repo
+            .purge_deleted_users(Duration::days(30), now)
+            .await
+            .unwrap();
+        assert_eq!(purged, 1);
+
+        let purged = repo.purge_deleted_users(Duration::ZERO, now).await.unwrap();
+        assert_eq!(
+            purged, 1,
+            "the recently deleted user survived the first purge"
+        );
+
+        assert!(repo.get_user_by_id(&live).await.unwrap().is_some());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_purge_deleted_users() {
+        let repo = setup_sqlite_repo().await;
+        test_purge_deleted_users_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_purge_deleted_users() {
+        let repo = setup_postgres_repo().await;
+        test_purge_deleted_users_impl(repo).await;
+    }
+
+    async fn test_deleted_users_are_told_apart_from_missing_impl(repo: Arc<dyn UserRepository>) {
+        let username = Username::parse(generate_test_username()).unwrap();
+        let email = Email::parse(format!("{}@example.com", username)).unwrap();
+        let id = repo
+            .create_user(&username, &email, "hashed_password")
+            .await
+            .unwrap();
+        let deleted_at = chrono::Utc::now().naive_utc() - chrono::Duration::hours(1);
+        assert!(repo.soft_delete_user(&id, deleted_at).await.unwrap());
+
+        assert!(repo.get_user_by_id(&id).await.unwrap().is_none());
+        let user = repo
+            .get_user_by_id_including_deleted(&id)
+            .await
+            .unwrap()
+            .expect("a soft-deleted user is still found by the admin lookup");
+        let seen_deleted_at = user.deleted_at.expect("deleted_at is populated");
+        assert!((seen_deleted_at - deleted_at).num_seconds().abs() < 1);
+
+        let missing = Uuid::new_v4();
+        assert!(repo.get_user_by_id(&missing).await.unwrap().is_none());
+        assert!(
+            repo.get_user_by_id_including_deleted(&missing)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(!repo.restore_user(&missing).await.unwrap());
+
+        assert!(repo.restore_user(&id).await.unwrap());
+        assert!(
+            !repo.restore_user(&id).await.unwrap(),
+            "a live user cannot be restored"
+        );
+        let user = repo.get_user_by_id(&id).await.unwrap().unwrap();
+        assert_eq!(user.deleted_at, None);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_deleted_users_are_told_apart_from_missing() {
+        let repo = setup_sqlite_repo().await;
+        test_deleted_users_are_told_apart_from_missing_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_deleted_users_are_told_apart_from_missing() {
+        let repo = setup_postgres_repo().await;
+        test_deleted_users_are_told_apart_from_missing_impl(repo).await;
+    }
+
+    async fn test_get_users_by_ids_skips_missing_and_deleted_impl(repo: Arc<dyn UserRepository>) {
+        let first = create_test_user(&repo).await;
+        let second = create_test_user(&repo).await;
+        let deleted = create_test_user(&repo).await;
+        assert!(
+            repo.soft_delete_user(&deleted, chrono::Utc::now().naive_utc())
+                .await
+                .unwrap()
+        );
+
+        let mut found: Vec<Uuid> = repo
+            .get_users_by_ids(&[first, Uuid::new_v4(), second, deleted, first])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.id)
+            .collect();
+        found.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        assert!(repo.get_users_by_ids(&[]).await.unwrap().is_empty());
+        assert!(
+            repo.get_users_by_ids(&[Uuid::new_v4()])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    async fn test_get_user_by_identifier_matches_username_or_email_impl(
+        repo: Arc<dyn UserRepository>,
+    ) {
+        let username = generate_test_username();
+        let email = Email::parse(format!("{}@example.com", username)).unwrap();
+        let id = repo
+            .create_user(
+                &Username::parse(username.as_str()).unwrap(),
+                &email,
+                "hashed_password",
+            )
+            .await
+            .unwrap();
+
+        let by_username = repo
+            .get_user_by_identifier(&username.to_uppercase())
+            .await
+            .unwrap();
+        assert_eq!(by_username.map(|u| u.id), Some(id));
+
+        let by_email = repo
+            .get_user_by_identifier(&email.as_str().to_uppercase())
+            .await
+            .unwrap();
+        assert_eq!(by_email.map(|u| u.id), Some(id));
+
+        assert!(
+            repo.get_user_by_identifier("nobody@example.com")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        assert!(
+            repo.soft_delete_user(&id, chrono::Utc::now().naive_utc())
+                .await
+                .unwrap()
+        );
+        assert!(
+            repo.get_user_by_identifier(&username)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_get_user_by_identifier_matches_username_or_email() {
+        let repo = setup_sqlite_repo().await;
+        test_get_user_by_identifier_matches_username_or_email_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_get_user_by_identifier_matches_username_or_email() {
+        let repo = setup_postgres_repo().await;
+        test_get_user_by_identifier_matches_username_or_email_impl(repo).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_get_users_by_ids_skips_missing_and_deleted() {
+        let repo = setup_sqlite_repo().await;
+        test_get_users_by_ids_skips_missing_and_deleted_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_get_users_by_ids_skips_missing_and_deleted() {
+        let repo = setup_postgres_repo().await;
+        test_get_users_by_ids_skips_missing_and_deleted_impl(repo).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_set_role() {
+        let repo = setup_sqlite_repo().await;
+        test_set_role_impl(repo).await;
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_set_role() {
+        let repo = setup_postgres_repo().await;
+        test_set_role_impl(repo).await;
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_pool_exhaustion_is_service_unavailable() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_millis(100))
+            .connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+        let repo = SqliteUserRepository::new(pool.clone());
+
+        // Hold the only connection so the repository can't acquire one
+        let _held = pool.acquire().await.unwrap();
+        let result = repo.get_user_by_username("anyone").await;
+
+        assert!(matches!(result, Err(AppError::ServiceUnavailable(_))));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_write_waits_out_a_held_lock() {
+        use sqlx::{Connection, sqlite::SqliteConnectOptions};
+        use std::str::FromStr;
+
+        // Locks are per file, an in-memory database has no second connection to contend with
+        let path = std::env::temp_dir().join(format!("serverust-busy-{}.db", Uuid::new_v4()));
+        let options = SqliteConnectOptions::from_str(path.to_str().unwrap())
+            .unwrap()
+            .create_if_missing(true)
+            .busy_timeout(std::time::Duration::ZERO);
+        let pool = sqlx::SqlitePool::connect_with(options.clone())
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+        let repo = SqliteUserRepository::new(pool.clone());
+        let mut holder = sqlx::SqliteConnection::connect_with(&options)
+            .await
+            .unwrap();
+        let create = |name: &str| {
+            let username = Username::parse(name).unwrap();
+            let email = Email::parse(format!("{}@example.com", name)).unwrap();
+            (username, email)
+        };
+
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut holder)
+            .await
+            .unwrap();
+        let (username, email) = create("blocked");
+        let result = repo.create_user(&username, &email, "hash").await;
+        assert!(matches!(result, Err(AppError::ServiceUnavailable(_))));
+
+        let (username, email) = create("patient");
+        let (result, _) = tokio::join!(repo.create_user(&username, &email, "hash"), async {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            sqlx::query("COMMIT").execute(&mut holder).await.unwrap();
+        });
+        let id = result.expect("the write is retried once the lock is released");
+        assert!(repo.get_user_by_id(&id).await.unwrap().is_some());
+
+        holder.close().await.unwrap();
+        pool.close().await;
+        for suffix in ["", "-journal", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_backend_name() {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+
+        assert_eq!(DbPool::Sqlite(pool).backend(), "sqlite");
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_backend_name() {
+        // A lazy pool never connects, so this runs without a server
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+
+        assert_eq!(DbPool::Postgres(pool).backend(), "postgres");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_queries_fail_after_pool_close() {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        sqlx::migrate!("./migrations-sqlite")
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+        let repo = SqliteUserRepository::new(pool.clone());
+        let db_pool = DbPool::Sqlite(pool.clone());
+
+        db_pool.close().await;
+
+        assert!(pool.is_closed());
+        let result = repo.get_user_by_username("anyone").await;
+        assert!(matches!(result, Err(AppError::Database(_))));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_sqlite_ping_fails_once_pool_is_closed() {
+        let db_pool = DbPool::Sqlite(sqlx::SqlitePool::connect(":memory:").await.unwrap());
+        db_pool.ping().await.unwrap();
+
+        db_pool.close().await;
+
+        assert!(matches!(db_pool.ping().await, Err(AppError::Database(_))));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_ping_fails_once_pool_is_closed() {
+        let database_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL environment variable must be set for PostgreSQL tests");
+        let db_pool = DbPool::Postgres(sqlx::PgPool::connect(&database_url).await.unwrap());
+        db_pool.ping().await.unwrap();
+
+        db_pool.close().await;
+
+        assert!(matches!(db_pool.ping().await, Err(AppError::Database(_))));
+    }
+
+    #[cfg(feature = "postgres")]
     #[tokio::test]
     async fn test_postgres_create_and_get_user() {
         let repo = setup_postgres_repo().await;
@@ -1,17 +1,66 @@
-use axum::{Router, http};
-use http::header::{AUTHORIZATION, CONTENT_TYPE};
-use sqlx::{Sqlite, migrate::MigrateDatabase, postgres::PgPoolOptions, sqlite::SqlitePoolOptions};
-use std::{fs::File, sync::Arc};
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
-use uuid::Uuid;
+use axum::{
+    BoxError, Router, error_handling::HandleErrorLayer, extract::DefaultBodyLimit, http, middleware,
+};
+use http::header::{
+    AUTHORIZATION, CONTENT_TYPE, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use sqlx::ConnectOptions;
+#[cfg(feature = "postgres")]
+use sqlx::{Postgres, postgres::PgConnectOptions};
+#[cfg(feature = "sqlite")]
+use sqlx::{Sqlite, migrate::MigrateDatabase, sqlite::SqliteConnectOptions};
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+use std::str::FromStr;
+use std::{
+    fs::File,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tower::{ServiceBuilder, limit::GlobalConcurrencyLimitLayer};
+use tower_http::{
+    cors::CorsLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    set_header::SetResponseHeaderLayer,
+    trace::TraceLayer,
+};
+use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
 
+#[cfg(feature = "sqlite")]
+use crate::config::DEFAULT_SQLITE_URL;
+#[cfg(feature = "postgres")]
+use crate::config::{postgres_options_from_vars, secret_from_env};
 use crate::{
-    application::user_service::UserService,
-    config::{AppConfig, DatabaseType},
+    application::{
+        app_error::AppError,
+        audit_logger::AuditLogger,
+        clock::{Clock, SystemClock},
+        disposable_domains::DisposableDomains,
+        email_verification_service::EmailVerificationService,
+        failed_login_cache::FailedLoginCache,
+        hash_limiter::HashLimiter,
+        invite_service::InviteService,
+        login_lockout::LoginLockout,
+        metrics::Metrics,
+        password_reset_service::PasswordResetService,
+        registration_limiter::RegistrationLimiter,
+        session_service::SessionService,
+        token_service::TokenService,
+        user_service::UserService,
+    },
+    config::{AppConfig, DatabaseType, RateLimitBackend},
     crypto::Argon2PasswordHasher,
-    persistence::{DbPool, PostgresUserRepository, SqliteUserRepository, UserRepository},
-    web::{AppState, user_router},
+    persistence::{
+        DbPool, InMemoryRateLimitStore, RateLimitStore, Repositories, SlowDbEventLayer,
+        run_migrations, spawn_pool_sampler, spawn_token_cleanup,
+    },
+    web::{
+        AppState, InFlight, SampledMakeSpan, SampledOnResponse, admin_router, health_router,
+        method_not_allowed, metrics_router, negotiate_error_format, pagination::TOTAL_COUNT_HEADER,
+        record_request_line, require_current_admin, route_not_found, track_in_flight, user_router,
+    },
 };
 
 // ============================================================================
@@ -19,49 +68,136 @@ use crate::{
 // ============================================================================
 
 async fn init_db(config: &AppConfig) -> anyhow::Result<DbPool> {
-    let database_url = &config.database_url;
-
     match config.database_type {
-        DatabaseType::Postgres => {
-            tracing::info!("Connecting to PostgreSQL database");
-            let pool = PgPoolOptions::new()
-                .max_connections(5)
-                .connect(database_url)
-                .await?;
-
-            tracing::info!("Running PostgreSQL migrations");
-            sqlx::migrate!("./migrations").run(&pool).await?;
-
-            tracing::info!("Connected to PostgreSQL database");
-            Ok(DbPool::Postgres(pool))
-        }
-        DatabaseType::Sqlite => {
-            // Create database if it doesn't exist
-            if !Sqlite::database_exists(database_url).await? {
-                tracing::info!("Creating SQLite database at: {}", database_url);
-                Sqlite::create_database(database_url).await?;
-            }
+        #[cfg(feature = "postgres")]
+        DatabaseType::Postgres => init_postgres(config).await,
+        #[cfg(feature = "sqlite")]
+        DatabaseType::Sqlite => init_sqlite(config).await,
+        #[cfg(not(all(feature = "postgres", feature = "sqlite")))]
+        ref database_type => anyhow::bail!(
+            "DATABASE_TYPE {:?} is not available, this build only supports {:?}",
+            database_type,
+            DbPool::COMPILED_BACKENDS
+        ),
+    }
+}
 
-            tracing::info!("Connecting to SQLite database");
-            let pool = SqlitePoolOptions::new()
-                .max_connections(5)
-                .connect(database_url)
-                .await?;
+/// Connection options from `DATABASE_URL`, or the `PG*` variables when it's unset
+/// Connections are tagged with the configured `application_name` for `pg_stat_activity`
+#[cfg(feature = "postgres")]
+fn postgres_connect_options(config: &AppConfig) -> anyhow::Result<PgConnectOptions> {
+    let options = match &config.database_url {
+        Some(database_url) => PgConnectOptions::from_str(database_url)?,
+        None => {
+            tracing::info!("DATABASE_URL not set, using PG* environment variables");
+            postgres_options_from_vars(secret_from_env)?
+        }
+    };
 
-            tracing::info!("Running SQLite migrations");
-            sqlx::migrate!("./migrations-sqlite").run(&pool).await?;
+    Ok(options
+        .application_name(&config.database_application_name)
+        .log_statements(statement_log_level(config))
+        .log_slow_statements(
+            log::LevelFilter::Warn,
+            config.db_slow_query_threshold.unsigned_abs(),
+        ))
+}
 
-            tracing::info!("Connected to SQLite database");
-            Ok(DbPool::Sqlite(pool))
-        }
+/// sqlx logs every statement with its bound parameters, so that only happens when asked for
+fn statement_log_level(config: &AppConfig) -> log::LevelFilter {
+    if config.db_log_statements {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Off
+    }
+}
+
+/// Connections are recycled after `db_idle_timeout` unused or `db_max_lifetime` in total,
+/// before a proxy or the server can drop them underneath a query
+/// Waits past `db_slow_acquire_threshold` are warned about, a sign the pool is saturated
+fn pool_options<DB: sqlx::Database>(config: &AppConfig) -> sqlx::pool::PoolOptions<DB> {
+    sqlx::pool::PoolOptions::new()
+        .max_connections(5)
+        .idle_timeout(config.db_idle_timeout.unsigned_abs())
+        .max_lifetime(config.db_max_lifetime.unsigned_abs())
+        .acquire_slow_level(log::LevelFilter::Warn)
+        .acquire_slow_threshold(config.db_slow_acquire_threshold.unsigned_abs())
+}
+
+#[cfg(feature = "postgres")]
+async fn init_postgres(config: &AppConfig) -> anyhow::Result<DbPool> {
+    let options = postgres_connect_options(config)?;
+
+    tracing::info!("Connecting to PostgreSQL database");
+    let pool = pool_options::<Postgres>(config)
+        .connect_with(options)
+        .await?;
+
+    tracing::info!("Connected to PostgreSQL database");
+    Ok(DbPool::Postgres(pool))
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_connect_options(config: &AppConfig) -> anyhow::Result<SqliteConnectOptions> {
+    let database_url = config.database_url.as_deref().unwrap_or(DEFAULT_SQLITE_URL);
+
+    Ok(SqliteConnectOptions::from_str(database_url)?
+        .log_statements(statement_log_level(config))
+        .log_slow_statements(
+            log::LevelFilter::Warn,
+            config.db_slow_query_threshold.unsigned_abs(),
+        ))
+}
+
+#[cfg(feature = "sqlite")]
+async fn init_sqlite(config: &AppConfig) -> anyhow::Result<DbPool> {
+    let database_url = config.database_url.as_deref().unwrap_or(DEFAULT_SQLITE_URL);
+    let options = sqlite_connect_options(config)?;
+
+    // SQLite creates the file but not the directories leading to it
+    let filename = options.get_filename().to_path_buf();
+    if let Some(parent) = filename.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // Create database if it doesn't exist
+    if !Sqlite::database_exists(database_url).await? {
+        tracing::info!("Creating SQLite database at: {}", database_url);
+        Sqlite::create_database(database_url).await?;
+    }
+
+    tracing::info!("Connecting to SQLite database");
+    let pool = pool_options::<Sqlite>(config).connect_with(options).await?;
+
+    tracing::info!("Connected to SQLite database");
+    Ok(DbPool::Sqlite(pool))
+}
+
+/// Bring the schema up to date, unless `RUN_MIGRATIONS` leaves that to someone else
+async fn migrate_if_enabled(config: &AppConfig, pool: &DbPool) -> anyhow::Result<()> {
+    if !config.run_migrations {
+        tracing::info!("RUN_MIGRATIONS is off, skipping migrations");
+        return Ok(());
     }
+
+    run_migrations(pool).await
 }
 
 // ============================================================================
 // Logging Setup
 // ============================================================================
 
+/// Where JSON logs are written unless `LOG_FILE` says otherwise
+const DEFAULT_LOG_FILE: &str = "app.log";
+
 fn init_tracing() {
+    let log_file = std::env::var("LOG_FILE").unwrap_or_else(|_| DEFAULT_LOG_FILE.to_string());
+    init_tracing_with_log_file(&log_file);
+}
+
+fn init_tracing_with_log_file(log_file: &str) {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| "clean_architecture=debug,tower_http=debug".into());
 
@@ -72,19 +208,33 @@ fn init_tracing() {
         .pretty();
 
     // File (structured JSON logs)
-    let file = File::create("app.log").expect("Cannot create log file");
-    let json_layer = tracing_subscriber::fmt::layer()
-        .json()
-        .with_writer(file)
-        .with_current_span(true)
-        .with_span_list(true);
+    // A full disk or unwritable path isn't worth taking the server down, so carry on without it
+    let (json_layer, file_error) = match File::create(log_file) {
+        Ok(file) => {
+            let layer = tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(file)
+                .with_current_span(true)
+                .with_span_list(true);
+            (Some(layer), None)
+        }
+        Err(error) => (None, Some(error)),
+    };
 
+    // The filter applies to the log output only, so slow query warnings are counted either way
     tracing_subscriber::registry()
-        .with(filter)
-        .with(console_layer)
-        .with(json_layer)
+        .with(console_layer.and_then(json_layer).with_filter(filter))
+        .with(SlowDbEventLayer.with_filter(SlowDbEventLayer::filter()))
         .try_init()
         .ok();
+
+    if let Some(error) = file_error {
+        tracing::warn!(
+            %error,
+            path = log_file,
+            "Cannot create log file, continuing without JSON file logs"
+        );
+    }
 }
 
 // ============================================================================
@@ -93,22 +243,191 @@ fn init_tracing() {
 
 async fn init_app_state() -> anyhow::Result<AppState> {
     let config = AppConfig::from_env();
+    config.log_summary();
 
     // Initialize database
     let pool = init_db(&config).await?;
 
-    // Create repository based on database type
-    let user_repository: Arc<dyn UserRepository> = match pool {
-        DbPool::Postgres(pg_pool) => Arc::new(PostgresUserRepository::new(pg_pool)),
-        DbPool::Sqlite(sqlite_pool) => Arc::new(SqliteUserRepository::new(sqlite_pool)),
+    app_state_with_pool(config, pool).await
+}
+
+/// Migrate `pool`, then build the state around it and seed the admin
+async fn app_state_with_pool(config: AppConfig, pool: DbPool) -> anyhow::Result<AppState> {
+    migrate_if_enabled(&config, &pool).await?;
+    tracing::info!(backend = pool.backend(), "Database ready");
+
+    let app_state = build_app_state(config, pool)?;
+    warm_up_password_hasher(
+        &app_state.user_service,
+        app_state.config.warm_up_password_hasher,
+    )
+    .await;
+    seed_admin(&app_state).await?;
+
+    Ok(app_state)
+}
+
+/// Create the configured admin account on first run
+async fn seed_admin(app_state: &AppState) -> anyhow::Result<()> {
+    let Some(seed) = &app_state.config.seed_admin else {
+        return Ok(());
+    };
+
+    let created = app_state
+        .user_service
+        .seed_admin(&seed.username, &seed.email, &seed.password)
+        .await?;
+    if !created {
+        tracing::info!("Admin already exists, skipping seed admin");
+    }
+
+    Ok(())
+}
+
+/// Hash a throwaway password so the first real registration or login isn't the slow one
+/// Returns how long the warmup took, or `None` when disabled
+async fn warm_up_password_hasher(user_service: &UserService, enabled: bool) -> Option<Duration> {
+    if !enabled {
+        return None;
+    }
+
+    let started = Instant::now();
+    if let Err(e) = user_service.warm_up_hasher().await {
+        tracing::warn!(error = ?e, "Password hasher warmup failed");
+        return None;
+    }
+    let elapsed = started.elapsed();
+    tracing::info!(
+        warmup_ms = elapsed.as_millis() as u64,
+        "Password hasher warmed up"
+    );
+
+    Some(elapsed)
+}
+
+/// The disposable email domains to refuse, from `DISPOSABLE_EMAIL_DOMAINS_FILE` or the bundled list
+fn load_disposable_domains(config: &AppConfig) -> anyhow::Result<Option<DisposableDomains>> {
+    if !config.block_disposable_emails {
+        return Ok(None);
+    }
+
+    let domains = match &config.disposable_email_domains_file {
+        Some(path) => DisposableDomains::from_file(path).map_err(|e| {
+            anyhow::anyhow!(
+                "DISPOSABLE_EMAIL_DOMAINS_FILE '{}' could not be read: {}",
+                path,
+                e
+            )
+        })?,
+        None => DisposableDomains::bundled(),
+    };
+    tracing::info!(
+        domains = domains.len(),
+        "Refusing registrations from disposable email domains"
+    );
+
+    Ok(Some(domains))
+}
+
+fn build_app_state(config: AppConfig, pool: DbPool) -> anyhow::Result<AppState> {
+    let repositories = Repositories::new(&pool);
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    // One store for every limiter, its windows are pruned on each hit
+    let rate_limits: Arc<dyn RateLimitStore> = match config.rate_limit_backend {
+        RateLimitBackend::Memory => Arc::new(InMemoryRateLimitStore::new()),
+        RateLimitBackend::Database => repositories.rate_limits.clone(),
     };
 
-    let password_hasher = Arc::new(Argon2PasswordHasher::default());
-    let user_service = UserService::new(password_hasher, user_repository);
+    let email_verification_service = Arc::new(EmailVerificationService::new(
+        repositories.email_verification.clone(),
+        config.email_verification_ttl,
+    ));
+
+    let mut password_hasher = Argon2PasswordHasher::new(config.argon2_algorithm)
+        .with_salt_length(config.argon2_salt_length)
+        .expect("ARGON2_SALT_LENGTH is range checked when the config is loaded");
+    if let Some(pepper) = &config.password_pepper {
+        password_hasher = password_hasher.with_pepper(pepper);
+    }
+    let password_hasher = Arc::new(password_hasher);
+    let hash_limiter = Arc::new(HashLimiter::new(config.max_concurrent_hashes));
+    let password_reset_service = PasswordResetService::new(
+        password_hasher.clone(),
+        repositories.users.clone(),
+        repositories.password_reset.clone(),
+        config.password_reset_ttl,
+    )
+    .with_hash_limiter(hash_limiter.clone())
+    .with_max_password_bytes(config.max_password_bytes);
+    let mut user_service = UserService::new(password_hasher, repositories.users.clone())
+        .with_hash_limiter(hash_limiter)
+        .with_max_password_bytes(config.max_password_bytes)
+        .with_registration_enabled(config.registration_enabled)
+        .with_allowed_email_domains(config.allowed_email_domains.clone())
+        .with_login_identifier(config.login_identifier)
+        .with_default_role(config.default_role)
+        .with_failed_login_delay(config.failed_login_delay)
+        .with_clock(clock.clone());
+    if config.email_verification_required {
+        user_service = user_service.with_email_verification(email_verification_service.clone());
+    }
+    if let Some(domains) = load_disposable_domains(&config)? {
+        user_service = user_service.with_disposable_domains(Arc::new(domains));
+    }
+    if config.registration_invite_only {
+        user_service = user_service.with_invite_codes(repositories.invite_codes.clone());
+    }
+    if let Some(limit) = config.registration_limit_per_domain {
+        let limiter = RegistrationLimiter::new(limit)
+            .with_store(rate_limits.clone())
+            .with_clock(clock.clone());
+        user_service = user_service.with_registration_limiter(Arc::new(limiter));
+    }
+    if let Some(threshold) = config.login_lockout_threshold {
+        let lockout = LoginLockout::new(threshold)
+            .with_store(rate_limits)
+            .with_clock(clock);
+        user_service = user_service.with_login_lockout(Arc::new(lockout));
+    }
+    if let Some(ttl) = config.failed_login_cache_ttl {
+        user_service = user_service
+            .with_failed_login_cache(Arc::new(FailedLoginCache::new(ttl.unsigned_abs())));
+    }
+    let mut token_service = TokenService::new(
+        &config.jwt_secret,
+        &config.jwt_issuer,
+        &config.jwt_audience,
+        config.access_token_ttl,
+    )
+    .with_verification_keys(&config.jwt_keys)
+    .with_legacy_key_id(config.jwt_legacy_key_id.as_deref());
+    if let Some(key) = config
+        .jwt_keys
+        .iter()
+        .find(|key| config.jwt_active_key_id.as_ref() == Some(&key.id))
+    {
+        token_service = token_service.with_signing_key(key);
+    }
+    let token_service = Arc::new(token_service);
+    let session_service = SessionService::new(
+        token_service.clone(),
+        repositories.users.clone(),
+        repositories.refresh_tokens.clone(),
+        config.refresh_token_ttl,
+        config.refresh_token_reuse_policy,
+    );
 
     Ok(AppState {
         config: Arc::new(config),
+        db_pool: pool,
         user_service: Arc::new(user_service),
+        token_service,
+        session_service: Arc::new(session_service),
+        email_verification_service,
+        password_reset_service: Arc::new(password_reset_service),
+        invite_service: Arc::new(InviteService::new(repositories.invite_codes.clone())),
+        audit_logger: Arc::new(AuditLogger::new(repositories.audit_log.clone())),
+        metrics: Arc::new(Metrics::new()),
     })
 }
 
@@ -116,10 +435,181 @@ async fn init_app_state() -> anyhow::Result<AppState> {
 // Server Creation
 // ============================================================================
 
-pub async fn create_app() -> anyhow::Result<Router> {
+/// Build the app from the environment
+/// The token cleanup and pool sampler tasks run until `shutdown` is cancelled
+pub async fn create_app(shutdown: CancellationToken) -> anyhow::Result<Router> {
+    init_tracing();
+
+    let app_state = init_app_state().await?;
+    start_pool_sampler(&app_state, shutdown.clone());
+    start_token_cleanup(&app_state, shutdown);
+
+    Ok(build_router(app_state))
+}
+
+/// Build the app around a pool the caller already owns, for embedding in a larger binary
+/// Tracing is left to the caller, migrations still run unless `config.run_migrations` is off
+/// The token cleanup and pool sampler tasks run until `shutdown` is cancelled
+pub async fn create_app_with_pool(
+    config: AppConfig,
+    pool: DbPool,
+    shutdown: CancellationToken,
+) -> anyhow::Result<Router> {
+    let app_state = app_state_with_pool(config, pool).await?;
+    start_pool_sampler(&app_state, shutdown.clone());
+    start_token_cleanup(&app_state, shutdown);
+
+    Ok(build_router(app_state))
+}
+
+/// Keep the pool gauges served at `/metrics` current until `shutdown` is cancelled
+fn start_pool_sampler(app_state: &AppState, shutdown: CancellationToken) -> JoinHandle<()> {
+    spawn_pool_sampler(
+        app_state.db_pool.clone(),
+        app_state.metrics.clone(),
+        app_state.config.pool_metrics_interval.unsigned_abs(),
+        shutdown,
+    )
+}
+
+/// Delete expired tokens every `token_cleanup_interval` until `shutdown` is cancelled
+fn start_token_cleanup(app_state: &AppState, shutdown: CancellationToken) -> JoinHandle<()> {
+    spawn_token_cleanup(
+        app_state.db_pool.clone(),
+        app_state.config.token_cleanup_interval.unsigned_abs(),
+        shutdown,
+    )
+}
+
+/// Apply pending migrations to the configured database and return, for the `migrate` command
+/// Runs regardless of `RUN_MIGRATIONS`, which only governs startup
+pub async fn migrate() -> anyhow::Result<()> {
+    init_tracing();
+
+    let config = AppConfig::from_env();
+    let pool = init_db(&config).await?;
+    run_migrations(&pool).await?;
+    tracing::info!(backend = pool.backend(), "Migrations applied");
+    pool.close().await;
+
+    Ok(())
+}
+
+/// Serve the app on `listener` until Ctrl+C or SIGTERM, then drain the database pool
+/// In-flight requests get `shutdown_grace` to finish before they are dropped
+pub async fn run(listener: TcpListener) -> anyhow::Result<()> {
     init_tracing();
 
     let app_state = init_app_state().await?;
+    let stop_tasks = CancellationToken::new();
+    let pool_sampler = start_pool_sampler(&app_state, stop_tasks.clone());
+    let token_cleanup = start_token_cleanup(&app_state, stop_tasks.clone());
+    let db_pool = app_state.db_pool.clone();
+    let shutdown_grace = app_state.config.shutdown_grace.unsigned_abs();
+    let in_flight = InFlight::default();
+    let router = build_router(app_state).layer(middleware::from_fn_with_state(
+        in_flight.clone(),
+        track_in_flight,
+    ));
+
+    tracing::info!("Server listening on {}", listener.local_addr()?);
+
+    let (stop_accepting, stopped) = tokio::sync::oneshot::channel::<()>();
+    let mut server = tokio::spawn(
+        // Connect info lets handlers see the peer address, for the audit log
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async {
+            let _ = stopped.await;
+        })
+        .into_future(),
+    );
+
+    tokio::select! {
+        result = &mut server => return Ok(result??),
+        _ = shutdown_signal() => {}
+    }
+    let _ = stop_accepting.send(());
+
+    let remaining = in_flight.drain(shutdown_grace).await;
+    if remaining > 0 {
+        tracing::warn!(
+            in_flight = remaining,
+            "Shutdown grace period elapsed, dropping requests still in flight"
+        );
+        server.abort();
+    } else {
+        server.await??;
+    }
+
+    // Let a cleanup pass in progress finish before its connection goes away
+    stop_tasks.cancel();
+    let _ = token_cleanup.await;
+    let _ = pool_sampler.await;
+
+    tracing::info!("Closing database pool");
+    db_pool.close().await;
+
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received");
+}
+
+/// One year, covering subdomains
+const HSTS_HEADER_VALUE: &str = "max-age=31536000; includeSubDomains";
+
+/// Answer requests beyond `max_concurrent_requests` in flight with a 503 instead of queueing them
+/// `Router::layer` wraps every route separately, so the limit has to share one semaphore
+fn shed_load(router: Router, max_concurrent_requests: Option<usize>) -> Router {
+    let Some(max) = max_concurrent_requests else {
+        return router;
+    };
+
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(|_: BoxError| async {
+                AppError::ServiceUnavailable("Too many concurrent requests".into())
+            }))
+            .load_shed()
+            .layer(GlobalConcurrencyLimitLayer::new(max)),
+    )
+}
+
+fn build_router(app_state: AppState) -> Router {
+    let make_span = SampledMakeSpan::new(app_state.config.trace_sample_rate)
+        .with_quiet_paths(app_state.config.trace_quiet_paths.clone());
+    let max_request_body_bytes = app_state.config.max_request_body_bytes;
+    let hsts_enabled = app_state.config.hsts_enabled;
+    let max_concurrent_requests = app_state.config.max_concurrent_requests;
+    let cors_enabled = app_state.config.cors_enabled;
+    let cors_max_age = app_state.config.cors_max_age.unsigned_abs();
+    let request_id_header = app_state.config.request_id_header.clone();
 
     let cors = CorsLayer::new()
         .allow_origin(
@@ -127,26 +617,2335 @@ pub async fn create_app() -> anyhow::Result<Router> {
                 .parse::<http::HeaderValue>()
                 .unwrap(),
         )
-        .allow_methods([http::Method::POST, http::Method::GET])
+        .allow_methods([
+            http::Method::POST,
+            http::Method::GET,
+            http::Method::PUT,
+            http::Method::DELETE,
+        ])
         .allow_headers([CONTENT_TYPE, AUTHORIZATION])
+        // Readable by browser clients, so the request id can go into bug reports
+        .expose_headers([
+            request_id_header.clone(),
+            http::HeaderName::from_static(TOTAL_COUNT_HEADER),
+        ])
+        .max_age(cors_max_age)
         .allow_credentials(true);
 
     let router = Router::new()
         .nest("/api/user", user_router())
+        .nest(
+            "/api/admin",
+            admin_router().route_layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                require_current_admin,
+            )),
+        )
+        .nest("/health", health_router())
+        .merge(metrics_router())
+        .fallback(route_not_found)
+        .method_not_allowed_fallback(method_not_allowed)
         .with_state(app_state)
-        .layer(cors)
+        .layer(DefaultBodyLimit::max(max_request_body_bytes))
+        .layer(middleware::from_fn(negotiate_error_format));
+
+    // API-only deployments have no browser clients, so no CORS headers at all
+    let router = if cors_enabled {
+        router.layer(cors)
+    } else {
+        router
+    };
+
+    let router = router
+        .layer(SetResponseHeaderLayer::if_not_present(
+            X_CONTENT_TYPE_OPTIONS,
+            http::HeaderValue::from_static("nosniff"),
+        ))
+        .layer(SetResponseHeaderLayer::if_not_present(
+            X_FRAME_OPTIONS,
+            http::HeaderValue::from_static("DENY"),
+        ));
+
+    // Browsers only honour HSTS over HTTPS, so it stays off until the deployment terminates TLS
+    let router = if hsts_enabled {
+        router.layer(SetResponseHeaderLayer::if_not_present(
+            STRICT_TRANSPORT_SECURITY,
+            http::HeaderValue::from_static(HSTS_HEADER_VALUE),
+        ))
+    } else {
+        router
+    };
+
+    shed_load(router, max_concurrent_requests)
+        .layer(middleware::from_fn(record_request_line))
         .layer(
-            TraceLayer::new_for_http().make_span_with(|request: &http::Request<_>| {
-                let request_id = Uuid::new_v4();
-                tracing::info_span!(
-                    "http-request",
-                    method = %request.method(),
-                    uri = %request.uri(),
-                    version = ?request.version(),
-                    request_id = %request_id
-                )
-            }),
+            TraceLayer::new_for_http()
+                .make_span_with(make_span)
+                .on_response(SampledOnResponse),
+        )
+        // Outermost, so the span and every response carry the id the proxy sent or a new one
+        .layer(PropagateRequestIdLayer::new(request_id_header.clone()))
+        .layer(SetRequestIdLayer::new(request_id_header, MakeRequestUuid))
+}
+
+// The fixtures run against in-memory SQLite
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{app_error::AppResult, user_service::PasswordHasher},
+        config::{LoginIdentifier, RateLimitBackend, RefreshTokenReusePolicy, SeedAdmin},
+        crypto::token::TOKEN_LENGTH,
+        domain::{
+            Email, Username,
+            user::Role,
+            username::{LEGACY_USERNAME_MAX_LENGTH, USERNAME_MAX_LENGTH},
+        },
+        persistence::{SQLITE_MIGRATOR, SqliteUserRepository, UserRepository},
+    };
+    use axum::body::{Body, to_bytes};
+    use time::Duration;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn test_config() -> AppConfig {
+        AppConfig {
+            jwt_secret: "secret".into(),
+            jwt_keys: vec![],
+            jwt_active_key_id: None,
+            jwt_legacy_key_id: None,
+            jwt_issuer: "sultan".into(),
+            jwt_audience: "sultan".into(),
+            access_token_ttl: Duration::minutes(15),
+            refresh_token_ttl: Duration::days(30),
+            refresh_token_reuse_policy: RefreshTokenReusePolicy::RevokeFamily,
+            database_type: DatabaseType::Sqlite,
+            database_url: Some("sqlite::memory:".into()),
+            database_application_name: "sultan".into(),
+            argon2_algorithm: argon2::Algorithm::Argon2id,
+            argon2_salt_length: crate::crypto::password::DEFAULT_SALT_LENGTH,
+            password_pepper: None,
+            warm_up_password_hasher: false,
+            email_verification_required: false,
+            email_verification_ttl: Duration::hours(24),
+            password_reset_ttl: Duration::minutes(30),
+            trace_sample_rate: 1.0,
+            trace_quiet_paths: vec!["/health".into()],
+            trusted_proxies: Vec::new(),
+            registration_enabled: true,
+            registration_invite_only: false,
+            hide_registration_conflicts: false,
+            default_role: Role::User,
+            allowed_email_domains: Vec::new(),
+            block_disposable_emails: false,
+            disposable_email_domains_file: None,
+            registration_limit_per_domain: None,
+            rate_limit_backend: RateLimitBackend::Memory,
+            login_identifier: LoginIdentifier::Username,
+            login_lockout_threshold: None,
+            failed_login_cache_ttl: None,
+            failed_login_delay: Duration::ZERO,
+            max_request_body_bytes: 16384,
+            max_password_bytes: 1024,
+            max_concurrent_requests: None,
+            hsts_enabled: false,
+            require_https: false,
+            health_check_token: None,
+            cors_enabled: true,
+            cors_max_age: Duration::minutes(10),
+            request_id_header: http::HeaderName::from_static("x-request-id"),
+            response_envelope: false,
+            shutdown_grace: Duration::seconds(30),
+            pool_metrics_interval: Duration::seconds(15),
+            token_cleanup_interval: Duration::hours(1),
+            max_concurrent_hashes: 4,
+            db_log_statements: false,
+            db_idle_timeout: Duration::minutes(10),
+            run_migrations: true,
+            db_max_lifetime: Duration::minutes(30),
+            db_slow_acquire_threshold: Duration::seconds(1),
+            db_slow_query_threshold: Duration::milliseconds(500),
+            seed_admin: None,
+        }
+    }
+
+    async fn setup_router() -> Router {
+        setup_router_with_pool().await.0
+    }
+
+    async fn setup_router_with_pool() -> (Router, sqlx::SqlitePool) {
+        setup_router_with_config(test_config()).await
+    }
+
+    async fn setup_router_with_config(config: AppConfig) -> (Router, sqlx::SqlitePool) {
+        let pool = sqlx::SqlitePool::connect(":memory:")
+            .await
+            .expect("Failed to create in-memory SQLite database");
+        SQLITE_MIGRATOR
+            .run(&pool)
+            .await
+            .expect("Failed to run SQLite migrations");
+
+        let router = build_router(build_app_state(config, DbPool::Sqlite(pool.clone())).unwrap());
+        (router, pool)
+    }
+
+    async fn send_json(
+        router: &Router,
+        request: http::Request<Body>,
+    ) -> (http::StatusCode, serde_json::Value) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn post_json(uri: &str, body: serde_json::Value) -> http::Request<Body> {
+        http::Request::builder()
+            .method(http::Method::POST)
+            .uri(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn get_with_token(uri: &str, access_token: &str) -> http::Request<Body> {
+        http::Request::builder()
+            .uri(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", access_token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn delete_with_token(uri: &str, access_token: &str) -> http::Request<Body> {
+        http::Request::builder()
+            .method(http::Method::DELETE)
+            .uri(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", access_token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    /// Log in again as an already registered user from another device
+    async fn login_with_user_agent(
+        router: &Router,
+        username: &str,
+        user_agent: &str,
+    ) -> serde_json::Value {
+        let mut request = post_json(
+            "/api/user/login",
+            serde_json::json!({ "username": username, "password": "password123" }),
+        );
+        request
+            .headers_mut()
+            .insert(http::header::USER_AGENT, user_agent.parse().unwrap());
+
+        let (status, body) = send_json(router, request).await;
+        assert_eq!(status, http::StatusCode::OK);
+        body
+    }
+
+    /// Register `username` and log in, returning the login response body
+    async fn register_and_login(router: &Router, username: &str) -> serde_json::Value {
+        let (status, _) = send_json(
+            router,
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": username,
+                    "email": format!("{}@example.com", username),
+                    "password": "password123",
+                }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::CREATED);
+
+        let (status, body) = send_json(
+            router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": username, "password": "password123" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+        body
+    }
+
+    #[derive(Default)]
+    struct CountingPasswordHasher(std::sync::atomic::AtomicUsize);
+
+    impl PasswordHasher for CountingPasswordHasher {
+        fn hash_password(&self, password: &str) -> AppResult<String> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(format!("{}_hashed", password))
+        }
+        fn verify_password(&self, password: &str, hash: &str) -> AppResult<bool> {
+            Ok(format!("{}_hashed", password) == hash)
+        }
+    }
+
+    async fn counting_user_service() -> (UserService, Arc<CountingPasswordHasher>) {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        SQLITE_MIGRATOR.run(&pool).await.unwrap();
+        let hasher = Arc::new(CountingPasswordHasher::default());
+        let service = UserService::new(hasher.clone(), Arc::new(SqliteUserRepository::new(pool)));
+        (service, hasher)
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_hashes_once_when_enabled() {
+        let (service, hasher) = counting_user_service().await;
+
+        let elapsed = warm_up_password_hasher(&service, true).await;
+
+        assert!(elapsed.is_some());
+        assert_eq!(hasher.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Unknown logins reuse the warmed up hash instead of computing their own
+        let result = service.login("nobody", &"password123".into()).await;
+        assert!(matches!(result, Err(AppError::InvalidCredentials)));
+        assert_eq!(hasher.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_skipped_when_disabled() {
+        let (service, hasher) = counting_user_service().await;
+
+        let elapsed = warm_up_password_hasher(&service, false).await;
+
+        assert!(elapsed.is_none());
+        assert_eq!(hasher.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_init_db_creates_sqlite_parent_directories() {
+        let root = std::env::temp_dir().join(format!("serverust-{}", Uuid::new_v4()));
+        let db_path = root.join("nested/data/app.db");
+        let config = AppConfig {
+            database_url: Some(format!("sqlite://{}", db_path.display())),
+            ..test_config()
+        };
+
+        let pool = init_db(&config).await.expect("init_db should succeed");
+
+        assert!(db_path.exists());
+        drop(pool);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    #[tokio::test]
+    async fn test_init_db_rejects_backend_not_compiled_in() {
+        let config = AppConfig {
+            database_type: DatabaseType::Postgres,
+            ..test_config()
+        };
+
+        let error = init_db(&config).await.err().unwrap();
+
+        assert!(error.to_string().contains("only supports [\"sqlite\"]"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_route_returns_json_404() {
+        let router = setup_router().await;
+
+        let response = router
+            .oneshot(
+                http::Request::builder()
+                    .uri("/does/not/exist")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            &body[..],
+            br#"{"code":"not_found","error":"route not found"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wrong_method_returns_json_405_with_allow_header() {
+        let router = setup_router().await;
+
+        let response = router
+            .oneshot(
+                http::Request::builder()
+                    .method(http::Method::GET)
+                    .uri("/api/user/register")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), http::StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[http::header::ALLOW], "POST");
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            &body[..],
+            br#"{"code":"method_not_allowed","error":"method not allowed"}"#
         );
+    }
+
+    #[tokio::test]
+    async fn test_me_returns_authenticated_user() {
+        let router = setup_router().await;
+        let login = register_and_login(&router, "alice").await;
+        let access_token = login["access_token"].as_str().unwrap();
+
+        let (status, body) = send_json(&router, get_with_token("/api/user/me", access_token)).await;
+
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["id"], login["user"]["id"]);
+        assert_eq!(body["username"], "alice");
+        assert_eq!(body["email"], "alice@example.com");
+        let created_at = body["created_at"].as_str().unwrap();
+        assert!(created_at.ends_with('Z'), "{}", created_at);
+        assert!(chrono::DateTime::parse_from_rfc3339(created_at).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_me_returns_404_for_deleted_user() {
+        let (router, pool) = setup_router_with_pool().await;
+        let login = register_and_login(&router, "alice").await;
+        let access_token = login["access_token"].as_str().unwrap();
+
+        sqlx::query("DELETE FROM refresh_tokens")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM users")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let (status, body) = send_json(&router, get_with_token("/api/user/me", access_token)).await;
+
+        assert_eq!(status, http::StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "User not found");
+    }
+
+    #[tokio::test]
+    async fn test_me_is_revalidated_with_etag() {
+        let router = setup_router().await;
+        let login = register_and_login(&router, "alice").await;
+        let access_token = login["access_token"].as_str().unwrap();
+        let get_me = |if_none_match: Option<&str>| {
+            let mut request = get_with_token("/api/user/me", access_token);
+            if let Some(etag) = if_none_match {
+                request
+                    .headers_mut()
+                    .insert(http::header::IF_NONE_MATCH, etag.parse().unwrap());
+            }
+            router.clone().oneshot(request)
+        };
+
+        let first = get_me(None).await.unwrap();
+        let second = get_me(None).await.unwrap();
+        assert_eq!(first.status(), http::StatusCode::OK);
+        assert_eq!(
+            first.headers()[http::header::CACHE_CONTROL],
+            "private, no-cache"
+        );
+        let etag = first.headers()[http::header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(second.headers()[http::header::ETAG], etag.as_str());
+
+        let not_modified = get_me(Some(&etag)).await.unwrap();
+        assert_eq!(not_modified.status(), http::StatusCode::NOT_MODIFIED);
+        assert_eq!(not_modified.headers()[http::header::ETAG], etag.as_str());
+        let body = to_bytes(not_modified.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        // Logging in again records last_login_at, which is part of the profile
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        login_with_user_agent(&router, "alice", "test").await;
+        let changed = get_me(Some(&etag)).await.unwrap();
+        assert_eq!(changed.status(), http::StatusCode::OK);
+        assert_ne!(changed.headers()[http::header::ETAG], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_me_requires_token() {
+        let router = setup_router().await;
+
+        let (status, _) = send_json(
+            &router,
+            http::Request::builder()
+                .uri("/api/user/me")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_register_accepts_form_encoded_body() {
+        let router = setup_router().await;
+
+        let response = router
+            .clone()
+            .oneshot(
+                http::Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/api/user/register")
+                    .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "username=alice&email=alice%40example.com&password=password123",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::CREATED);
+
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": "alice", "password": "password123" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_invalid_payload_before_user_service() {
+        let (router, pool) = setup_router_with_pool().await;
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": "no spaces",
+                    "email": "not-an-email",
+                    "password": "short",
+                }),
+            ),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Validation failed");
+        assert_eq!(field_names(&body), ["username", "email", "password"]);
+
+        let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(users, 0);
+    }
+
+    #[tokio::test]
+    async fn test_admin_list_users_requires_admin() {
+        let (router, pool) = setup_router_with_pool().await;
+        let alice = register_and_login(&router, "alice").await;
+        let alice_token = alice["access_token"].as_str().unwrap();
+
+        let (status, _) = send_json(&router, get_with_token("/api/admin/users", alice_token)).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+
+        let admin_token = promote_to_admin(&router, &pool, "alice").await;
+        register_and_login(&router, "bob").await;
+
+        let (status, body) = send_json(
+            &router,
+            get_with_token("/api/admin/users?role=user&verified=false", &admin_token),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(usernames(&body), ["bob"]);
+    }
+
+    /// Make an already registered user an admin and return a fresh access token carrying the role
+    async fn promote_to_admin(router: &Router, pool: &sqlx::SqlitePool, username: &str) -> String {
+        sqlx::query("UPDATE users SET role = 'admin' WHERE username = ?")
+            .bind(username)
+            .execute(pool)
+            .await
+            .unwrap();
+        let (_, login) = send_json(
+            router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": username, "password": "password123" }),
+            ),
+        )
+        .await;
+        login["access_token"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_admin_deleted_user_is_hidden_until_purged() {
+        let (router, pool) = setup_router_with_pool().await;
+        register_and_login(&router, "admin").await;
+        let bob = register_and_login(&router, "bob").await;
+        let bob_id = bob["user"]["id"].as_str().unwrap();
+        let admin_token = promote_to_admin(&router, &pool, "admin").await;
+
+        let response = router
+            .clone()
+            .oneshot(delete_with_token(
+                &format!("/api/admin/users/{}", bob_id),
+                &admin_token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": "bob", "password": "password123" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+
+        let purge = |days: u32| {
+            let mut request = post_json(
+                &format!("/api/admin/users/purge?older_than_days={}", days),
+                serde_json::json!({}),
+            );
+            request.headers_mut().insert(
+                AUTHORIZATION,
+                format!("Bearer {}", admin_token).parse().unwrap(),
+            );
+            request
+        };
+        // A window reaching past the earliest representable date is refused, not a panic
+        let (status, body) = send_json(&router, purge(100_000_000)).await;
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "validation_error");
+
+        let (status, body) = send_json(&router, purge(30)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["purged"], 0);
+
+        let (_, body) = send_json(&router, purge(0)).await;
+        assert_eq!(body["purged"], 1);
+
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[tokio::test]
+    async fn test_admin_logout_all_revokes_every_session() {
+        let (router, pool) = setup_router_with_pool().await;
+        register_and_login(&router, "admin").await;
+        let bob = register_and_login(&router, "bob").await;
+        let bob_id = bob["user"]["id"].as_str().unwrap();
+        let phone = login_with_user_agent(&router, "bob", "phone").await;
+        let admin_token = promote_to_admin(&router, &pool, "admin").await;
+        let logout_all = |token: &str| {
+            let mut request = post_json(
+                &format!("/api/admin/users/{}/logout-all", bob_id),
+                serde_json::json!({}),
+            );
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+            request
+        };
+
+        let bob_token = bob["access_token"].as_str().unwrap();
+        let (status, _) = send_json(&router, logout_all(bob_token)).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+
+        let (status, body) = send_json(&router, logout_all(&admin_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["revoked"], 2);
+
+        for session in [&bob, &phone] {
+            let (status, _) = send_json(
+                &router,
+                post_json(
+                    "/api/user/refresh",
+                    serde_json::json!({ "refresh_token": session["refresh_token"] }),
+                ),
+            )
+            .await;
+            assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+        }
+
+        // The admin's own session is untouched
+        let (_, sessions) = send_json(
+            &router,
+            get_with_token("/api/user/me/sessions", &admin_token),
+        )
+        .await;
+        assert_eq!(sessions.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_username_differing_only_in_case() {
+        let router = setup_router().await;
+        let register = |username: &str, email: &str| {
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": username,
+                    "email": email,
+                    "password": "password123",
+                }),
+            )
+        };
+
+        let (status, _) = send_json(&router, register("Alice", "first@example.com")).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+
+        let (status, body) = send_json(&router, register("alice", "second@example.com")).await;
+        assert_eq!(status, http::StatusCode::CONFLICT);
+        assert_eq!(body["error"], "Already taken");
+    }
 
-    Ok(router)
+    #[tokio::test]
+    async fn test_duplicate_registration_can_look_like_success() {
+        let register = |email: &str| {
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": "alice",
+                    "email": email,
+                    "password": "password123",
+                }),
+            )
+        };
+
+        for hide_registration_conflicts in [false, true] {
+            let config = AppConfig {
+                hide_registration_conflicts,
+                ..test_config()
+            };
+            let (router, pool) = setup_router_with_config(config).await;
+            let (status, created) = send_json(&router, register("first@example.com")).await;
+            assert_eq!(status, http::StatusCode::CREATED);
+
+            let (status, body) = send_json(&router, register("second@example.com")).await;
+
+            if hide_registration_conflicts {
+                assert_eq!(status, http::StatusCode::CREATED);
+                assert_eq!(body, created);
+            } else {
+                assert_eq!(status, http::StatusCode::CONFLICT);
+                assert_eq!(body["error"], "Already taken");
+            }
+            let emails: Vec<String> = sqlx::query_scalar("SELECT email FROM users")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            assert_eq!(emails, ["first@example.com"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auth_endpoints_require_https_when_configured() {
+        let config = AppConfig {
+            require_https: true,
+            ..test_config()
+        };
+        let (router, _) = setup_router_with_config(config).await;
+        let register = |proto: Option<&str>| {
+            let mut request = post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": "alice",
+                    "email": "alice@example.com",
+                    "password": "password123",
+                }),
+            );
+            if let Some(proto) = proto {
+                request.headers_mut().insert(
+                    "x-forwarded-proto",
+                    http::HeaderValue::from_str(proto).unwrap(),
+                );
+            }
+            request
+        };
+
+        for proto in [None, Some("http")] {
+            let (status, _) = send_json(&router, register(proto)).await;
+            assert_eq!(status, http::StatusCode::UPGRADE_REQUIRED);
+        }
+        let mut login = post_json(
+            "/api/user/login",
+            serde_json::json!({ "username": "alice", "password": "password123" }),
+        );
+        login
+            .headers_mut()
+            .insert("x-forwarded-proto", http::HeaderValue::from_static("http"));
+        let (status, _) = send_json(&router, login).await;
+        assert_eq!(status, http::StatusCode::UPGRADE_REQUIRED);
+
+        let (status, _) = send_json(&router, register(Some("https"))).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_register_refuses_disposable_email_domains() {
+        let list = std::env::temp_dir().join(format!("sultan-disposable-{}", Uuid::new_v4()));
+        std::fs::write(&list, "throwaway.test\n").unwrap();
+        let config = AppConfig {
+            block_disposable_emails: true,
+            disposable_email_domains_file: Some(list.display().to_string()),
+            ..test_config()
+        };
+        let (router, _) = setup_router_with_config(config).await;
+        std::fs::remove_file(&list).unwrap();
+        let register = |username: &str, email: &str| {
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": username,
+                    "email": email,
+                    "password": "password123",
+                }),
+            )
+        };
+
+        let (status, body) =
+            send_json(&router, register("alice", "alice@Inbox.Throwaway.Test")).await;
+        assert_eq!(status, http::StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "Disposable email addresses are not accepted");
+
+        let (status, _) = send_json(&router, register("bob", "bob@example.com")).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_register_refuses_password_over_byte_cap() {
+        let config = AppConfig {
+            max_password_bytes: 16,
+            ..test_config()
+        };
+        let (router, _) = setup_router_with_config(config).await;
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": "alice",
+                    "email": "alice@example.com",
+                    "password": "password123456789",
+                }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "Password must be at most 16 bytes");
+
+        // Nothing was created, so the name is still free
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": "alice",
+                    "email": "alice@example.com",
+                    "password": "password123",
+                }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_register_applies_default_password_byte_cap() {
+        let router = setup_router().await;
+        let register = |username: &str, password: String| {
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": username,
+                    "email": format!("{}@example.com", username),
+                    "password": password,
+                }),
+            )
+        };
+
+        // Well past the old 128 character rule, still within the 1024 byte default
+        let long = format!("{}1", "p".repeat(199));
+        let (status, _) = send_json(&router, register("alice", long.clone())).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": "alice", "password": long }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+
+        let (status, body) =
+            send_json(&router, register("bob", format!("{}1", "p".repeat(1024)))).await;
+        assert_eq!(status, http::StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "Password must be at most 1024 bytes");
+    }
+
+    #[tokio::test]
+    async fn test_login_accepts_email_when_configured() {
+        let config = AppConfig {
+            login_identifier: LoginIdentifier::Either,
+            ..test_config()
+        };
+        let (router, _) = setup_router_with_config(config).await;
+        register_and_login(&router, "alice").await;
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "email": "alice@example.com", "password": "password123" }),
+            ),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["user"]["username"], "alice");
+    }
+
+    #[tokio::test]
+    async fn test_register_follows_registration_toggle() {
+        let register = || {
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": "alice",
+                    "email": "alice@example.com",
+                    "password": "password123",
+                }),
+            )
+        };
+
+        let (status, _) = send_json(&setup_router().await, register()).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+
+        let config = AppConfig {
+            registration_enabled: false,
+            ..test_config()
+        };
+        let (router, _) = setup_router_with_config(config).await;
+        let (status, body) = send_json(&router, register()).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "Registration is closed");
+    }
+
+    #[tokio::test]
+    async fn test_registration_is_recorded_in_audit_log() {
+        let (router, pool) = setup_router_with_config(test_config()).await;
+        let mut register = post_json(
+            "/api/user/register",
+            serde_json::json!({
+                "username": "alice",
+                "email": "alice@example.com",
+                "password": "password123",
+            }),
+        );
+        register
+            .extensions_mut()
+            .insert(axum::extract::ConnectInfo(SocketAddr::from((
+                [203, 0, 113, 7],
+                4242,
+            ))));
+        let (status, _) = send_json(&router, register).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+
+        let admin_token = promote_to_admin(&router, &pool, "alice").await;
+        let response = router
+            .clone()
+            .oneshot(get_with_token("/api/admin/audit-log", &admin_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers()["x-total-count"], "2");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let entries: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        // Promoting logs alice in, which is recorded too
+        let registered = entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["action"] == "register")
+            .unwrap();
+        assert_eq!(registered["actor_id"], registered["target_id"]);
+        assert_eq!(registered["ip"], "203.0.113.7");
+        let alice_id: String = sqlx::query_scalar("SELECT id FROM users WHERE username = 'alice'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(registered["target_id"], alice_id);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_exports_as_ndjson() {
+        let (router, pool) = setup_router_with_config(test_config()).await;
+        for username in ["alice", "bob", "carol"] {
+            register_and_login(&router, username).await;
+        }
+        let admin_token = promote_to_admin(&router, &pool, "alice").await;
+
+        let response = router
+            .clone()
+            .oneshot(get_with_token("/api/admin/audit/export", &admin_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.ends_with('\n'));
+        let entries: Vec<serde_json::Value> = body
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // Three registrations, three logins and alice's login on promotion
+        assert_eq!(entries.len(), 7);
+        assert!(entries.iter().all(serde_json::Value::is_object));
+        assert_eq!(entries[0]["action"], "register");
+
+        let response = router
+            .clone()
+            .oneshot(get_with_token(
+                "/api/admin/audit/export?from=2100-01-01T00:00:00",
+                &admin_token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(body.is_empty());
+
+        // Also served next to the list endpoint
+        let response = router
+            .oneshot(get_with_token("/api/admin/audit-log/export", &admin_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_sees_client_behind_trusted_proxy_only() {
+        let config = AppConfig {
+            trusted_proxies: vec!["10.0.0.0/8".parse().unwrap()],
+            ..test_config()
+        };
+        let (router, pool) = setup_router_with_config(config).await;
+        let register = |username: &str, peer: [u8; 4]| {
+            let mut request = post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": username,
+                    "email": format!("{}@example.com", username),
+                    "password": "password123",
+                }),
+            );
+            request.headers_mut().insert(
+                "x-forwarded-for",
+                http::HeaderValue::from_static("198.51.100.7"),
+            );
+            request
+                .extensions_mut()
+                .insert(axum::extract::ConnectInfo(SocketAddr::from((peer, 4242))));
+            request
+        };
+
+        let (status, _) = send_json(&router, register("proxied", [10, 0, 0, 2])).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+        let (status, _) = send_json(&router, register("direct", [203, 0, 113, 7])).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+
+        let ips: Vec<(String, String)> = sqlx::query_as(
+            "SELECT users.username, audit_log.ip FROM audit_log \
+             JOIN users ON users.id = audit_log.target_id ORDER BY users.username",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            ips,
+            [
+                ("direct".to_string(), "203.0.113.7".to_string()),
+                ("proxied".to_string(), "198.51.100.7".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invite_only_registration_consumes_codes() {
+        let config = AppConfig {
+            registration_invite_only: true,
+            ..test_config()
+        };
+        let (router, pool) = setup_router_with_config(config).await;
+        let register = |username: &str, invite_code: Option<&str>| {
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": username,
+                    "email": format!("{}@example.com", username),
+                    "password": "password123",
+                    "invite_code": invite_code,
+                }),
+            )
+        };
+
+        let (status, _) = send_json(&router, register("bob", None)).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+        let (status, _) = send_json(&router, register("bob", Some("not-a-real-code"))).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+
+        // Admins are seeded rather than registered, so skip the invite check
+        SqliteUserRepository::new(pool.clone())
+            .create_user(
+                &Username::parse("admin").unwrap(),
+                &Email::parse("admin@example.com").unwrap(),
+                &Argon2PasswordHasher::default()
+                    .hash_password("password123")
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let admin_token = promote_to_admin(&router, &pool, "admin").await;
+        let mut mint = post_json("/api/admin/invites", serde_json::json!({}));
+        mint.headers_mut().insert(
+            AUTHORIZATION,
+            format!("Bearer {}", admin_token).parse().unwrap(),
+        );
+        let (status, invite) = send_json(&router, mint).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+        let code = invite["invite_code"].as_str().unwrap();
+
+        let (status, _) = send_json(&router, register("bob", Some(code))).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+
+        let (status, body) = send_json(&router, register("carol", Some(code))).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "Invite code is invalid or already used");
+        let carol: Option<String> =
+            sqlx::query_scalar("SELECT id FROM users WHERE username = 'carol'")
+                .fetch_optional(&pool)
+                .await
+                .unwrap();
+        assert_eq!(carol, None);
+    }
+
+    #[tokio::test]
+    async fn test_register_ignores_requested_role() {
+        let router = setup_router().await;
+
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": "mallory",
+                    "email": "mallory@example.com",
+                    "password": "password123",
+                    "role": "admin",
+                }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::CREATED);
+
+        let (_, login) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": "mallory", "password": "password123" }),
+            ),
+        )
+        .await;
+        assert_eq!(login["user"]["role"], "user");
+        let token = login["access_token"].as_str().unwrap();
+        let (status, _) = send_json(&router, get_with_token("/api/admin/users", token)).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+    }
+
+    fn set_role_request(user_id: &str, role: &str, access_token: &str) -> http::Request<Body> {
+        let mut request = post_json(
+            &format!("/api/admin/users/{}/role", user_id),
+            serde_json::json!({ "role": role }),
+        );
+        *request.method_mut() = http::Method::PUT;
+        with_token(request, access_token)
+    }
+
+    #[tokio::test]
+    async fn test_demoted_admin_loses_access_and_sessions() {
+        let (router, pool) = setup_router_with_pool().await;
+        register_and_login(&router, "alice").await;
+        let bob = register_and_login(&router, "bob").await;
+        let bob_id = bob["user"]["id"].as_str().unwrap();
+        let alice_token = promote_to_admin(&router, &pool, "alice").await;
+        let (status, _) = send_json(&router, set_role_request(bob_id, "admin", &alice_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        let (_, login) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": "bob", "password": "password123" }),
+            ),
+        )
+        .await;
+        let bob_token = login["access_token"].as_str().unwrap();
+        let bob_refresh = login["refresh_token"].as_str().unwrap();
+        let (status, _) = send_json(&router, get_with_token("/api/admin/users", bob_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+
+        let (status, body) =
+            send_json(&router, set_role_request(bob_id, "user", &alice_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["role"], "user");
+
+        // The access token still says admin, the stored role doesn't
+        let (status, _) = send_json(&router, get_with_token("/api/admin/users", bob_token)).await;
+        assert_eq!(status, http::StatusCode::FORBIDDEN);
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/refresh",
+                serde_json::json!({ "refresh_token": bob_refresh }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_last_admin_cannot_be_demoted() {
+        let (router, pool) = setup_router_with_pool().await;
+        let alice = register_and_login(&router, "alice").await;
+        let alice_id = alice["user"]["id"].as_str().unwrap();
+        let alice_token = promote_to_admin(&router, &pool, "alice").await;
+
+        let (status, body) =
+            send_json(&router, set_role_request(alice_id, "user", &alice_token)).await;
+
+        assert_eq!(status, http::StatusCode::CONFLICT);
+        assert_eq!(body["error"], "Cannot demote the last admin");
+        let (status, _) =
+            send_json(&router, get_with_token("/api/admin/users", &alice_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_last_admin_cannot_be_deleted() {
+        let (router, pool) = setup_router_with_pool().await;
+        let alice = register_and_login(&router, "alice").await;
+        let alice_uri = format!("/api/admin/users/{}", alice["user"]["id"].as_str().unwrap());
+        let alice_token = promote_to_admin(&router, &pool, "alice").await;
+
+        let (status, body) = send_json(&router, delete_with_token(&alice_uri, &alice_token)).await;
+
+        assert_eq!(status, http::StatusCode::CONFLICT);
+        assert_eq!(body["error"], "Cannot delete the last admin");
+        let (status, body) = send_json(&router, get_with_token(&alice_uri, &alice_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["deleted_at"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_user_sessions_stay_revoked_after_restore() {
+        let (router, pool) = setup_router_with_pool().await;
+        register_and_login(&router, "admin").await;
+        let bob = register_and_login(&router, "bob").await;
+        let bob_uri = format!("/api/admin/users/{}", bob["user"]["id"].as_str().unwrap());
+        let admin_token = promote_to_admin(&router, &pool, "admin").await;
+
+        let response = router
+            .clone()
+            .oneshot(delete_with_token(&bob_uri, &admin_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+        let restore = post_json(&format!("{}/restore", bob_uri), serde_json::json!({}));
+        let response = router
+            .clone()
+            .oneshot(with_token(restore, &admin_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/refresh",
+                serde_json::json!({ "refresh_token": bob["refresh_token"] }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_admin_sets_only_known_roles() {
+        let (router, pool) = setup_router_with_pool().await;
+        register_and_login(&router, "admin").await;
+        let bob = register_and_login(&router, "bob").await;
+        let bob_id = bob["user"]["id"].as_str().unwrap();
+        let admin_token = promote_to_admin(&router, &pool, "admin").await;
+        let set_role = |role: &str| {
+            let mut request = post_json(
+                &format!("/api/admin/users/{}/role", bob_id),
+                serde_json::json!({ "role": role }),
+            );
+            *request.method_mut() = http::Method::PUT;
+            request.headers_mut().insert(
+                AUTHORIZATION,
+                format!("Bearer {}", admin_token).parse().unwrap(),
+            );
+            request
+        };
+
+        let (status, body) = send_json(&router, set_role("superuser")).await;
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(field_names(&body), ["role"]);
+
+        let (status, body) = send_json(&router, set_role("admin")).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["role"], "admin");
+    }
+
+    #[tokio::test]
+    async fn test_admin_sees_and_restores_deleted_users() {
+        let (router, pool) = setup_router_with_pool().await;
+        register_and_login(&router, "admin").await;
+        let bob = register_and_login(&router, "bob").await;
+        let bob_id = bob["user"]["id"].as_str().unwrap();
+        let admin_token = promote_to_admin(&router, &pool, "admin").await;
+        let bob_uri = format!("/api/admin/users/{}", bob_id);
+
+        let (status, body) = send_json(&router, get_with_token(&bob_uri, &admin_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["deleted_at"], serde_json::Value::Null);
+
+        let response = router
+            .clone()
+            .oneshot(delete_with_token(&bob_uri, &admin_token))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+
+        let (status, body) = send_json(&router, get_with_token(&bob_uri, &admin_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["username"], "bob");
+        assert!(body["deleted_at"].is_string(), "{}", body);
+
+        let missing_uri = format!("/api/admin/users/{}", uuid::Uuid::new_v4());
+        let (status, _) = send_json(&router, get_with_token(&missing_uri, &admin_token)).await;
+        assert_eq!(status, http::StatusCode::NOT_FOUND);
+
+        let mut restore = post_json(&format!("{}/restore", bob_uri), serde_json::json!({}));
+        restore.headers_mut().insert(
+            AUTHORIZATION,
+            format!("Bearer {}", admin_token).parse().unwrap(),
+        );
+        let response = router.clone().oneshot(restore).await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+
+        let (status, body) = send_json(&router, get_with_token(&bob_uri, &admin_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["deleted_at"], serde_json::Value::Null);
+    }
+
+    fn with_token(mut request: http::Request<Body>, access_token: &str) -> http::Request<Body> {
+        request.headers_mut().insert(
+            AUTHORIZATION,
+            format!("Bearer {}", access_token).parse().unwrap(),
+        );
+        request
+    }
+
+    /// Store `token` for `user_id` in one of the single-use token tables, valid for an hour
+    async fn insert_token(pool: &sqlx::SqlitePool, table: &str, user_id: &str, token: &str) {
+        sqlx::query(&format!(
+            "INSERT INTO {} (token_hash, user_id, expires_at) VALUES (?, ?, ?)",
+            table
+        ))
+        .bind(crate::crypto::token::hash_token(token))
+        .bind(user_id)
+        .bind(chrono::Utc::now().naive_utc() + chrono::Duration::hours(1))
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_success_bodies_are_wrapped_when_envelope_is_enabled() {
+        let config = AppConfig {
+            response_envelope: true,
+            ..test_config()
+        };
+        let (router, pool) = setup_router_with_config(config).await;
+        let register = |username: &str| {
+            post_json(
+                "/api/user/register",
+                serde_json::json!({
+                    "username": username,
+                    "email": format!("{}@example.com", username),
+                    "password": "password123",
+                }),
+            )
+        };
+        let login = |username: &str| {
+            post_json(
+                "/api/user/login",
+                serde_json::json!({ "username": username, "password": "password123" }),
+            )
+        };
+
+        let (status, body) = send_json(&router, register("alice")).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+        assert_eq!(body, serde_json::json!({ "data": { "success": true } }));
+        send_json(&router, register("bob")).await;
+
+        sqlx::query("UPDATE users SET role = 'admin' WHERE username = 'alice'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let (status, body) = send_json(&router, login("alice")).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["data"]["user"]["username"], "alice");
+        let token = body["data"]["access_token"].as_str().unwrap().to_string();
+        let refresh_token = body["data"]["refresh_token"].as_str().unwrap().to_string();
+        let (_, body) = send_json(&router, login("bob")).await;
+        let bob_id = body["data"]["user"]["id"].as_str().unwrap().to_string();
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/refresh",
+                serde_json::json!({ "refresh_token": refresh_token }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert!(body["data"]["access_token"].is_string());
+
+        let (_, body) = send_json(&router, get_with_token("/api/user/me", &token)).await;
+        assert_eq!(body["data"]["username"], "alice");
+
+        let (_, body) = send_json(&router, get_with_token("/api/user/me/sessions", &token)).await;
+        assert!(body["data"].is_array());
+
+        insert_token(&pool, "email_verification_tokens", &bob_id, "verify-token").await;
+        let verify = http::Request::builder()
+            .uri("/api/user/verify?token=verify-token")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send_json(&router, verify).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "data": { "success": true } }));
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/password-reset/request",
+                serde_json::json!({ "email": "bob@example.com" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "data": { "success": true } }));
+
+        insert_token(&pool, "password_reset_tokens", &bob_id, "reset-token").await;
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/password-reset/confirm",
+                serde_json::json!({ "token": "reset-token", "new_password": "newpassword123" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "data": { "success": true } }));
+
+        let (_, body) = send_json(&router, get_with_token("/api/admin/users", &token)).await;
+        assert!(body["data"].is_array());
+
+        let (_, body) = send_json(
+            &router,
+            get_with_token(&format!("/api/admin/users/{}", bob_id), &token),
+        )
+        .await;
+        assert_eq!(body["data"]["username"], "bob");
+
+        let mut set_role = post_json(
+            &format!("/api/admin/users/{}/role", bob_id),
+            serde_json::json!({ "role": "user" }),
+        );
+        *set_role.method_mut() = http::Method::PUT;
+        let (status, body) = send_json(&router, with_token(set_role, &token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body["data"]["role"], "user");
+
+        let purge = post_json(
+            "/api/admin/users/purge?older_than_days=30",
+            serde_json::json!({}),
+        );
+        let (status, body) = send_json(&router, with_token(purge, &token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "data": { "purged": 0 } }));
+
+        let invite = post_json("/api/admin/invites", serde_json::json!({}));
+        let (status, body) = send_json(&router, with_token(invite, &token)).await;
+        assert_eq!(status, http::StatusCode::CREATED);
+        assert!(body["data"]["invite_code"].is_string());
+
+        let logout_all = post_json(
+            &format!("/api/admin/users/{}/logout-all", bob_id),
+            serde_json::json!({}),
+        );
+        let (status, body) = send_json(&router, with_token(logout_all, &token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert!(body["data"]["revoked"].is_number());
+
+        let (_, body) = send_json(&router, get_with_token("/api/admin/audit-log", &token)).await;
+        assert!(body["data"].is_array());
+
+        let (status, body) = send_json(&router, get_with_token("/api/user/me", "bogus")).await;
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+        assert!(body.get("data").is_none());
+    }
+
+    fn usernames(body: &serde_json::Value) -> Vec<&str> {
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|user| user["username"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_admin_empty_search_returns_newest_first() {
+        let (router, pool) = setup_router_with_pool().await;
+        for (username, created_at) in [
+            ("admin", "2026-01-01 00:00:00"),
+            ("bob", "2026-01-02 00:00:00"),
+            ("carol", "2026-01-03 00:00:00"),
+            ("bobby", "2026-01-04 00:00:00"),
+        ] {
+            register_and_login(&router, username).await;
+            sqlx::query("UPDATE users SET created_at = ? WHERE username = ?")
+                .bind(created_at)
+                .bind(username)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let admin_token = promote_to_admin(&router, &pool, "admin").await;
+
+        let (status, body) =
+            send_json(&router, get_with_token("/api/admin/users?q=", &admin_token)).await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(usernames(&body), ["bobby", "carol", "bob", "admin"]);
+
+        let (_, body) = send_json(
+            &router,
+            get_with_token("/api/admin/users?q=&limit=2", &admin_token),
+        )
+        .await;
+        assert_eq!(usernames(&body), ["bobby", "carol"]);
+
+        let (_, body) = send_json(
+            &router,
+            get_with_token("/api/admin/users?q=BOB", &admin_token),
+        )
+        .await;
+        assert_eq!(usernames(&body), ["bobby", "bob"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_shows_metadata_only() {
+        let router = setup_router().await;
+        let first = register_and_login(&router, "alice").await;
+        let second = login_with_user_agent(&router, "alice", "Phone/1.0").await;
+        let access_token = second["access_token"].as_str().unwrap();
+
+        let (status, body) = send_json(
+            &router,
+            get_with_token("/api/user/me/sessions", access_token),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::OK);
+        let sessions = body.as_array().unwrap();
+        assert_eq!(sessions.len(), 2);
+        let agents: Vec<_> = sessions.iter().map(|s| &s["user_agent"]).collect();
+        assert!(agents.contains(&&serde_json::json!("Phone/1.0")));
+        assert!(agents.contains(&&serde_json::Value::Null));
+        for session in sessions {
+            assert!(session["created_at"].is_string());
+            assert!(session.get("last_used_at").is_some());
+        }
+        let raw = body.to_string();
+        assert!(!raw.contains(first["refresh_token"].as_str().unwrap()));
+        assert!(!raw.contains(second["refresh_token"].as_str().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_list_sessions_is_paginated() {
+        let router = setup_router().await;
+        register_and_login(&router, "alice").await;
+        for i in 0..4 {
+            login_with_user_agent(&router, "alice", &format!("Device/{}", i)).await;
+        }
+        let latest = login_with_user_agent(&router, "alice", "Device/latest").await;
+        let access_token = latest["access_token"].as_str().unwrap();
+
+        let mut seen = Vec::new();
+        for offset in [0, 2, 4] {
+            let response = router
+                .clone()
+                .oneshot(get_with_token(
+                    &format!("/api/user/me/sessions?limit=2&offset={}", offset),
+                    access_token,
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), http::StatusCode::OK);
+            assert_eq!(response.headers()["x-total-count"], "6");
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let page: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            let page = page.as_array().unwrap();
+            assert_eq!(page.len(), 2);
+            seen.extend(page.iter().map(|s| s["id"].clone()));
+        }
+
+        seen.sort_by_key(|id| id.to_string());
+        seen.dedup();
+        assert_eq!(seen.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_session_signs_out_only_that_session() {
+        let router = setup_router().await;
+        let laptop = register_and_login(&router, "alice").await;
+        let phone = login_with_user_agent(&router, "alice", "Phone/1.0").await;
+        let access_token = laptop["access_token"].as_str().unwrap();
+
+        let (_, sessions) = send_json(
+            &router,
+            get_with_token("/api/user/me/sessions", access_token),
+        )
+        .await;
+        let phone_session = sessions
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["user_agent"] == "Phone/1.0")
+            .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let response = router
+            .clone()
+            .oneshot(delete_with_token(
+                &format!("/api/user/me/sessions/{}", phone_session),
+                access_token,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), http::StatusCode::NO_CONTENT);
+
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/refresh",
+                serde_json::json!({ "refresh_token": phone["refresh_token"] }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED);
+
+        let (status, _) = send_json(
+            &router,
+            post_json(
+                "/api/user/refresh",
+                serde_json::json!({ "refresh_token": laptop["refresh_token"] }),
+            ),
+        )
+        .await;
+        assert_eq!(status, http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_session_of_another_user_is_not_found() {
+        let router = setup_router().await;
+        let alice = register_and_login(&router, "alice").await;
+        let bob = register_and_login(&router, "bob").await;
+
+        let (_, sessions) = send_json(
+            &router,
+            get_with_token(
+                "/api/user/me/sessions",
+                bob["access_token"].as_str().unwrap(),
+            ),
+        )
+        .await;
+        let bob_session = sessions[0]["id"].as_str().unwrap();
+
+        let (status, body) = send_json(
+            &router,
+            delete_with_token(
+                &format!("/api/user/me/sessions/{}", bob_session),
+                alice["access_token"].as_str().unwrap(),
+            ),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "Session not found");
+    }
+
+    fn field_names(body: &serde_json::Value) -> Vec<&str> {
+        body["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field["field"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_oversized_fields_before_database() {
+        let (router, pool) = setup_router_with_pool().await;
+        // Any lookup would now fail, so a 400 proves validation ran first
+        pool.close().await;
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({
+                    "username": "a".repeat(LEGACY_USERNAME_MAX_LENGTH + 1),
+                    "password": "password123",
+                }),
+            ),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Validation failed");
+        assert_eq!(field_names(&body), ["username"]);
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({
+                    "username": "alice",
+                    "password": "p".repeat(1025),
+                }),
+            ),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"], "Password must be at most 1024 bytes");
+    }
+
+    #[tokio::test]
+    async fn test_login_accepts_usernames_longer_than_the_registration_limit() {
+        let router = setup_router().await;
+
+        // Accounts from before the 32 character limit may have up to 50
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/login",
+                serde_json::json!({
+                    "username": "a".repeat(USERNAME_MAX_LENGTH + 1),
+                    "password": "Password123!",
+                }),
+            ),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::UNAUTHORIZED, "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rejects_oversized_token_before_database() {
+        let (router, pool) = setup_router_with_pool().await;
+        pool.close().await;
+
+        let (status, body) = send_json(
+            &router,
+            post_json(
+                "/api/user/refresh",
+                serde_json::json!({ "refresh_token": "f".repeat(TOKEN_LENGTH + 1) }),
+            ),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(field_names(&body), ["refresh_token"]);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_is_rejected() {
+        let router = setup_router().await;
+
+        let response = router
+            .oneshot(post_json(
+                "/api/user/login",
+                serde_json::json!({
+                    "username": "alice",
+                    "password": "p".repeat(test_config().max_request_body_bytes),
+                }),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), http::StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_database_backend() {
+        let router = setup_router().await;
+
+        let (status, body) = send_json(
+            &router,
+            http::Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({ "status": "ok", "database": "sqlite" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seed_admin_runs_once() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        SQLITE_MIGRATOR.run(&pool).await.unwrap();
+        let config = AppConfig {
+            seed_admin: Some(SeedAdmin {
+                username: "root".into(),
+                email: "root@example.com".into(),
+                password: "password123".into(),
+            }),
+            ..test_config()
+        };
+        let app_state = build_app_state(config, DbPool::Sqlite(pool.clone())).unwrap();
+
+        seed_admin(&app_state).await.unwrap();
+        seed_admin(&app_state).await.unwrap();
+
+        let admins: Vec<(String, String)> =
+            sqlx::query_as("SELECT username, password_hash FROM users WHERE role = 'admin'")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(admins.len(), 1);
+        assert_eq!(admins[0].0, "root");
+        assert_ne!(admins[0].1, "password123");
+    }
+
+    #[tokio::test]
+    async fn test_readiness_is_503_with_retry_after_when_database_is_down() {
+        let (router, pool) = setup_router_with_pool().await;
+        pool.close().await;
+
+        let response = router
+            .oneshot(
+                http::Request::builder()
+                    .uri("/health/migrations")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[http::header::RETRY_AFTER], "5");
+    }
+
+    #[tokio::test]
+    async fn test_readiness_is_503_with_retry_after_when_migrations_pending() {
+        let (router, pool) = setup_router_with_pool().await;
+        let latest = SQLITE_MIGRATOR.iter().map(|m| m.version).max().unwrap();
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = ?")
+            .bind(latest)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let request = http::Request::builder()
+            .uri("/health/migrations")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[http::header::RETRY_AFTER], "5");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["pending"], serde_json::json!([latest]));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_connections_carry_application_name() {
+        let config = AppConfig {
+            database_type: DatabaseType::Postgres,
+            database_url: Some(
+                std::env::var("DATABASE_URL")
+                    .expect("DATABASE_URL environment variable must be set for PostgreSQL tests"),
+            ),
+            database_application_name: "sultan-test".into(),
+            ..test_config()
+        };
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_with(postgres_connect_options(&config).unwrap())
+            .await
+            .unwrap();
+
+        let application_name: String =
+            sqlx::query_scalar("SELECT current_setting('application_name')")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        assert_eq!(application_name, "sultan-test");
+    }
+
+    #[test]
+    fn test_statement_logging_follows_toggle() {
+        let statements_level = |db_log_statements| {
+            let config = AppConfig {
+                db_log_statements,
+                ..test_config()
+            };
+            let options = format!("{:?}", sqlite_connect_options(&config).unwrap());
+            assert!(options.contains("statements_level"));
+            options.contains("statements_level: Debug")
+        };
+
+        assert!(!statements_level(false));
+        assert!(statements_level(true));
+        assert_eq!(
+            statement_log_level(&test_config()),
+            log::LevelFilter::Off,
+            "statement logging must be off unless enabled"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_app_with_external_pool() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+
+        let router = create_app_with_pool(
+            test_config(),
+            DbPool::Sqlite(pool.clone()),
+            CancellationToken::new(),
+        )
+        .await
+        .unwrap();
+        let login = register_and_login(&router, "alice").await;
+
+        assert_eq!(login["user"]["username"], "alice");
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_app_with_pool_can_skip_migrations() {
+        let pool = DbPool::Sqlite(sqlx::SqlitePool::connect(":memory:").await.unwrap());
+        let config = AppConfig {
+            run_migrations: false,
+            ..test_config()
+        };
+
+        let _router = create_app_with_pool(config, pool.clone(), CancellationToken::new())
+            .await
+            .unwrap();
+
+        let pending = crate::persistence::pending_migrations(&pool).await.unwrap();
+        assert_eq!(pending.len(), SQLITE_MIGRATOR.iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_metrics_serves_sampled_pool_gauges() {
+        let pool = DbPool::Sqlite(sqlx::SqlitePool::connect(":memory:").await.unwrap());
+        let router = create_app_with_pool(test_config(), pool, CancellationToken::new())
+            .await
+            .unwrap();
+        let scrape = || {
+            router.clone().oneshot(
+                http::Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        // The sampler's first tick fires straight away, but on its own task
+        let mut body = String::new();
+        for _ in 0..100 {
+            let response = scrape().await.unwrap();
+            assert_eq!(response.status(), http::StatusCode::OK);
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            body = String::from_utf8(bytes.to_vec()).unwrap();
+            if !body.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        assert!(body.contains("db_pool_connections "), "{}", body);
+        assert!(body.contains("db_pool_idle_connections "), "{}", body);
+        assert!(body.contains("db_pool_in_use_connections "), "{}", body);
+    }
+
+    fn get_with_bearer(uri: &str, token: Option<&str>) -> http::Request<Body> {
+        let mut request = http::Request::builder().uri(uri);
+        if let Some(token) = token {
+            request = request.header(http::header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_check_token_guards_health_and_metrics() {
+        let (router, _pool) = setup_router_with_config(AppConfig {
+            health_check_token: Some("probe-secret".into()),
+            ..test_config()
+        })
+        .await;
+
+        for uri in ["/health", "/metrics"] {
+            for token in [None, Some("wrong-secret")] {
+                let response = router
+                    .clone()
+                    .oneshot(get_with_bearer(uri, token))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), http::StatusCode::UNAUTHORIZED, "{}", uri);
+                assert_eq!(response.headers()[http::header::WWW_AUTHENTICATE], "Bearer");
+            }
+
+            let response = router
+                .clone()
+                .oneshot(get_with_bearer(uri, Some("probe-secret")))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), http::StatusCode::OK, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_and_metrics_stay_open_without_token() {
+        let router = setup_router().await;
+
+        for uri in ["/health", "/metrics"] {
+            let response = router
+                .clone()
+                .oneshot(get_with_bearer(uri, None))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), http::StatusCode::OK, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_pool_options_follow_config() {
+        let config = AppConfig {
+            db_idle_timeout: Duration::seconds(42),
+            db_max_lifetime: Duration::minutes(7),
+            ..test_config()
+        };
+
+        let options = pool_options::<Sqlite>(&config);
+
+        assert_eq!(
+            options.get_idle_timeout(),
+            Some(std::time::Duration::from_secs(42))
+        );
+        assert_eq!(
+            options.get_max_lifetime(),
+            Some(std::time::Duration::from_secs(7 * 60))
+        );
+    }
+
+    // Collects the target of every warning
+    #[derive(Clone, Default)]
+    struct RecordedWarnings(Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl<S: tracing::Subscriber> Layer<S> for RecordedWarnings {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if *event.metadata().level() == tracing::Level::WARN {
+                let target = event.metadata().target().to_string();
+                self.0.lock().unwrap().push(target);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_acquire_is_warned_and_counted() {
+        let warnings = RecordedWarnings::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(warnings.clone())
+            .with(SlowDbEventLayer.with_filter(SlowDbEventLayer::filter()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let config = AppConfig {
+            db_slow_acquire_threshold: Duration::milliseconds(10),
+            ..test_config()
+        };
+        let pool = pool_options::<Sqlite>(&config)
+            .max_connections(1)
+            .connect(":memory:")
+            .await
+            .unwrap();
+        let (slow_acquires_before, _) = crate::persistence::slow_db_event_counts();
+
+        // Hold the only connection past the threshold while a second acquire waits for it
+        let held = pool.acquire().await.unwrap();
+        let release = async {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            drop(held);
+        };
+        let (_, waited) = tokio::join!(release, pool.acquire());
+        waited.unwrap();
+
+        assert!(
+            warnings
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|target| target == "sqlx::pool::acquire")
+        );
+        let (slow_acquires, _) = crate::persistence::slow_db_event_counts();
+        assert!(slow_acquires > slow_acquires_before);
+
+        let metrics = Metrics::new();
+        crate::persistence::record_slow_db_events(&metrics);
+        assert_eq!(
+            metrics.gauge("db_slow_acquires_total"),
+            Some(slow_acquires as i64)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requests_beyond_the_concurrency_limit_are_shed() {
+        let (entered, mut entered_rx) = tokio::sync::mpsc::unbounded_channel();
+        let release = Arc::new(tokio::sync::Semaphore::new(0));
+        let slow = {
+            let release = release.clone();
+            move || async move {
+                entered.send(()).unwrap();
+                let _permit = release.acquire().await.unwrap();
+                "done"
+            }
+        };
+        let router = shed_load(
+            Router::new().route("/slow", axum::routing::get(slow)),
+            Some(2),
+        );
+        let slow_request = || http::Request::get("/slow").body(Body::empty()).unwrap();
+
+        let in_flight: Vec<_> = (0..2)
+            .map(|_| tokio::spawn(router.clone().oneshot(slow_request())))
+            .collect();
+        for _ in 0..2 {
+            entered_rx.recv().await.unwrap();
+        }
+
+        let shed = router.clone().oneshot(slow_request()).await.unwrap();
+        assert_eq!(shed.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+        assert!(shed.headers().contains_key(http::header::RETRY_AFTER));
+
+        release.add_permits(2);
+        for request in in_flight {
+            let response = request.await.unwrap().unwrap();
+            assert_eq!(response.status(), http::StatusCode::OK);
+        }
+    }
+
+    fn get_health() -> http::Request<Body> {
+        http::Request::builder()
+            .uri("/health")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_responses_carry_security_headers() {
+        let router = setup_router().await;
+
+        for request in [get_health(), get_with_token("/api/user/me", "not-a-token")] {
+            let response = router.clone().oneshot(request).await.unwrap();
+            let headers = response.headers();
+            assert_eq!(headers[X_CONTENT_TYPE_OPTIONS], "nosniff");
+            assert_eq!(headers[X_FRAME_OPTIONS], "DENY");
+            assert!(headers.get(STRICT_TRANSPORT_SECURITY).is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hsts_is_sent_when_enabled() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        let config = AppConfig {
+            hsts_enabled: true,
+            ..test_config()
+        };
+        let router = build_router(build_app_state(config, DbPool::Sqlite(pool)).unwrap());
+
+        let response = router.oneshot(get_health()).await.unwrap();
+
+        assert_eq!(
+            response.headers()[STRICT_TRANSPORT_SECURITY],
+            HSTS_HEADER_VALUE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_is_cached_and_exposes_headers() {
+        let router = setup_router().await;
+        let preflight = http::Request::builder()
+            .method(http::Method::OPTIONS)
+            .uri("/api/user/me")
+            .header(http::header::ORIGIN, "http://localhost:5173")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router.clone().oneshot(preflight).await.unwrap();
+        assert_eq!(
+            response.headers()[http::header::ACCESS_CONTROL_MAX_AGE],
+            "600"
+        );
+
+        // Signing out one session is a DELETE, so its preflight has to allow it too
+        let preflight = http::Request::builder()
+            .method(http::Method::OPTIONS)
+            .uri(format!("/api/user/me/sessions/{}", Uuid::new_v4()))
+            .header(http::header::ORIGIN, "http://localhost:5173")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD, "DELETE")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(preflight).await.unwrap();
+        let allowed = response.headers()[http::header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap();
+        assert!(allowed.contains("DELETE"), "{}", allowed);
+        assert!(allowed.contains("PUT"), "{}", allowed);
+
+        let mut request = get_health();
+        request.headers_mut().insert(
+            http::header::ORIGIN,
+            http::HeaderValue::from_static("http://localhost:5173"),
+        );
+        let response = router.oneshot(request).await.unwrap();
+        let exposed = response.headers()[http::header::ACCESS_CONTROL_EXPOSE_HEADERS]
+            .to_str()
+            .unwrap();
+        assert!(exposed.contains("x-request-id"), "{}", exposed);
+        assert!(exposed.contains("x-total-count"), "{}", exposed);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_uses_the_configured_header() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        let config = AppConfig {
+            request_id_header: http::HeaderName::from_static("x-correlation-id"),
+            ..test_config()
+        };
+        let router = build_router(build_app_state(config, DbPool::Sqlite(pool)).unwrap());
+
+        let mut request = get_health();
+        request.headers_mut().insert(
+            "x-correlation-id",
+            http::HeaderValue::from_static("abc-123"),
+        );
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["x-correlation-id"], "abc-123");
+        assert!(response.headers().get("x-request-id").is_none());
+
+        let response = router.oneshot(get_health()).await.unwrap();
+        let minted = response.headers()["x-correlation-id"].to_str().unwrap();
+        assert!(Uuid::parse_str(minted).is_ok(), "{}", minted);
+    }
+
+    #[tokio::test]
+    async fn test_cors_disabled_sends_no_cors_headers() {
+        let config = AppConfig {
+            cors_enabled: false,
+            ..test_config()
+        };
+        let (router, _) = setup_router_with_config(config).await;
+        let preflight = http::Request::builder()
+            .method(http::Method::OPTIONS)
+            .uri("/api/user/me")
+            .header(http::header::ORIGIN, "http://localhost:5173")
+            .header(http::header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap();
+        let mut request = get_health();
+        request.headers_mut().insert(
+            http::header::ORIGIN,
+            http::HeaderValue::from_static("http://localhost:5173"),
+        );
+
+        for request in [preflight, request] {
+            let response = router.clone().oneshot(request).await.unwrap();
+            let cors_headers: Vec<_> = response
+                .headers()
+                .keys()
+                .filter(|name| name.as_str().starts_with("access-control-"))
+                .collect();
+            assert!(cors_headers.is_empty(), "{:?}", cors_headers);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_log_file_does_not_stop_startup() {
+        init_tracing_with_log_file("/nonexistent-directory/app.log");
+
+        let (status, _) = send_json(&setup_router().await, get_health()).await;
+
+        assert_eq!(status, http::StatusCode::OK);
+    }
 }
@@ -0,0 +1,433 @@
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::{Path, Query, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+};
+use chrono::NaiveDateTime;
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use time::Duration;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        audit_logger::AuditLogger,
+        invite_service::InviteService,
+        session_service::SessionService,
+        user_service::UserService,
+    },
+    domain::{
+        audit::AuditAction,
+        user::{Role, User},
+    },
+    persistence::{AuditLogFilter, UserFilter},
+    web::{
+        app_state::AppState,
+        auth::AuthUser,
+        client_ip::ClientIp,
+        pagination::{Pagination, TOTAL_COUNT_HEADER},
+        response::{ApiResponse, ResponseEnvelope},
+        rfc3339,
+        user_routes::UserResponse,
+        validation::{FieldError, Validate, ValidatedJson, check_field},
+    },
+};
+
+/// Entries read from the database per chunk of an audit log export
+const EXPORT_BATCH_SIZE: u32 = 500;
+
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+// ============================================================================
+// DTOs (Request/Response models)
+// ============================================================================
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ListUsersQuery {
+    /// Username prefix, an empty value lists the newest users
+    q: Option<String>,
+    verified: Option<bool>,
+    role: Option<Role>,
+    created_after: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+struct AuditExportQuery {
+    from: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+}
+
+impl From<AuditExportQuery> for AuditLogFilter {
+    fn from(query: AuditExportQuery) -> Self {
+        Self {
+            from: query.from,
+            until: query.until,
+        }
+    }
+}
+
+impl From<ListUsersQuery> for UserFilter {
+    fn from(query: ListUsersQuery) -> Self {
+        Self {
+            username_prefix: query
+                .q
+                .map(|q| q.trim().to_string())
+                .filter(|q| !q.is_empty()),
+            email_verified: query.verified,
+            role: query.role,
+            created_after: query.created_after,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PurgeQuery {
+    /// Only purge users soft-deleted at least this many days ago
+    older_than_days: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct PurgeResponse {
+    purged: u64,
+}
+
+/// The role is a plain string so unknown values fail validation with a 400
+#[derive(Debug, Deserialize)]
+struct SetRoleRequest {
+    role: String,
+}
+
+impl Validate for SetRoleRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check_field(&mut errors, "role", self.role.parse::<Role>());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A user as admins see them, soft-deleted or not
+#[derive(Debug, Serialize)]
+struct AdminUserResponse {
+    #[serde(flatten)]
+    user: UserResponse,
+    #[serde(serialize_with = "rfc3339::option::serialize")]
+    deleted_at: Option<NaiveDateTime>,
+}
+
+impl From<User> for AdminUserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            deleted_at: user.deleted_at,
+            user: UserResponse::from(user),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct InviteResponse {
+    invite_code: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LogoutAllResponse {
+    revoked: u64,
+}
+
+// ============================================================================
+// HTTP Handlers
+// ============================================================================
+
+/// List a page of users, newest first, optionally searched by username prefix and filtered
+#[instrument(skip(user_service, auth_user), fields(admin_id = %auth_user.id))]
+async fn list_users(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(envelope): State<ResponseEnvelope>,
+    pagination: Pagination,
+    Query(query): Query<ListUsersQuery>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("List users endpoint called");
+
+    let users = user_service
+        .list_users(&query.into(), pagination.limit, pagination.offset)
+        .await?;
+
+    Ok(ApiResponse::new(
+        envelope,
+        users
+            .into_iter()
+            .map(UserResponse::from)
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Look up any user, including soft-deleted ones with their `deleted_at`
+/// A 404 here means the user never existed or has been purged
+#[instrument(skip(user_service, auth_user), fields(admin_id = %auth_user.id))]
+async fn get_user(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(envelope): State<ResponseEnvelope>,
+    Path(user_id): Path<Uuid>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Get user endpoint called");
+
+    let user = user_service.get_user_including_deleted(&user_id).await?;
+
+    Ok(ApiResponse::new(envelope, AdminUserResponse::from(user)))
+}
+
+/// Soft-delete a user, keeping the row until it is purged
+/// Their sessions are revoked, so no refresh token outlives the account
+#[instrument(skip(user_service, session_service, audit_logger, auth_user), fields(admin_id = %auth_user.id))]
+async fn delete_user(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(session_service): State<Arc<SessionService>>,
+    State(audit_logger): State<Arc<AuditLogger>>,
+    ClientIp(ip): ClientIp,
+    Path(user_id): Path<Uuid>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Delete user endpoint called");
+
+    user_service.delete_user(&user_id).await?;
+    session_service.revoke_all_sessions(user_id).await?;
+    audit_logger
+        .record(
+            Some(auth_user.id),
+            AuditAction::UserDeleted,
+            Some(user_id),
+            ip,
+        )
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Bring back a soft-deleted user that hasn't been purged yet
+#[instrument(skip(user_service, audit_logger, auth_user), fields(admin_id = %auth_user.id))]
+async fn restore_user(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(audit_logger): State<Arc<AuditLogger>>,
+    ClientIp(ip): ClientIp,
+    Path(user_id): Path<Uuid>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Restore user endpoint called");
+
+    user_service.restore_user(&user_id).await?;
+    audit_logger
+        .record(
+            Some(auth_user.id),
+            AuditAction::UserRestored,
+            Some(user_id),
+            ip,
+        )
+        .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Permanently remove users soft-deleted before the retention window
+#[instrument(skip(user_service, auth_user), fields(admin_id = %auth_user.id))]
+async fn purge_deleted_users(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(envelope): State<ResponseEnvelope>,
+    Query(query): Query<PurgeQuery>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Purge deleted users endpoint called");
+
+    let purged = user_service
+        .purge_deleted_users(Duration::days(i64::from(query.older_than_days)))
+        .await?;
+
+    Ok(ApiResponse::new(envelope, PurgeResponse { purged }))
+}
+
+/// Change a user's role, the only way anyone becomes an admin after seeding
+/// A changed role signs the user out everywhere, so no refresh token mints the old role
+#[instrument(skip(user_service, session_service, auth_user, payload), fields(admin_id = %auth_user.id))]
+async fn set_role(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(session_service): State<Arc<SessionService>>,
+    State(envelope): State<ResponseEnvelope>,
+    Path(user_id): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<SetRoleRequest>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Set role endpoint called");
+
+    let role = payload.role.parse()?;
+    let (user, changed) = user_service.set_role(&user_id, role).await?;
+    if changed {
+        session_service.revoke_all_sessions(user.id).await?;
+    }
+
+    Ok(ApiResponse::new(envelope, UserResponse::from(user)))
+}
+
+/// A page of the audit log, newest first
+/// The number of entries across every page is sent in `X-Total-Count`
+#[instrument(skip(audit_logger, auth_user), fields(admin_id = %auth_user.id))]
+async fn list_audit_log(
+    auth_user: AuthUser,
+    State(audit_logger): State<Arc<AuditLogger>>,
+    State(envelope): State<ResponseEnvelope>,
+    pagination: Pagination,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("List audit log endpoint called");
+
+    let (entries, total) = audit_logger
+        .list_entries(pagination.limit, pagination.offset)
+        .await?;
+
+    Ok((
+        [(TOTAL_COUNT_HEADER, total.to_string())],
+        ApiResponse::new(envelope, entries),
+    ))
+}
+
+/// Stream the audit log as newline-delimited JSON, oldest first, for SIEM ingestion
+/// Entries are read a batch at a time, so memory stays flat however long the log is.
+/// A database error mid-stream cuts the response short rather than ending it cleanly
+#[instrument(skip(audit_logger, auth_user), fields(admin_id = %auth_user.id))]
+async fn export_audit_log(
+    auth_user: AuthUser,
+    State(audit_logger): State<Arc<AuditLogger>>,
+    Query(query): Query<AuditExportQuery>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Export audit log endpoint called");
+
+    let filter = AuditLogFilter::from(query);
+    let batches = stream::unfold(Some(None), move |after| {
+        let audit_logger = audit_logger.clone();
+        async move {
+            let after = after?;
+            let batch = match audit_logger
+                .export_batch(&filter, after, EXPORT_BATCH_SIZE)
+                .await
+            {
+                Ok(batch) => batch,
+                Err(e) => {
+                    warn!(error = ?e, "Audit log export failed part way");
+                    return Some((Err(e), None));
+                }
+            };
+            let last = batch.last()?;
+            let next = Some((last.created_at, last.id));
+
+            let mut chunk = Vec::new();
+            for entry in &batch {
+                if let Err(e) = serde_json::to_writer(&mut chunk, entry) {
+                    return Some((Err(AppError::Internal(e.to_string())), None));
+                }
+                chunk.push(b'\n');
+            }
+            Some((Ok(Bytes::from(chunk)), Some(next)))
+        }
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(batches),
+    ))
+}
+
+/// Mint a single-use invite code for registration while it is invite-only
+/// The code is only returned here, it is stored hashed
+#[instrument(skip(invite_service, auth_user), fields(admin_id = %auth_user.id))]
+async fn create_invite(
+    auth_user: AuthUser,
+    State(invite_service): State<Arc<InviteService>>,
+    State(envelope): State<ResponseEnvelope>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Create invite endpoint called");
+
+    let invite_code = invite_service.mint_code(auth_user.id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        ApiResponse::new(envelope, InviteResponse { invite_code }),
+    ))
+}
+
+/// Revoke every refresh token of a user, so a compromised account must log in again
+/// Access tokens already issued stay valid until they expire
+#[instrument(skip(user_service, session_service, auth_user), fields(admin_id = %auth_user.id))]
+async fn logout_all(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(session_service): State<Arc<SessionService>>,
+    State(envelope): State<ResponseEnvelope>,
+    Path(user_id): Path<Uuid>,
+) -> AppResult<impl IntoResponse> {
+    auth_user.require_admin()?;
+    info!("Logout all endpoint called");
+
+    let user = user_service.get_user(&user_id).await?;
+    let revoked = session_service.revoke_all_sessions(user.id).await?;
+
+    Ok(ApiResponse::new(envelope, LogoutAllResponse { revoked }))
+}
+
+/// Check the stored role of the caller on every admin request
+/// Access tokens carry the role they were issued with, so without this a demoted or deleted
+/// admin would keep admin access until their token expires
+pub async fn require_current_admin(
+    State(user_service): State<Arc<UserService>>,
+    auth_user: AuthUser,
+    request: Request,
+    next: Next,
+) -> AppResult<Response> {
+    auth_user.require_admin()?;
+    let role = match user_service.get_user(&auth_user.id).await {
+        Ok(user) => Some(user.role),
+        Err(AppError::NotFound(_)) => None,
+        Err(e) => return Err(e),
+    };
+    if role != Some(Role::Admin) {
+        return Err(AppError::Forbidden("Admin role required".into()));
+    }
+
+    Ok(next.run(request).await)
+}
+
+// ============================================================================
+// Router
+// ============================================================================
+
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/users", get(list_users))
+        .route("/users/{id}", get(get_user).delete(delete_user))
+        .route("/users/{id}/restore", post(restore_user))
+        .route("/users/purge", post(purge_deleted_users))
+        .route("/users/{id}/role", put(set_role))
+        .route("/users/{id}/logout-all", post(logout_all))
+        .route("/invites", post(create_invite))
+        .route("/audit-log", get(list_audit_log))
+        .route("/audit/export", get(export_audit_log))
+        .route("/audit-log/export", get(export_audit_log))
+}
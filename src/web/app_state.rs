@@ -1,12 +1,39 @@
 use axum::extract::FromRef;
 use std::sync::Arc;
 
-use crate::{application::user_service::UserService, config::AppConfig};
+use crate::{
+    application::{
+        audit_logger::AuditLogger, email_verification_service::EmailVerificationService,
+        invite_service::InviteService, metrics::Metrics,
+        password_reset_service::PasswordResetService, session_service::SessionService,
+        token_service::TokenService, user_service::UserService,
+    },
+    config::AppConfig,
+    persistence::DbPool,
+    web::{
+        client_ip::TrustedProxies, health_routes::HealthCheckToken, response::ResponseEnvelope,
+        secure_transport::RequireHttps, user_routes::HideRegistrationConflicts,
+    },
+};
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<AppConfig>,
+    pub db_pool: DbPool,
     pub user_service: Arc<UserService>,
+    pub token_service: Arc<TokenService>,
+    pub session_service: Arc<SessionService>,
+    pub email_verification_service: Arc<EmailVerificationService>,
+    pub password_reset_service: Arc<PasswordResetService>,
+    pub invite_service: Arc<InviteService>,
+    pub audit_logger: Arc<AuditLogger>,
+    pub metrics: Arc<Metrics>,
+}
+
+impl FromRef<AppState> for Arc<Metrics> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.metrics.clone()
+    }
 }
 
 impl FromRef<AppState> for Arc<UserService> {
@@ -14,3 +41,75 @@ impl FromRef<AppState> for Arc<UserService> {
         app_state.user_service.clone()
     }
 }
+
+impl FromRef<AppState> for DbPool {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.db_pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<TokenService> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.token_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<EmailVerificationService> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.email_verification_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<PasswordResetService> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.password_reset_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<SessionService> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.session_service.clone()
+    }
+}
+
+impl FromRef<AppState> for TrustedProxies {
+    fn from_ref(app_state: &AppState) -> Self {
+        TrustedProxies(app_state.config.trusted_proxies.as_slice().into())
+    }
+}
+
+impl FromRef<AppState> for HideRegistrationConflicts {
+    fn from_ref(app_state: &AppState) -> Self {
+        HideRegistrationConflicts(app_state.config.hide_registration_conflicts)
+    }
+}
+
+impl FromRef<AppState> for RequireHttps {
+    fn from_ref(app_state: &AppState) -> Self {
+        RequireHttps(app_state.config.require_https)
+    }
+}
+
+impl FromRef<AppState> for HealthCheckToken {
+    fn from_ref(app_state: &AppState) -> Self {
+        HealthCheckToken(app_state.config.health_check_token.clone())
+    }
+}
+
+impl FromRef<AppState> for ResponseEnvelope {
+    fn from_ref(app_state: &AppState) -> Self {
+        ResponseEnvelope(app_state.config.response_envelope)
+    }
+}
+
+impl FromRef<AppState> for Arc<InviteService> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.invite_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<AuditLogger> {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.audit_logger.clone()
+    }
+}
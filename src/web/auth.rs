@@ -0,0 +1,261 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{
+        HeaderValue,
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        request::Parts,
+    },
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::Span;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        token_service::TokenService,
+    },
+    domain::user::Role,
+};
+
+// ============================================================================
+// Authenticated User Extractor
+// ============================================================================
+
+/// The user identified by a valid `Authorization: Bearer <token>` header
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub id: Uuid,
+    pub username: String,
+    pub role: Role,
+}
+
+impl AuthUser {
+    /// Reject the request with `403` unless the user is an admin
+    pub fn require_admin(&self) -> AppResult<()> {
+        if self.role == Role::Admin {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden("Admin role required".into()))
+        }
+    }
+}
+
+/// Rejection from the [`AuthUser`] extractor
+/// Adds `WWW-Authenticate: Bearer` to `401`s so clients know which scheme to use
+#[derive(Debug)]
+pub struct AuthRejection(pub AppError);
+
+impl From<AppError> for AuthRejection {
+    fn from(error: AppError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        let challenge = matches!(self.0, AppError::InvalidCredentials);
+        let mut response = self.0.into_response();
+        if challenge {
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+impl<S> FromRequestParts<S> for AuthUser
+where
+    Arc<TokenService>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(AppError::InvalidCredentials)?;
+
+        let claims = Arc::<TokenService>::from_ref(state).verify_access_token(token)?;
+        // Lands on the `http-request` span, so every log line of the request names the user
+        Span::current().record("user_id", tracing::field::display(claims.sub));
+
+        Ok(AuthUser {
+            id: claims.sub,
+            username: claims.username,
+            role: claims.role,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{email::Email, user::User, username::Username};
+    use crate::web::request_trace::SampledMakeSpan;
+    use axum::{Router, body::Body, http::Request, http::StatusCode, routing::get};
+    use std::sync::Mutex;
+    use time::Duration;
+    use tower::ServiceExt;
+    use tower_http::trace::TraceLayer;
+    use tracing::{
+        Subscriber,
+        field::{Field, Visit},
+        span::{Id, Record},
+    };
+    use tracing_subscriber::{
+        Layer,
+        layer::{Context, SubscriberExt},
+        registry::Registry,
+    };
+
+    async fn admin_only(user: AuthUser) -> AppResult<StatusCode> {
+        user.require_admin()?;
+        Ok(StatusCode::OK)
+    }
+
+    fn setup() -> (Router, Arc<TokenService>) {
+        let token_service = Arc::new(TokenService::new(
+            "secret",
+            "sultan",
+            "sultan",
+            Duration::minutes(15),
+        ));
+        let router = Router::new()
+            .route("/admin", get(admin_only))
+            .with_state(token_service.clone());
+        (router, token_service)
+    }
+
+    fn token_for(token_service: &TokenService, role: Role) -> String {
+        let user = User {
+            id: Uuid::new_v4(),
+            username: Username::parse("testuser").unwrap(),
+            email: Email::parse("testuser@gmail.com").unwrap(),
+            password_hash: "hash".into(),
+            role,
+            email_verified: false,
+            last_login_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: chrono::Utc::now().naive_utc(),
+            deleted_at: None,
+        };
+        token_service.issue_access_token(&user).unwrap()
+    }
+
+    async fn get_admin(router: Router, token: Option<&str>) -> StatusCode {
+        send_admin(router, token).await.status()
+    }
+
+    async fn send_admin(router: Router, token: Option<&str>) -> Response {
+        let mut request = Request::builder().uri("/admin");
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_admin_token_reaches_admin_route() {
+        let (router, token_service) = setup();
+        let token = token_for(&token_service, Role::Admin);
+
+        assert_eq!(get_admin(router, Some(&token)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_user_token_is_forbidden_on_admin_route() {
+        let (router, token_service) = setup();
+        let token = token_for(&token_service, Role::User);
+
+        assert_eq!(get_admin(router, Some(&token)).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_is_unauthorized() {
+        let (router, _) = setup();
+
+        assert_eq!(get_admin(router, None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_response_has_www_authenticate_header() {
+        let (router, _) = setup();
+
+        let missing = send_admin(router.clone(), None).await;
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(missing.headers()[WWW_AUTHENTICATE], "Bearer");
+
+        let invalid = send_admin(router, Some("not-a-jwt")).await;
+        assert_eq!(invalid.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(invalid.headers()[WWW_AUTHENTICATE], "Bearer");
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_response_has_no_www_authenticate_header() {
+        let (router, token_service) = setup();
+        let token = token_for(&token_service, Role::User);
+
+        let response = send_admin(router, Some(&token)).await;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(!response.headers().contains_key(WWW_AUTHENTICATE));
+    }
+
+    #[test]
+    fn test_service_unavailable_rejection_sets_retry_after() {
+        let response =
+            AuthRejection(AppError::ServiceUnavailable("database down".into())).into_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(
+            response
+                .headers()
+                .contains_key(axum::http::header::RETRY_AFTER)
+        );
+    }
+
+    // Collects every `user_id` recorded onto a span after it was opened
+    #[derive(Clone, Default)]
+    struct RecordedUserIds(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for RecordedUserIds {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "user_id" {
+                self.0.lock().unwrap().push(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for RecordedUserIds {
+        fn on_record(&self, _span: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_request_span_carries_user_id() {
+        let recorded = RecordedUserIds::default();
+        let subscriber = Registry::default().with(recorded.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let (router, token_service) = setup();
+        let router =
+            router.layer(TraceLayer::new_for_http().make_span_with(SampledMakeSpan::new(1.0)));
+        let token = token_for(&token_service, Role::User);
+        let user_id = token_service.verify_access_token(&token).unwrap().sub;
+
+        send_admin(router.clone(), None).await;
+        assert!(recorded.0.lock().unwrap().is_empty());
+
+        send_admin(router, Some(&token)).await;
+        assert_eq!(*recorded.0.lock().unwrap(), [user_id.to_string()]);
+    }
+}
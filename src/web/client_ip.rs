@@ -0,0 +1,202 @@
+use axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{HeaderMap, request::Parts},
+};
+use ipnet::IpNet;
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+// ============================================================================
+// Trusted Proxies
+// ============================================================================
+
+/// Networks whose forwarding headers are believed, set by `TRUSTED_PROXIES`
+/// Empty means no proxy is trusted and the socket peer is always the client
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(pub Arc<[IpNet]>);
+
+impl TrustedProxies {
+    fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(ip))
+    }
+
+    /// The client behind `peer`, read from the forwarding headers only when `peer` is trusted
+    /// Hops are walked from the nearest back, so a client can't spoof past our own proxies
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.contains(&peer) {
+            return peer;
+        }
+
+        let hops = forwarded_for(headers);
+        hops.iter()
+            .rev()
+            .find(|hop| !self.contains(hop))
+            // Every hop was one of ours, so the furthest one is as close to the client as we get
+            .or_else(|| hops.first())
+            .copied()
+            .unwrap_or(peer)
+    }
+}
+
+/// Addresses in `X-Forwarded-For`, or failing that `Forwarded`, furthest hop first
+/// A malformed entry ends the list, since nothing before it can be placed reliably
+fn forwarded_for(headers: &HeaderMap) -> Vec<IpAddr> {
+    let x_forwarded_for: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect();
+    let entries = if x_forwarded_for.is_empty() {
+        headers
+            .get_all(axum::http::header::FORWARDED)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(forwarded_for_param)
+            .collect()
+    } else {
+        x_forwarded_for
+    };
+
+    let parsed: Vec<Option<IpAddr>> = entries.into_iter().map(parse_hop).collect();
+    let trailing_valid = parsed.iter().rev().take_while(|hop| hop.is_some()).count();
+    parsed[parsed.len() - trailing_valid..]
+        .iter()
+        .flatten()
+        .copied()
+        .collect()
+}
+
+/// The `for=` parameter of one `Forwarded` element
+fn forwarded_for_param(element: &str) -> Option<&str> {
+    element.split(';').find_map(|pair| {
+        let (name, value) = pair.trim().split_once('=')?;
+        name.eq_ignore_ascii_case("for").then_some(value)
+    })
+}
+
+/// Parse one hop, which may be quoted, bracketed or carry a port as `Forwarded` allows
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    let hop = hop.trim().trim_matches('"');
+    if let Ok(ip) = hop.parse() {
+        return Some(ip);
+    }
+    if let Ok(addr) = hop.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    hop.strip_prefix('[')?.strip_suffix(']')?.parse().ok()
+}
+
+// ============================================================================
+// Client IP Extractor
+// ============================================================================
+
+/// Address of the client that sent the request, seen through any trusted proxies
+/// `None` unless the server was started with connect info, as `server::run` does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    TrustedProxies: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(ConnectInfo(peer)) = parts.extensions.get::<ConnectInfo<SocketAddr>>() else {
+            return Ok(ClientIp(None));
+        };
+
+        let trusted_proxies = TrustedProxies::from_ref(state);
+        Ok(ClientIp(Some(
+            trusted_proxies.client_ip(peer.ip(), &parts.headers),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn proxies(nets: &[&str]) -> TrustedProxies {
+        TrustedProxies(nets.iter().map(|net| net.parse().unwrap()).collect())
+    }
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_untrusted_peer_is_the_client_whatever_it_forwards() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let spoofed = headers("x-forwarded-for", "1.2.3.4");
+
+        assert_eq!(
+            trusted.client_ip(ip("203.0.113.9"), &spoofed),
+            ip("203.0.113.9")
+        );
+        assert_eq!(
+            TrustedProxies::default().client_ip(ip("10.0.0.1"), &spoofed),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_trusted_peer_forwards_nearest_untrusted_hop() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        // The client claimed 1.2.3.4 itself, our proxies appended what they saw
+        let forwarded = headers("x-forwarded-for", "1.2.3.4, 198.51.100.7, 10.0.0.5");
+
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &forwarded),
+            ip("198.51.100.7")
+        );
+    }
+
+    #[test]
+    fn test_trusted_peer_without_header_is_the_client() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &HeaderMap::new()),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_forwarded_header_is_read_when_x_forwarded_for_is_missing() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let forwarded = headers(
+            "forwarded",
+            "for=192.0.2.60;proto=https, for=\"[2001:db8:cafe::17]:4711\"",
+        );
+
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &forwarded),
+            ip("2001:db8:cafe::17")
+        );
+    }
+
+    #[test]
+    fn test_garbage_hop_stops_the_walk() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let forwarded = headers("x-forwarded-for", "198.51.100.7, not-an-ip, 10.0.0.5");
+
+        assert_eq!(
+            trusted.client_ip(ip("10.0.0.1"), &forwarded),
+            ip("10.0.0.5")
+        );
+    }
+}
@@ -1,25 +1,415 @@
 use axum::{
-    http::StatusCode,
+    Json,
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
     response::{IntoResponse, Response},
 };
+use serde::Serialize;
 
-use crate::application::app_error::AppError;
+use crate::application::app_error::{AppError, FieldError};
+
+/// Seconds clients should wait before retrying a `503`
+pub(crate) const RETRY_AFTER_SECS: &str = "5";
+
+// ============================================================================
+// Error Body
+// ============================================================================
+
+/// Body of every error response
+/// Also stored as a response extension so the body can be re-rendered for the client
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBody {
+    /// Stable, machine-readable name of the error, unlike `error` which may be reworded
+    pub code: &'static str,
+    pub error: String,
+    /// Per-field problems, only present for request bodies that failed `Validate`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// Render an error body as JSON with `status`, keeping a copy for content negotiation
+pub(crate) fn error_response(status: StatusCode, body: ErrorBody) -> Response {
+    let mut response = (status, Json(&body)).into_response();
+    response.extensions_mut().insert(body);
+    response
+}
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         tracing::error!(error = ?self, "Request failed");
 
-        match self {
-            AppError::Database(_) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        let mut fields = Vec::new();
+        let (status, code, message) = match self {
+            AppError::Database(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "database_error",
+                "Database error".into(),
+            ),
+            AppError::Validation(msg) => (StatusCode::BAD_REQUEST, "validation_error", msg),
+            AppError::InvalidFields(invalid) => {
+                fields = invalid;
+                (
+                    StatusCode::BAD_REQUEST,
+                    "validation_failed",
+                    "Validation failed".into(),
+                )
+            }
+            AppError::InvalidCredentials => (
+                StatusCode::UNAUTHORIZED,
+                "invalid_credentials",
+                "Invalid credentials".into(),
+            ),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "forbidden", msg),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "not_found", msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "conflict", msg),
+            AppError::Unprocessable(msg) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "unprocessable", msg)
+            }
+            AppError::MethodNotAllowed => (
+                StatusCode::METHOD_NOT_ALLOWED,
+                "method_not_allowed",
+                "method not allowed".into(),
+            ),
+            AppError::UpgradeRequired(msg) => {
+                (StatusCode::UPGRADE_REQUIRED, "upgrade_required", msg)
             }
-            AppError::InvalidCredentials => {
-                (StatusCode::UNAUTHORIZED, "Invalid credentials").into_response()
+            AppError::TooManyRequests(msg) => {
+                (StatusCode::TOO_MANY_REQUESTS, "too_many_requests", msg)
             }
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg).into_response(),
-            AppError::Internal(_) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+            AppError::ServiceUnavailable(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "service_unavailable",
+                "Service unavailable".into(),
+            ),
+            AppError::Internal(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal error".into(),
+            ),
+        };
+
+        let mut response = error_response(
+            status,
+            ErrorBody {
+                code,
+                error: message,
+                fields,
+            },
+        );
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from_static(RETRY_AFTER_SECS),
+            );
+        }
+        response
+    }
+}
+
+/// Fallback for paths that match no route
+pub async fn route_not_found() -> AppError {
+    AppError::NotFound("route not found".into())
+}
+
+/// Fallback for known paths requested with an unsupported method
+/// Axum fills in the `Allow` header listing the methods the path accepts
+pub async fn method_not_allowed() -> AppError {
+    AppError::MethodNotAllowed
+}
+
+// ============================================================================
+// Content Negotiation
+// ============================================================================
+
+/// Whether the `Accept` header prefers plain text over JSON
+/// The first of `text/plain` or `application/json` listed wins, JSON is the default
+fn prefers_plain_text(accept: Option<&HeaderValue>) -> bool {
+    let Some(accept) = accept.and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+
+    accept
+        .split(',')
+        .map(|media| media.split(';').next().unwrap_or_default().trim())
+        .find(|media| matches!(*media, "text/plain" | "application/json"))
+        == Some("text/plain")
+}
+
+/// Middleware that renders error responses as plain text when the client asks for it
+pub async fn negotiate_error_format(request: Request, next: Next) -> Response {
+    let plain_text = prefers_plain_text(request.headers().get(header::ACCEPT));
+
+    let response = next.run(request).await;
+    if !plain_text {
+        return response;
+    }
+
+    let Some(body) = response.extensions().get::<ErrorBody>().cloned() else {
+        return response;
+    };
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body.error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::to_bytes, middleware, routing::get};
+    use tower::ServiceExt;
+
+    async fn forbidden() -> Result<(), AppError> {
+        Err(AppError::Forbidden("Forbidden".into()))
+    }
+
+    async fn request_with_accept(accept: Option<&str>) -> (StatusCode, String, String) {
+        let router = Router::new()
+            .route("/", get(forbidden))
+            .layer(middleware::from_fn(negotiate_error_format));
+
+        let mut request = Request::builder().uri("/");
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        let response = router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let content_type = response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_error_as_json() {
+        let (status, content_type, body) = request_with_accept(Some("application/json")).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body, r#"{"code":"forbidden","error":"Forbidden"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_error_as_plain_text() {
+        let (status, content_type, body) = request_with_accept(Some("text/plain")).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(content_type.starts_with("text/plain"));
+        assert_eq!(body, "Forbidden");
+    }
+
+    #[tokio::test]
+    async fn test_error_defaults_to_json() {
+        let (_, content_type, _) = request_with_accept(None).await;
+        assert_eq!(content_type, "application/json");
+
+        let (_, content_type, _) = request_with_accept(Some("*/*")).await;
+        assert_eq!(content_type, "application/json");
+    }
+
+    /// A message no generic response may leak
+    const DETAIL: &str = "internal detail";
+
+    /// Fails to compile when a variant is added, so it has to get a row in the matrix below
+    fn matrix_row(error: &AppError) -> usize {
+        match error {
+            AppError::Database(_) => 0,
+            AppError::Validation(_) => 1,
+            AppError::InvalidFields(_) => 2,
+            AppError::InvalidCredentials => 3,
+            AppError::Forbidden(_) => 4,
+            AppError::NotFound(_) => 5,
+            AppError::Conflict(_) => 6,
+            AppError::Unprocessable(_) => 7,
+            AppError::MethodNotAllowed => 8,
+            AppError::UpgradeRequired(_) => 9,
+            AppError::TooManyRequests(_) => 10,
+            AppError::ServiceUnavailable(_) => 11,
+            AppError::Internal(_) => 12,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_variant_maps_to_its_status_and_message() {
+        // Each variant with its status, its stable `code` and the `error` clients see,
+        // `DETAIL` where the message is passed through and a fixed text where it is kept back
+        let matrix = [
+            (
+                AppError::Database(sqlx::Error::Protocol(DETAIL.into())),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "database_error",
+                "Database error",
+            ),
+            (
+                AppError::Validation(DETAIL.into()),
+                StatusCode::BAD_REQUEST,
+                "validation_error",
+                DETAIL,
+            ),
+            (
+                AppError::validation("email", DETAIL),
+                StatusCode::BAD_REQUEST,
+                "validation_failed",
+                "Validation failed",
+            ),
+            (
+                AppError::InvalidCredentials,
+                StatusCode::UNAUTHORIZED,
+                "invalid_credentials",
+                "Invalid credentials",
+            ),
+            (
+                AppError::Forbidden(DETAIL.into()),
+                StatusCode::FORBIDDEN,
+                "forbidden",
+                DETAIL,
+            ),
+            (
+                AppError::NotFound(DETAIL.into()),
+                StatusCode::NOT_FOUND,
+                "not_found",
+                DETAIL,
+            ),
+            (
+                AppError::Conflict(DETAIL.into()),
+                StatusCode::CONFLICT,
+                "conflict",
+                DETAIL,
+            ),
+            (
+                AppError::Unprocessable(DETAIL.into()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "unprocessable",
+                DETAIL,
+            ),
+            (
+                AppError::MethodNotAllowed,
+                StatusCode::METHOD_NOT_ALLOWED,
+                "method_not_allowed",
+                "method not allowed",
+            ),
+            (
+                AppError::UpgradeRequired(DETAIL.into()),
+                StatusCode::UPGRADE_REQUIRED,
+                "upgrade_required",
+                DETAIL,
+            ),
+            (
+                AppError::TooManyRequests(DETAIL.into()),
+                StatusCode::TOO_MANY_REQUESTS,
+                "too_many_requests",
+                DETAIL,
+            ),
+            (
+                AppError::ServiceUnavailable(DETAIL.into()),
+                StatusCode::SERVICE_UNAVAILABLE,
+                "service_unavailable",
+                "Service unavailable",
+            ),
+            (
+                AppError::Internal(DETAIL.into()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal error",
+            ),
+        ];
+
+        for (row, (error, status, code, message)) in matrix.into_iter().enumerate() {
+            assert_eq!(
+                matrix_row(&error),
+                row,
+                "matrix is out of order at {:?}",
+                error
+            );
+            let has_fields = matches!(error, AppError::InvalidFields(_));
+            let response = error.into_response();
+
+            assert_eq!(response.status(), status, "row {}", row);
+            let body = body_json(response).await;
+            assert_eq!(body["code"], code, "row {}", row);
+            assert_eq!(body["error"], message, "row {}", row);
+            let mut keys: Vec<&String> = body.as_object().unwrap().keys().collect();
+            keys.sort();
+            if has_fields {
+                assert_eq!(keys, ["code", "error", "fields"], "row {}", row);
+                assert_eq!(body["fields"][0]["message"], DETAIL);
+            } else {
+                assert_eq!(keys, ["code", "error"], "row {}", row);
             }
         }
     }
+
+    #[test]
+    fn test_service_unavailable_sets_retry_after() {
+        let response = AppError::ServiceUnavailable("pool exhausted".into()).into_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], RETRY_AFTER_SECS);
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_single_field_validation_response() {
+        let response = AppError::validation("email", "Email is malformed").into_response();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body_json(response).await,
+            serde_json::json!({
+                "code": "validation_failed",
+                "error": "Validation failed",
+                "fields": [{ "field": "email", "message": "Email is malformed" }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multi_field_validation_response() {
+        let response = AppError::validations(vec![
+            FieldError {
+                field: "username",
+                message: "Username is too short".into(),
+            },
+            FieldError {
+                field: "password",
+                message: "Password is too short".into(),
+            },
+        ])
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = body_json(response).await;
+        assert_eq!(body["error"], "Validation failed");
+        assert_eq!(body["fields"][0]["field"], "username");
+        assert_eq!(body["fields"][1]["field"], "password");
+    }
+
+    #[test]
+    fn test_first_listed_format_wins() {
+        let accept = HeaderValue::from_static("application/json, text/plain;q=0.5");
+        assert!(!prefers_plain_text(Some(&accept)));
+
+        let accept = HeaderValue::from_static("text/plain, application/json");
+        assert!(prefers_plain_text(Some(&accept)));
+    }
 }
@@ -0,0 +1,155 @@
+use axum::{
+    Json, Router,
+    extract::{FromRef, FromRequestParts, State},
+    http::{HeaderValue, StatusCode, header, request::Parts},
+    response::IntoResponse,
+    routing::get,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{instrument, warn};
+
+use crate::{
+    application::{
+        app_error::{AppError, AppResult},
+        metrics::Metrics,
+    },
+    crypto::token::tokens_match,
+    persistence::{DbPool, pending_migrations},
+    web::{app_state::AppState, auth::AuthRejection, error_response::RETRY_AFTER_SECS},
+};
+
+/// Version 0.0.4 of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+// ============================================================================
+// Health Check Auth
+// ============================================================================
+
+/// Bearer token the health and metrics endpoints require, set by `HEALTH_CHECK_TOKEN`
+#[derive(Debug, Clone, Default)]
+pub struct HealthCheckToken(pub Option<String>);
+
+/// Proof that the caller may see health and metrics
+/// Rejects with 401 when `HealthCheckToken` is set and the request doesn't carry it
+#[derive(Debug, Clone, Copy)]
+pub struct HealthCheckAuth;
+
+impl<S> FromRequestParts<S> for HealthCheckAuth
+where
+    HealthCheckToken: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let HealthCheckToken(Some(expected)) = HealthCheckToken::from_ref(state) else {
+            return Ok(HealthCheckAuth);
+        };
+        let presented = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if !tokens_match(&expected, presented) {
+            return Err(AppError::InvalidCredentials.into());
+        }
+        Ok(HealthCheckAuth)
+    }
+}
+
+// ============================================================================
+// DTOs (Request/Response models)
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    database: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MigrationStatusResponse {
+    up_to_date: bool,
+    pending: Vec<i64>,
+}
+
+// ============================================================================
+// HTTP Handlers
+// ============================================================================
+
+/// Report that the server is up and which database backend it's using
+#[instrument(skip(db_pool))]
+async fn health(_: HealthCheckAuth, State(db_pool): State<DbPool>) -> impl IntoResponse {
+    Json(HealthResponse {
+        status: "ok",
+        database: db_pool.backend(),
+    })
+}
+
+/// Report whether every bundled migration has been applied
+/// Either an unreachable database or pending migrations make the server not ready
+#[instrument(skip(db_pool))]
+async fn migrations(
+    _: HealthCheckAuth,
+    State(db_pool): State<DbPool>,
+) -> AppResult<impl IntoResponse> {
+    let pending = async {
+        db_pool.ping().await?;
+        pending_migrations(&db_pool).await
+    }
+    .await
+    .map_err(|e| match e {
+        AppError::ServiceUnavailable(_) => e,
+        other => {
+            warn!(error = ?other, "Readiness check could not reach the database");
+            AppError::ServiceUnavailable("Database is unreachable".into())
+        }
+    })?;
+
+    let body = Json(MigrationStatusResponse {
+        up_to_date: pending.is_empty(),
+        pending: pending.clone(),
+    });
+    if pending.is_empty() {
+        return Ok((StatusCode::OK, body).into_response());
+    }
+
+    warn!(?pending, "Database schema is behind bundled migrations");
+    Ok((
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(
+            header::RETRY_AFTER,
+            HeaderValue::from_static(RETRY_AFTER_SECS),
+        )],
+        body,
+    )
+        .into_response())
+}
+
+// ============================================================================
+// Router
+// ============================================================================
+
+/// Serve every registered gauge and counter in the Prometheus text format
+async fn metrics(_: HealthCheckAuth, State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE),
+        )],
+        metrics.render(),
+    )
+}
+
+pub fn health_router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(health))
+        .route("/migrations", get(migrations))
+}
+
+/// Mounted at the root, where scrapers look for `/metrics` by default
+pub fn metrics_router() -> Router<AppState> {
+    Router::new().route("/metrics", get(metrics))
+}
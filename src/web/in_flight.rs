@@ -0,0 +1,150 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::Notify;
+
+// ============================================================================
+// In-Flight Requests
+// ============================================================================
+
+/// Counts requests currently being handled, so shutdown can wait for them to finish
+#[derive(Clone, Default)]
+pub struct InFlight {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+impl InFlight {
+    /// Number of requests being handled right now
+    pub fn count(&self) -> usize {
+        self.inner.count.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no request is in flight, or `grace` has elapsed
+    /// Returns how many requests were still in flight at the deadline, zero once drained
+    pub async fn drain(&self, grace: Duration) -> usize {
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            // Register for the wake-up before reading the count, so a finish in between isn't missed
+            let idle = self.inner.idle.notified();
+            let count = self.count();
+            if count == 0 {
+                return 0;
+            }
+            if tokio::time::timeout_at(deadline, idle).await.is_err() {
+                return self.count();
+            }
+        }
+    }
+
+    fn start(&self) -> InFlightGuard {
+        self.inner.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.inner.clone())
+    }
+}
+
+/// Decrements the count when dropped, even if the request future is cancelled
+struct InFlightGuard(Arc<Inner>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Middleware that counts the request as in flight until its response is ready
+pub async fn track_in_flight(
+    State(in_flight): State<InFlight>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let _guard = in_flight.start();
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use tower::ServiceExt;
+
+    const SLOW_REQUEST: Duration = Duration::from_millis(200);
+
+    fn slow_router(in_flight: &InFlight) -> Router {
+        Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(SLOW_REQUEST).await;
+                    "done"
+                }),
+            )
+            .layer(middleware::from_fn_with_state(
+                in_flight.clone(),
+                track_in_flight,
+            ))
+    }
+
+    /// Start a slow request in the background and wait until it is being handled
+    async fn start_slow_request(in_flight: &InFlight) -> tokio::task::JoinHandle<Response> {
+        let router = slow_router(in_flight);
+        let request = tokio::spawn(async move {
+            router
+                .oneshot(Request::builder().uri("/slow").body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+        });
+        while in_flight.count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_slow_request() {
+        let in_flight = InFlight::default();
+        let request = start_slow_request(&in_flight).await;
+
+        let started = std::time::Instant::now();
+        let remaining = in_flight.drain(Duration::from_secs(5)).await;
+
+        assert_eq!(remaining, 0);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(request.await.unwrap().status().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_drain_gives_up_at_deadline() {
+        let in_flight = InFlight::default();
+        let request = start_slow_request(&in_flight).await;
+
+        let remaining = in_flight.drain(Duration::from_millis(10)).await;
+
+        assert_eq!(remaining, 1);
+        request.await.unwrap();
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_returns_immediately_when_idle() {
+        let remaining = InFlight::default().drain(Duration::ZERO).await;
+
+        assert_eq!(remaining, 0);
+    }
+}
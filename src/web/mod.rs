@@ -1,6 +1,28 @@
+pub mod admin_routes;
 pub mod app_state;
+pub mod auth;
+pub mod client_ip;
 pub mod error_response;
+pub mod health_routes;
+pub mod in_flight;
+pub mod pagination;
+pub mod request_trace;
+pub mod response;
+pub mod rfc3339;
+pub mod secure_transport;
 pub mod user_routes;
+pub mod validation;
 
+pub use admin_routes::{admin_router, require_current_admin};
 pub use app_state::AppState;
+pub use auth::{AuthRejection, AuthUser};
+pub use client_ip::{ClientIp, TrustedProxies};
+pub use error_response::{method_not_allowed, negotiate_error_format, route_not_found};
+pub use health_routes::{health_router, metrics_router};
+pub use in_flight::{InFlight, track_in_flight};
+pub use pagination::Pagination;
+pub use request_trace::{SampledMakeSpan, SampledOnResponse, record_request_line};
+pub use response::{ApiResponse, ResponseEnvelope};
+pub use secure_transport::{RequireHttps, SecureTransport};
 pub use user_routes::user_router;
+pub use validation::{FieldError, Validate, ValidatedJson, ValidatedJsonOrForm};
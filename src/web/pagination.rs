@@ -0,0 +1,114 @@
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::Deserialize;
+
+use crate::application::app_error::AppError;
+
+/// Page size when the client doesn't ask for one
+pub const DEFAULT_LIMIT: u32 = 20;
+
+/// Largest page a client may request
+pub const MAX_LIMIT: u32 = 100;
+
+/// Response header carrying the number of items across every page
+pub const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+// ============================================================================
+// Pagination Extractor
+// ============================================================================
+
+/// `?limit=&offset=` query parameters, defaulted and clamped
+/// Extract it directly in a handler, alongside any other `Query` of the same request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Pagination {
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+        }
+    }
+}
+
+impl Pagination {
+    /// Keep `limit` within `1..=MAX_LIMIT`
+    pub fn clamped(self) -> Self {
+        Self {
+            limit: self.limit.clamp(1, MAX_LIMIT),
+            offset: self.offset,
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for Pagination
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(pagination) = Query::<Pagination>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| AppError::Validation(rejection.body_text()))?;
+
+        Ok(pagination.clamped())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn extract(uri: &str) -> Result<Pagination, AppError> {
+        let (mut parts, _) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+        Pagination::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_defaults_when_absent() {
+        let pagination = extract("/users").await.unwrap();
+
+        assert_eq!(pagination, Pagination::default());
+    }
+
+    #[tokio::test]
+    async fn test_reads_limit_and_offset() {
+        let pagination = extract("/users?limit=5&offset=10").await.unwrap();
+
+        assert_eq!(
+            pagination,
+            Pagination {
+                limit: 5,
+                offset: 10
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clamps_limit() {
+        assert_eq!(extract("/users?limit=0").await.unwrap().limit, 1);
+        assert_eq!(extract("/users?limit=5000").await.unwrap().limit, MAX_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_numeric_limit() {
+        let result = extract("/users?limit=ten").await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_negative_offset() {
+        let result = extract("/users?offset=-1").await;
+
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
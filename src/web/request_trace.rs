@@ -0,0 +1,257 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::{
+    extract::Request,
+    http::{Method, Uri},
+    middleware::Next,
+    response::Response,
+};
+use std::{sync::Arc, time::Duration};
+use tower_http::{
+    request_id::RequestId,
+    trace::{DefaultOnResponse, MakeSpan, OnResponse},
+};
+use tracing::{Level, Span};
+use uuid::Uuid;
+
+// ============================================================================
+// Request Line
+// ============================================================================
+
+/// Method and URI of the request a response answers
+/// Lets unsampled failures be logged with the request they came from
+#[derive(Debug, Clone)]
+pub struct RequestLine {
+    pub method: Method,
+    pub uri: Uri,
+}
+
+/// Middleware that copies the request line onto the response
+/// Must sit inside the `TraceLayer` so `SampledOnResponse` can read it
+pub async fn record_request_line(request: Request, next: Next) -> Response {
+    let line = RequestLine {
+        method: request.method().clone(),
+        uri: request.uri().clone(),
+    };
+
+    let mut response = next.run(request).await;
+    response.extensions_mut().insert(line);
+    response
+}
+
+// ============================================================================
+// Sampled Spans
+// ============================================================================
+
+/// Opens an `info` span for a `sample_rate` fraction of requests and a `debug` span for the rest
+/// Requests under a quiet path always get a `debug` span
+#[derive(Debug, Clone)]
+pub struct SampledMakeSpan {
+    sample_rate: f64,
+    quiet_paths: Arc<[String]>,
+}
+
+impl SampledMakeSpan {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            sample_rate,
+            quiet_paths: Arc::from([]),
+        }
+    }
+
+    /// Never sample requests to these paths or anything nested under them
+    /// Meant for liveness probes and scrapers that would otherwise flood the logs
+    pub fn with_quiet_paths(mut self, paths: Vec<String>) -> Self {
+        self.quiet_paths = paths.into();
+        self
+    }
+
+    fn is_quiet(&self, path: &str) -> bool {
+        self.quiet_paths.iter().any(|quiet| {
+            path.strip_prefix(quiet.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    fn sampled(&self) -> bool {
+        if self.sample_rate >= 1.0 {
+            return true;
+        }
+        // Top 53 bits give a uniform f64 in [0, 1)
+        let roll = (OsRng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        roll < self.sample_rate
+    }
+}
+
+impl<B> MakeSpan<B> for SampledMakeSpan {
+    fn make_span(&mut self, request: &axum::http::Request<B>) -> Span {
+        // Set by `SetRequestIdLayer`, either taken from the request or freshly minted
+        let request_id = request
+            .extensions()
+            .get::<RequestId>()
+            .and_then(|id| id.header_value().to_str().ok())
+            .map_or_else(|| Uuid::new_v4().to_string(), str::to_owned);
+
+        if !self.is_quiet(request.uri().path()) && self.sampled() {
+            tracing::info_span!(
+                "http-request",
+                method = %request.method(),
+                uri = %request.uri(),
+                version = ?request.version(),
+                request_id = %request_id,
+                // Filled in by `AuthUser` once the request is authenticated
+                user_id = tracing::field::Empty
+            )
+        } else {
+            tracing::debug_span!(
+                "http-request",
+                method = %request.method(),
+                uri = %request.uri(),
+                version = ?request.version(),
+                request_id = %request_id,
+                // Filled in by `AuthUser` once the request is authenticated
+                user_id = tracing::field::Empty
+            )
+        }
+    }
+}
+
+/// Logs responses like the default, and always logs error responses of unsampled requests
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SampledOnResponse;
+
+impl<B> OnResponse<B> for SampledOnResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        let sampled = span
+            .metadata()
+            .is_some_and(|metadata| *metadata.level() == Level::INFO);
+        let status = response.status();
+
+        if sampled || !(status.is_client_error() || status.is_server_error()) {
+            DefaultOnResponse::default().on_response(response, latency, span);
+            return;
+        }
+
+        let line = response.extensions().get::<RequestLine>();
+        let failure_span = tracing::info_span!(
+            "http-request",
+            method = line.map(|line| tracing::field::display(&line.method)),
+            uri = line.map(|line| tracing::field::display(&line.uri)),
+            status = status.as_u16(),
+            latency_ms = latency.as_millis() as u64
+        );
+        failure_span.in_scope(|| tracing::info!("Request failed"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::app_error::AppError;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use std::sync::{Arc, Mutex};
+    use tower::ServiceExt;
+    use tower_http::trace::TraceLayer;
+    use tracing::{
+        Subscriber,
+        span::{Attributes, Id},
+    };
+    use tracing_subscriber::{
+        Layer,
+        layer::{Context, SubscriberExt},
+        registry::Registry,
+    };
+
+    // Collects the level of every `http-request` span opened
+    #[derive(Clone, Default)]
+    struct RecordedSpans(Arc<Mutex<Vec<Level>>>);
+
+    impl<S: Subscriber> Layer<S> for RecordedSpans {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            if attrs.metadata().name() == "http-request" {
+                self.0.lock().unwrap().push(*attrs.metadata().level());
+            }
+        }
+    }
+
+    async fn ok() -> &'static str {
+        "ok"
+    }
+
+    async fn fail() -> Result<(), AppError> {
+        Err(AppError::Internal("boom".into()))
+    }
+
+    async fn info_spans_for(uri: &str, sample_rate: f64) -> usize {
+        info_spans_with(SampledMakeSpan::new(sample_rate), uri).await
+    }
+
+    async fn info_spans_with(make_span: SampledMakeSpan, uri: &str) -> usize {
+        let recorded = RecordedSpans::default();
+        let subscriber = Registry::default().with(recorded.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let router = Router::new()
+            .route("/ok", get(ok))
+            .route("/fail", get(fail))
+            .route("/health", get(ok))
+            .route("/health/fail", get(fail))
+            .layer(middleware::from_fn(record_request_line))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(make_span)
+                    .on_response(SampledOnResponse),
+            );
+        router
+            .oneshot(
+                axum::http::Request::builder()
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let levels = recorded.0.lock().unwrap();
+        levels.iter().filter(|level| **level == Level::INFO).count()
+    }
+
+    #[tokio::test]
+    async fn test_unsampled_success_has_no_info_span() {
+        assert_eq!(info_spans_for("/ok", 0.0).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unsampled_failure_still_has_info_span() {
+        assert_eq!(info_spans_for("/fail", 0.0).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_full_sampling_opens_info_span_per_request() {
+        assert_eq!(info_spans_for("/ok", 1.0).await, 1);
+        assert_eq!(info_spans_for("/fail", 1.0).await, 1);
+    }
+
+    fn quiet_health() -> SampledMakeSpan {
+        SampledMakeSpan::new(1.0).with_quiet_paths(vec!["/health".into()])
+    }
+
+    #[tokio::test]
+    async fn test_quiet_path_has_no_info_span() {
+        assert_eq!(info_spans_with(quiet_health(), "/health").await, 0);
+        assert_eq!(info_spans_with(quiet_health(), "/ok").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_quiet_path_failure_still_has_info_span() {
+        assert_eq!(info_spans_with(quiet_health(), "/health/fail").await, 1);
+    }
+
+    #[test]
+    fn test_quiet_path_matches_whole_segments() {
+        let make_span = quiet_health();
+
+        assert!(make_span.is_quiet("/health"));
+        assert!(make_span.is_quiet("/health/migrations"));
+        assert!(!make_span.is_quiet("/healthz"));
+    }
+}
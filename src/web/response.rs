@@ -0,0 +1,75 @@
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+// ============================================================================
+// Success Envelope
+// ============================================================================
+
+/// Whether success bodies are wrapped as `{"data": ...}`, set by `RESPONSE_ENVELOPE`
+/// Extract it with `State` in handlers that answer with `ApiResponse`, which every API
+/// success body goes through. Health and metrics stay bare for probes and scrapers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResponseEnvelope(pub bool);
+
+#[derive(Serialize)]
+struct Enveloped<T> {
+    data: T,
+}
+
+/// JSON success body, sent as `{"data": <payload>}` when the envelope is enabled
+/// and as the bare payload otherwise
+pub struct ApiResponse<T> {
+    envelope: ResponseEnvelope,
+    payload: T,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn new(envelope: ResponseEnvelope, payload: T) -> Self {
+        Self { envelope, payload }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        let ResponseEnvelope(enabled) = self.envelope;
+        if enabled {
+            Json(Enveloped { data: self.payload }).into_response()
+        } else {
+            Json(self.payload).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    async fn body_of(response: ApiResponse<serde_json::Value>) -> serde_json::Value {
+        let body = to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_payload_is_wrapped_in_data_when_enabled() {
+        let payload = serde_json::json!({ "username": "alice" });
+
+        let body = body_of(ApiResponse::new(ResponseEnvelope(true), payload.clone())).await;
+
+        assert_eq!(body, serde_json::json!({ "data": payload }));
+    }
+
+    #[tokio::test]
+    async fn test_payload_is_sent_bare_when_disabled() {
+        let payload = serde_json::json!([1, 2, 3]);
+
+        let body = body_of(ApiResponse::new(ResponseEnvelope(false), payload.clone())).await;
+
+        assert_eq!(body, payload);
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{NaiveDateTime, SecondsFormat};
+use serde::Serializer;
+
+// ============================================================================
+// RFC 3339 Timestamps
+// ============================================================================
+
+/// Serialize a UTC timestamp as RFC 3339 with millisecond precision and a `Z` suffix,
+/// which `Date.parse` reads as UTC rather than local time. Use with `#[serde(serialize_with)]`
+pub fn serialize<S: Serializer>(value: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(value))
+}
+
+/// `serialize` for optional timestamps, `None` stays `null`
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&format(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+fn format(value: &NaiveDateTime) -> String {
+    value.and_utc().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Stamped {
+        #[serde(serialize_with = "serialize")]
+        at: NaiveDateTime,
+        #[serde(serialize_with = "option::serialize")]
+        maybe: Option<NaiveDateTime>,
+    }
+
+    #[test]
+    fn test_timestamps_serialize_as_rfc3339_utc() {
+        let at =
+            NaiveDateTime::parse_from_str("2026-10-16 09:05:03.123456", "%Y-%m-%d %H:%M:%S%.f")
+                .unwrap();
+
+        let body = serde_json::to_value(Stamped {
+            at,
+            maybe: Some(at),
+        })
+        .unwrap();
+
+        assert_eq!(body["at"], "2026-10-16T09:05:03.123Z");
+        assert_eq!(body["maybe"], "2026-10-16T09:05:03.123Z");
+
+        let body = serde_json::to_value(Stamped { at, maybe: None }).unwrap();
+        assert_eq!(body["maybe"], serde_json::Value::Null);
+    }
+}
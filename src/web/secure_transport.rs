@@ -0,0 +1,74 @@
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{HeaderMap, request::Parts},
+};
+
+use crate::application::app_error::AppError;
+
+// ============================================================================
+// Secure Transport
+// ============================================================================
+
+/// Whether auth endpoints refuse requests that reached the proxy over plain HTTP,
+/// set by `REQUIRE_HTTPS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RequireHttps(pub bool);
+
+/// Proof that the request may carry credentials or receive tokens
+/// Rejects with 426 when `RequireHttps` is on and the client didn't use HTTPS
+#[derive(Debug, Clone, Copy)]
+pub struct SecureTransport;
+
+/// Whether the proxy nearest to us saw the client connect over HTTPS
+/// TLS is terminated in front of the app, so a request without the header arrived in plaintext.
+/// A client that fakes the header only exposes its own credentials, so any peer is believed
+fn forwarded_over_https(headers: &HeaderMap) -> bool {
+    headers
+        .get_all("x-forwarded-proto")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .next_back()
+        .is_some_and(|proto| proto.trim().eq_ignore_ascii_case("https"))
+}
+
+impl<S> FromRequestParts<S> for SecureTransport
+where
+    RequireHttps: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let RequireHttps(required) = RequireHttps::from_ref(state);
+        if required && !forwarded_over_https(&parts.headers) {
+            return Err(AppError::UpgradeRequired(
+                "This endpoint is only available over HTTPS".into(),
+            ));
+        }
+        Ok(SecureTransport)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-proto", HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_nearest_forwarded_proto_decides() {
+        assert!(forwarded_over_https(&headers(&["https"])));
+        assert!(forwarded_over_https(&headers(&["http, HTTPS"])));
+        assert!(!forwarded_over_https(&headers(&["http"])));
+        assert!(!forwarded_over_https(&headers(&["https", "http"])));
+        assert!(!forwarded_over_https(&HeaderMap::new()));
+    }
+}
@@ -1,12 +1,44 @@
-use axum::{Json, Router, extract::State, http::StatusCode, response::IntoResponse, routing::post};
-use secrecy::SecretString;
+use axum::{
+    Router,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+};
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{info, instrument};
+use uuid::Uuid;
 
 use crate::{
-    application::{app_error::AppResult, user_service::UserService},
-    web::app_state::AppState,
+    application::{
+        app_error::{AppError, AppResult},
+        audit_logger::AuditLogger,
+        email_verification_service::EmailVerificationService,
+        password_reset_service::PasswordResetService,
+        session_service::SessionService,
+        user_service::UserService,
+    },
+    crypto::token::TOKEN_LENGTH,
+    domain::{
+        audit::AuditAction,
+        email::{EMAIL_MAX_LENGTH, Email},
+        password::validate_password_strength,
+        refresh_token::RefreshToken,
+        user::{Role, User},
+        username::{LEGACY_USERNAME_MAX_LENGTH, Username},
+    },
+    web::{
+        app_state::AppState,
+        auth::AuthUser,
+        client_ip::ClientIp,
+        pagination::{Pagination, TOTAL_COUNT_HEADER},
+        response::{ApiResponse, ResponseEnvelope},
+        rfc3339,
+        secure_transport::SecureTransport,
+        validation::{FieldError, Validate, ValidatedJson, ValidatedJsonOrForm, check_field},
+    },
 };
 
 // ============================================================================
@@ -18,32 +50,479 @@ struct RegisterRequest {
     username: String,
     email: String,
     password: SecretString,
+    /// Only needed while registration is invite-only
+    invite_code: Option<String>,
+}
+
+impl Validate for RegisterRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check_field(&mut errors, "username", Username::parse(&self.username));
+        check_field(&mut errors, "email", Email::parse(&self.email));
+        check_field(
+            &mut errors,
+            "password",
+            validate_password_strength(self.password.expose_secret()),
+        );
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
+/// Whether a duplicate registration is answered like a successful one, set by
+/// `HIDE_REGISTRATION_CONFLICTS`. Nothing is created either way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HideRegistrationConflicts(pub bool);
+
 #[derive(Debug, Clone, Serialize)]
 struct RegisterResponse {
     success: bool,
 }
 
+/// `username` holds an email instead when `LOGIN_IDENTIFIER` allows it
+#[derive(Debug, Clone, Deserialize)]
+struct LoginRequest {
+    #[serde(alias = "email")]
+    username: String,
+    password: SecretString,
+}
+
+// Only bounds are checked so usernames that predate the current rules can still log in
+// The password's size is checked by the service against MAX_PASSWORD_BYTES
+impl Validate for LoginRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        // Usernames can't contain `@`, so anything with one can only be an email
+        let (max_length, name) = if self.username.contains('@') {
+            (EMAIL_MAX_LENGTH, "Email")
+        } else {
+            (LEGACY_USERNAME_MAX_LENGTH, "Username")
+        };
+        if self.username.chars().count() > max_length {
+            errors.push(FieldError {
+                field: "username",
+                message: format!("{} must be at most {} characters", name, max_length),
+            });
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct LoginResponse {
+    access_token: String,
+    refresh_token: String,
+    token_type: &'static str,
+    user: UserResponse,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+impl Validate for RefreshRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        // Anything longer than a generated token can't match, so skip the lookup
+        if self.refresh_token.len() > TOKEN_LENGTH {
+            return Err(vec![FieldError {
+                field: "refresh_token",
+                message: "Refresh token is malformed".into(),
+            }]);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RefreshResponse {
+    access_token: String,
+    refresh_token: String,
+    token_type: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct UserResponse {
+    id: Uuid,
+    username: String,
+    email: String,
+    role: Role,
+    email_verified: bool,
+    #[serde(serialize_with = "rfc3339::serialize")]
+    created_at: chrono::NaiveDateTime,
+    #[serde(serialize_with = "rfc3339::option::serialize")]
+    last_login_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username.to_string(),
+            email: user.email.to_string(),
+            role: user.role,
+            email_verified: user.email_verified,
+            created_at: user.created_at,
+            last_login_at: user.last_login_at,
+        }
+    }
+}
+
+/// Metadata of a live refresh token, never the token itself
+#[derive(Debug, Clone, Serialize)]
+struct SessionResponse {
+    id: Uuid,
+    user_agent: Option<String>,
+    #[serde(serialize_with = "rfc3339::serialize")]
+    created_at: chrono::NaiveDateTime,
+    #[serde(serialize_with = "rfc3339::option::serialize")]
+    last_used_at: Option<chrono::NaiveDateTime>,
+    #[serde(serialize_with = "rfc3339::serialize")]
+    expires_at: chrono::NaiveDateTime,
+}
+
+impl From<RefreshToken> for SessionResponse {
+    fn from(token: RefreshToken) -> Self {
+        Self {
+            id: token.id,
+            user_agent: token.user_agent,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct VerifyEmailQuery {
+    token: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct VerifyEmailResponse {
+    success: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PasswordResetRequest {
+    email: String,
+}
+
+// Malformed addresses are answered like unknown ones, so only the bound is checked
+impl Validate for PasswordResetRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        if self.email.len() > EMAIL_MAX_LENGTH {
+            return Err(vec![FieldError {
+                field: "email",
+                message: format!("Email must be at most {} characters", EMAIL_MAX_LENGTH),
+            }]);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PasswordResetConfirmRequest {
+    token: String,
+    new_password: SecretString,
+}
+
+impl Validate for PasswordResetConfirmRequest {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if self.token.len() > TOKEN_LENGTH {
+            errors.push(FieldError {
+                field: "token",
+                message: "Reset token is malformed".into(),
+            });
+        }
+        check_field(
+            &mut errors,
+            "new_password",
+            validate_password_strength(self.new_password.expose_secret()),
+        );
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PasswordResetResponse {
+    success: bool,
+}
+
 // ============================================================================
 // HTTP Handlers
 // ============================================================================
 
 /// Register a new user
-#[instrument(skip(user_service, payload))]
+#[instrument(skip(user_service, audit_logger, payload))]
 async fn register(
     State(user_service): State<Arc<UserService>>,
-    Json(payload): Json<RegisterRequest>,
+    State(audit_logger): State<Arc<AuditLogger>>,
+    State(envelope): State<ResponseEnvelope>,
+    State(HideRegistrationConflicts(hide_conflicts)): State<HideRegistrationConflicts>,
+    _: SecureTransport,
+    ClientIp(ip): ClientIp,
+    ValidatedJsonOrForm(payload): ValidatedJsonOrForm<RegisterRequest>,
 ) -> AppResult<impl IntoResponse> {
     info!("Register endpoint called");
 
-    user_service
-        .register_user(&payload.username, &payload.email, &payload.password)
-        .await?;
+    let registered = user_service
+        .register_user_with_invite(
+            &payload.username,
+            &payload.email,
+            &payload.password,
+            payload.invite_code.as_deref(),
+        )
+        .await;
+    match registered {
+        Ok(user_id) => {
+            audit_logger
+                .record(Some(user_id), AuditAction::Register, Some(user_id), ip)
+                .await;
+        }
+        // The password was hashed before the insert failed, so timing doesn't give it away either
+        Err(AppError::Conflict(_)) if hide_conflicts => {
+            info!("Duplicate registration answered as success");
+        }
+        Err(e) => return Err(e),
+    }
 
     Ok((
         StatusCode::CREATED,
-        Json(RegisterResponse { success: true }),
+        ApiResponse::new(envelope, RegisterResponse { success: true }),
+    ))
+}
+
+/// Log in with a username and password
+#[instrument(skip(user_service, session_service, audit_logger, headers, payload))]
+// One argument per extractor, grouping them would only move the list into a struct
+#[allow(clippy::too_many_arguments)]
+async fn login(
+    State(user_service): State<Arc<UserService>>,
+    State(session_service): State<Arc<SessionService>>,
+    State(audit_logger): State<Arc<AuditLogger>>,
+    State(envelope): State<ResponseEnvelope>,
+    _: SecureTransport,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    ValidatedJson(payload): ValidatedJson<LoginRequest>,
+) -> AppResult<impl IntoResponse> {
+    info!("Login endpoint called");
+
+    let user = user_service
+        .login(&payload.username, &payload.password)
+        .await?;
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    let tokens = session_service.start_session(&user, user_agent).await?;
+    audit_logger
+        .record(Some(user.id), AuditAction::Login, Some(user.id), ip)
+        .await;
+
+    Ok(ApiResponse::new(
+        envelope,
+        LoginResponse {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            token_type: "Bearer",
+            user: user.into(),
+        },
+    ))
+}
+
+/// Exchange a refresh token for a new access and refresh token
+#[instrument(skip(session_service, payload))]
+async fn refresh(
+    State(session_service): State<Arc<SessionService>>,
+    State(envelope): State<ResponseEnvelope>,
+    _: SecureTransport,
+    ValidatedJson(payload): ValidatedJson<RefreshRequest>,
+) -> AppResult<impl IntoResponse> {
+    info!("Refresh endpoint called");
+
+    let tokens = session_service.refresh(&payload.refresh_token).await?;
+
+    Ok(ApiResponse::new(
+        envelope,
+        RefreshResponse {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            token_type: "Bearer",
+        },
+    ))
+}
+
+/// Profiles are per user, so only the client may cache them, and must revalidate every time
+const PROFILE_CACHE_CONTROL: &str = "private, no-cache";
+
+/// Changes whenever the user row does, so clients can revalidate with `If-None-Match`
+fn user_etag(user: &User) -> String {
+    format!(
+        "\"{}-{:x}\"",
+        user.id.simple(),
+        user.updated_at.and_utc().timestamp_micros()
+    )
+}
+
+/// Whether any entity tag in `If-None-Match` matches `etag`, using weak comparison
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Profile of the user the access token belongs to
+#[instrument(skip(user_service, auth_user, headers), fields(user_id = %auth_user.id))]
+async fn me(
+    auth_user: AuthUser,
+    State(user_service): State<Arc<UserService>>,
+    State(envelope): State<ResponseEnvelope>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    info!("Me endpoint called");
+
+    let user = user_service.get_user(&auth_user.id).await?;
+    let etag = user_etag(&user);
+    let cache_headers = [
+        (header::CACHE_CONTROL, PROFILE_CACHE_CONTROL.to_string()),
+        (header::ETAG, etag.clone()),
+    ];
+
+    if if_none_match(&headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+
+    Ok((
+        cache_headers,
+        ApiResponse::new(envelope, UserResponse::from(user)),
+    )
+        .into_response())
+}
+
+/// A page of the current user's live sessions, newest first
+/// The number of sessions across every page is sent in `X-Total-Count`
+#[instrument(skip(session_service, auth_user), fields(user_id = %auth_user.id))]
+async fn list_sessions(
+    auth_user: AuthUser,
+    State(session_service): State<Arc<SessionService>>,
+    State(envelope): State<ResponseEnvelope>,
+    pagination: Pagination,
+) -> AppResult<impl IntoResponse> {
+    info!("List sessions endpoint called");
+
+    let (sessions, total) = session_service
+        .list_sessions_page(auth_user.id, pagination.limit, pagination.offset)
+        .await?;
+
+    Ok((
+        [(TOTAL_COUNT_HEADER, total.to_string())],
+        ApiResponse::new(
+            envelope,
+            sessions
+                .into_iter()
+                .map(SessionResponse::from)
+                .collect::<Vec<_>>(),
+        ),
+    ))
+}
+
+/// Sign out one of the current user's sessions
+#[instrument(skip(session_service, auth_user), fields(user_id = %auth_user.id))]
+async fn revoke_session(
+    auth_user: AuthUser,
+    State(session_service): State<Arc<SessionService>>,
+    Path(session_id): Path<Uuid>,
+) -> AppResult<impl IntoResponse> {
+    info!("Revoke session endpoint called");
+
+    session_service
+        .revoke_session(auth_user.id, session_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Confirm a user's email address with a verification token
+#[instrument(skip(email_verification_service, query))]
+async fn verify_email(
+    State(email_verification_service): State<Arc<EmailVerificationService>>,
+    State(envelope): State<ResponseEnvelope>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> AppResult<impl IntoResponse> {
+    info!("Verify email endpoint called");
+
+    email_verification_service
+        .verify_email(&query.token)
+        .await?;
+
+    Ok(ApiResponse::new(
+        envelope,
+        VerifyEmailResponse { success: true },
+    ))
+}
+
+/// Start a password reset
+/// Always succeeds so the response doesn't reveal whether the email is registered
+#[instrument(skip(password_reset_service, payload))]
+async fn request_password_reset(
+    State(password_reset_service): State<Arc<PasswordResetService>>,
+    State(envelope): State<ResponseEnvelope>,
+    ValidatedJson(payload): ValidatedJson<PasswordResetRequest>,
+) -> AppResult<impl IntoResponse> {
+    info!("Password reset request endpoint called");
+
+    password_reset_service.request_reset(&payload.email).await?;
+
+    Ok(ApiResponse::new(
+        envelope,
+        PasswordResetResponse { success: true },
+    ))
+}
+
+/// Finish a password reset with a token and a new password
+#[instrument(skip(password_reset_service, audit_logger, payload))]
+async fn confirm_password_reset(
+    State(password_reset_service): State<Arc<PasswordResetService>>,
+    State(audit_logger): State<Arc<AuditLogger>>,
+    State(envelope): State<ResponseEnvelope>,
+    _: SecureTransport,
+    ClientIp(ip): ClientIp,
+    ValidatedJson(payload): ValidatedJson<PasswordResetConfirmRequest>,
+) -> AppResult<impl IntoResponse> {
+    info!("Password reset confirm endpoint called");
+
+    let user_id = password_reset_service
+        .confirm_reset(&payload.token, &payload.new_password)
+        .await?;
+    // Whoever held the token acted without signing in, so there is no actor
+    audit_logger
+        .record(None, AuditAction::PasswordReset, Some(user_id), ip)
+        .await;
+
+    Ok(ApiResponse::new(
+        envelope,
+        PasswordResetResponse { success: true },
     ))
 }
 
@@ -52,5 +531,14 @@ async fn register(
 // ============================================================================
 
 pub fn user_router() -> Router<AppState> {
-    Router::new().route("/register", post(register))
+    Router::new()
+        .route("/register", post(register))
+        .route("/login", post(login))
+        .route("/refresh", post(refresh))
+        .route("/me", get(me))
+        .route("/me/sessions", get(list_sessions))
+        .route("/me/sessions/{id}", delete(revoke_session))
+        .route("/verify", get(verify_email))
+        .route("/password-reset/request", post(request_password_reset))
+        .route("/password-reset/confirm", post(confirm_password_reset))
 }
@@ -0,0 +1,299 @@
+use axum::{
+    Form, Json,
+    extract::{
+        FromRequest, Request,
+        rejection::{FormRejection, JsonRejection},
+    },
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+
+pub use crate::application::app_error::FieldError;
+use crate::{
+    application::app_error::{AppError, AppResult},
+    web::error_response::{ErrorBody, error_response},
+};
+
+// ============================================================================
+// Validate Trait
+// ============================================================================
+
+/// Request bodies that check their own fields before reaching a handler
+pub trait Validate {
+    /// Return every invalid field rather than stopping at the first
+    fn validate(&self) -> Result<(), Vec<FieldError>>;
+}
+
+/// Collect the message of a failed domain check as a `FieldError`
+pub fn check_field<T>(errors: &mut Vec<FieldError>, field: &'static str, result: AppResult<T>) {
+    match result {
+        Ok(_) => {}
+        Err(AppError::Validation(message)) => errors.push(FieldError { field, message }),
+        Err(other) => errors.push(FieldError {
+            field,
+            message: other.to_string(),
+        }),
+    }
+}
+
+// ============================================================================
+// Validated JSON Extractor
+// ============================================================================
+
+/// JSON body that has passed `Validate`, so handlers can't forget to validate
+#[derive(Debug, Clone)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(json_rejected)?;
+
+        value.validate().map_err(validation_failed)?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// Like `ValidatedJson`, but also accepts `application/x-www-form-urlencoded` for older clients
+/// Any other content type is treated as JSON, so its rejection still names JSON
+#[derive(Debug, Clone)]
+pub struct ValidatedJsonOrForm<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJsonOrForm<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_form = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|media| {
+                media
+                    .trim()
+                    .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+            });
+
+        let value = if is_form {
+            let Form(value) = Form::<T>::from_request(request, state)
+                .await
+                .map_err(form_rejected)?;
+            value
+        } else {
+            let Json(value) = Json::<T>::from_request(request, state)
+                .await
+                .map_err(json_rejected)?;
+            value
+        };
+
+        value.validate().map_err(validation_failed)?;
+
+        Ok(ValidatedJsonOrForm(value))
+    }
+}
+
+fn validation_failed(fields: Vec<FieldError>) -> Response {
+    AppError::validations(fields).into_response()
+}
+
+/// Keep axum's status, such as 415 or 422, but answer in the usual error body
+fn json_rejected(rejection: JsonRejection) -> Response {
+    error_response(
+        rejection.status(),
+        ErrorBody {
+            code: "invalid_body",
+            error: rejection.body_text(),
+            fields: Vec::new(),
+        },
+    )
+}
+
+fn form_rejected(rejection: FormRejection) -> Response {
+    error_response(
+        rejection.status(),
+        ErrorBody {
+            code: "invalid_body",
+            error: rejection.body_text(),
+            fields: Vec::new(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, body::to_bytes, http::StatusCode, routing::post};
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    #[derive(Deserialize)]
+    struct Payload {
+        name: String,
+        age: u32,
+    }
+
+    impl Validate for Payload {
+        fn validate(&self) -> Result<(), Vec<FieldError>> {
+            let mut errors = Vec::new();
+            if self.name.is_empty() {
+                errors.push(FieldError {
+                    field: "name",
+                    message: "Name must not be empty".into(),
+                });
+            }
+            if self.age > 150 {
+                errors.push(FieldError {
+                    field: "age",
+                    message: "Age is out of range".into(),
+                });
+            }
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        }
+    }
+
+    async fn handler(ValidatedJson(payload): ValidatedJson<Payload>) -> String {
+        payload.name
+    }
+
+    async fn json_or_form_handler(
+        ValidatedJsonOrForm(payload): ValidatedJsonOrForm<Payload>,
+    ) -> String {
+        format!("{} {}", payload.name, payload.age)
+    }
+
+    async fn post_payload(body: &str) -> (StatusCode, String) {
+        post_to(
+            Router::new().route("/", post(handler)),
+            "application/json",
+            body,
+        )
+        .await
+    }
+
+    async fn post_to(router: Router, content_type: &str, body: &str) -> (StatusCode, String) {
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/")
+                    .header(header::CONTENT_TYPE, content_type)
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_valid_payload_reaches_handler() {
+        let (status, body) = post_payload(r#"{"name":"bob","age":30}"#).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "bob");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_is_a_structured_error() {
+        let (status, body) = post_payload(r#"{"name":"bob","#).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(
+            body["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Failed to parse the request body as JSON"),
+            "{}",
+            body
+        );
+
+        let (status, body) = post_payload(r#"{"name":"bob"}"#).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(body["error"].as_str().unwrap().contains("age"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_payload_lists_every_field() {
+        let (status, body) = post_payload(r#"{"name":"","age":200}"#).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            r#"{"code":"validation_failed","error":"Validation failed","fields":[{"field":"name","message":"Name must not be empty"},{"field":"age","message":"Age is out of range"}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_or_form_accepts_both_encodings() {
+        let router = Router::new().route("/", post(json_or_form_handler));
+
+        let (status, body) = post_to(
+            router.clone(),
+            "application/json",
+            r#"{"name":"bob","age":30}"#,
+        )
+        .await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "bob 30"));
+
+        let (status, body) = post_to(
+            router.clone(),
+            "application/x-www-form-urlencoded; charset=utf-8",
+            "name=bob+smith&age=31",
+        )
+        .await;
+        assert_eq!((status, body.as_str()), (StatusCode::OK, "bob smith 31"));
+
+        let (status, _) = post_to(
+            router.clone(),
+            "application/x-www-form-urlencoded",
+            "name=&age=31",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = post_to(router, "text/plain", "name=bob&age=31").await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[test]
+    fn test_check_field_collects_validation_message() {
+        let mut errors = Vec::new();
+
+        check_field(&mut errors, "ok", Ok::<_, AppError>(()));
+        check_field::<()>(
+            &mut errors,
+            "email",
+            Err(AppError::Validation("Email is malformed".into())),
+        );
+
+        assert_eq!(
+            errors,
+            vec![FieldError {
+                field: "email",
+                message: "Email is malformed".into(),
+            }]
+        );
+    }
+}